@@ -0,0 +1,58 @@
+//! Measures the overhead of `Ioctl::ioctl` compared to calling `libc::ioctl` directly.
+//!
+//! Run with `cargo bench`. Both variants perform `FIONREAD` on a pipe, which is about as cheap as
+//! an `ioctl` gets, so any overhead added by the wrapper would show up here.
+
+use std::{
+    ffi::c_int,
+    hint::black_box,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+    time::{Duration, Instant},
+};
+
+use uoctl::Ioctl;
+
+const FIONREAD: Ioctl<*mut c_int> = Ioctl::from_raw(libc::FIONREAD as _);
+
+const ITERATIONS: u32 = 1_000_000;
+const ROUNDS: usize = 5;
+
+fn pipe() -> (OwnedFd, OwnedFd) {
+    let mut fds = [0; 2];
+    assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+    unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) }
+}
+
+/// Runs `f` `ITERATIONS` times per round, and returns the fastest round's time per iteration.
+fn bench(mut f: impl FnMut() -> io::Result<c_int>) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..ITERATIONS {
+                black_box(f().unwrap());
+            }
+            start.elapsed() / ITERATIONS
+        })
+        .min()
+        .unwrap()
+}
+
+fn main() {
+    let (read, _write) = pipe();
+    let fd = read.as_raw_fd();
+    let mut bytes = 0;
+
+    let raw = bench(|| {
+        let res = unsafe { libc::ioctl(black_box(fd), libc::FIONREAD, &raw mut bytes) };
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    });
+    let wrapped = bench(|| unsafe { FIONREAD.ioctl(&black_box(fd), &mut bytes) });
+
+    println!("libc::ioctl:  {raw:?}/iter");
+    println!("Ioctl::ioctl: {wrapped:?}/iter");
+}
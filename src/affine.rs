@@ -0,0 +1,159 @@
+//! File descriptors that must only be used on a single thread.
+
+#[cfg(debug_assertions)]
+use std::thread::{self, ThreadId};
+use std::{
+    fmt,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd},
+};
+
+/// A file descriptor that may only be used by the thread that created it.
+///
+/// Some drivers require all `ioctl`s on a file descriptor to be performed by the thread that
+/// opened it, and misbehave in subtle ways otherwise.
+/// [`ThreadAffineFd`] records the thread it was created on, and when debug assertions are enabled,
+/// panics when the file descriptor is accessed (via [`AsRawFd`] or [`AsFd`]) from any other
+/// thread.
+/// Since all `ioctl` methods obtain the file descriptor this way, this catches `ioctl`s issued from
+/// the wrong thread before they reach the driver.
+///
+/// When debug assertions are disabled, [`ThreadAffineFd`] performs no checks and has the same size
+/// as the wrapped file descriptor.
+///
+/// # Example
+///
+/// ```
+/// use std::{ffi::c_int, os::unix::net::UnixStream, thread};
+/// use uoctl::*;
+///
+/// const FIONREAD: Ioctl<*mut c_int> = Ioctl::from_raw(libc::FIONREAD as _);
+///
+/// let (sock, _peer) = UnixStream::pair()?;
+/// let fd = ThreadAffineFd::new(sock);
+///
+/// let mut bytes = 0;
+/// unsafe { FIONREAD.ioctl(&fd, &mut bytes)? };
+///
+/// // Performing the `ioctl` on another thread panics when debug assertions are enabled.
+/// let res = thread::scope(|s| {
+///     s.spawn(|| unsafe { FIONREAD.ioctl(&fd, &mut 0) }).join()
+/// });
+/// assert_eq!(res.is_err(), cfg!(debug_assertions));
+/// # std::io::Result::Ok(())
+/// ```
+pub struct ThreadAffineFd<F = OwnedFd> {
+    fd: F,
+    #[cfg(debug_assertions)]
+    owner: ThreadId,
+}
+
+impl<F: AsRawFd> ThreadAffineFd<F> {
+    /// Wraps `fd`, binding it to the calling thread.
+    pub fn new(fd: F) -> Self {
+        Self {
+            fd,
+            #[cfg(debug_assertions)]
+            owner: thread::current().id(),
+        }
+    }
+
+    /// Binds the file descriptor to the calling thread.
+    ///
+    /// This can be used to hand the file descriptor off to another thread, after ensuring that
+    /// the previous thread no longer uses it.
+    pub fn rebind(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            self.owner = thread::current().id();
+        }
+    }
+
+    /// Returns a reference to the wrapped file descriptor, without checking the calling thread.
+    pub fn get_ref(&self) -> &F {
+        &self.fd
+    }
+
+    /// Returns the wrapped file descriptor.
+    pub fn into_inner(self) -> F {
+        self.fd
+    }
+
+    #[inline]
+    fn check_thread(&self) {
+        #[cfg(debug_assertions)]
+        {
+            let current = thread::current().id();
+            assert_eq!(
+                current, self.owner,
+                "`ThreadAffineFd` used on a thread other than its owning thread",
+            );
+        }
+    }
+}
+
+impl<F: AsRawFd> AsRawFd for ThreadAffineFd<F> {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.check_thread();
+        self.fd.as_raw_fd()
+    }
+}
+
+impl<F: AsRawFd + AsFd> AsFd for ThreadAffineFd<F> {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.check_thread();
+        self.fd.as_fd()
+    }
+}
+
+impl<F: fmt::Debug> fmt::Debug for ThreadAffineFd<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("ThreadAffineFd");
+        s.field("fd", &self.fd);
+        #[cfg(debug_assertions)]
+        s.field("owner", &self.owner);
+        s.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{os::unix::net::UnixStream, thread};
+
+    use crate::tests::FIONREAD;
+
+    use super::*;
+
+    #[test]
+    fn same_thread() {
+        let (sock, _peer) = UnixStream::pair().unwrap();
+        let fd = ThreadAffineFd::new(sock);
+        let mut bytes = 0;
+        unsafe { FIONREAD.ioctl(&fd, &mut bytes).unwrap() };
+        assert_eq!(bytes, 0);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn cross_thread_panics() {
+        let (sock, _peer) = UnixStream::pair().unwrap();
+        let fd = ThreadAffineFd::new(sock);
+        let res = thread::scope(|s| s.spawn(|| unsafe { FIONREAD.ioctl(&fd, &mut 0) }).join());
+        let msg = *res.unwrap_err().downcast::<String>().unwrap();
+        assert!(msg.contains("owning thread"), "{msg}");
+    }
+
+    #[test]
+    fn rebind() {
+        let (sock, _peer) = UnixStream::pair().unwrap();
+        let fd = ThreadAffineFd::new(sock);
+        thread::spawn(move || {
+            let mut fd = fd;
+            fd.rebind();
+            unsafe { FIONREAD.ioctl(&fd, &mut 0).unwrap() };
+        })
+        .join()
+        .unwrap();
+    }
+}
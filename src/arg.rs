@@ -0,0 +1,764 @@
+//! Helpers for `ioctl`s with non-trivial argument conventions.
+
+use std::{
+    ffi::c_int,
+    fmt, io, mem,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+};
+
+use crate::Ioctl;
+
+/// An `ioctl` that multiplexes several operations, selected by a field in its argument.
+///
+/// Some drivers route many distinct operations through a single `ioctl` request code, and use a
+/// "sub-command" field in the argument `struct` to select the operation to perform.
+/// The sub-command is *not* part of the request code, so the operations can't be modeled as
+/// separate [`Ioctl`]s.
+///
+/// [`SubcommandIoctl`] pairs the [`Ioctl`] with a function that stores the sub-command in the
+/// argument, so that every call site has to specify the operation it wants to perform, and can't
+/// accidentally reuse a stale sub-command from a previous call.
+///
+/// # Example
+///
+/// From `linux/tiocl.h`, `TIOCLINUX` selects its operation via the first byte of its argument:
+///
+/// ```c
+/// #define TIOCL_SETSEL	2	/* set a selection */
+/// ...
+/// #define TIOCL_GETSHIFTSTATE	6	/* write shift state */
+/// #define TIOCL_GETMOUSEREPORTING	7	/* write whether mouse event reporting is on */
+/// ...
+/// #define TIOCL_GETKMSGREDIRECT	17	/* get the vt the kernel messages are restricted to */
+/// ```
+///
+/// ```no_run
+/// use std::fs::File;
+/// use uoctl::*;
+///
+/// const TIOCLINUX: Ioctl<*mut [u8; 1]> = Ioctl::from_raw(0x541C);
+/// const TIOCL_GETSHIFTSTATE: u8 = 6;
+/// const TIOCL_GETKMSGREDIRECT: u8 = 17;
+///
+/// const TIOCLINUX_SUBCOMMAND: SubcommandIoctl<[u8; 1], u8> =
+///     SubcommandIoctl::new(TIOCLINUX, |arg, subcommand| arg[0] = subcommand);
+///
+/// let tty = File::open("/dev/tty0")?;
+///
+/// // The kernel overwrites the sub-command byte with the result.
+/// let mut arg = [0];
+/// unsafe { TIOCLINUX_SUBCOMMAND.ioctl(&tty, TIOCL_GETSHIFTSTATE, &mut arg)? };
+/// println!("shift state: {:#x}", arg[0]);
+///
+/// unsafe { TIOCLINUX_SUBCOMMAND.ioctl(&tty, TIOCL_GETKMSGREDIRECT, &mut arg)? };
+/// println!("kernel messages go to VT {}", arg[0]);
+/// # std::io::Result::Ok(())
+/// ```
+pub struct SubcommandIoctl<T, S = u32> {
+    ioctl: Ioctl<*mut T>,
+    set_subcommand: fn(&mut T, S),
+}
+
+impl<T, S> Copy for SubcommandIoctl<T, S> {}
+impl<T, S> Clone for SubcommandIoctl<T, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, S> fmt::Debug for SubcommandIoctl<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SubcommandIoctl")
+            .field("request", &format_args!("{:#x}", self.ioctl.request()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, S> SubcommandIoctl<T, S> {
+    /// Creates a [`SubcommandIoctl`] from the multiplexed [`Ioctl`] and a function that stores the
+    /// sub-command in the argument.
+    pub const fn new(ioctl: Ioctl<*mut T>, set_subcommand: fn(&mut T, S)) -> Self {
+        Self {
+            ioctl,
+            set_subcommand,
+        }
+    }
+
+    /// Returns the underlying [`Ioctl`], which does not set the sub-command.
+    pub const fn inner(&self) -> Ioctl<*mut T> {
+        self.ioctl
+    }
+
+    /// Stores `subcommand` in `arg`, then performs the `ioctl`.
+    ///
+    /// Returns the value returned by the `ioctl(2)` invocation, or an I/O error if the call failed.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` *and the selected
+    /// sub-command* are met, that `T` denotes the correct argument type, and that `fd` is valid
+    /// (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl(&self, fd: &impl AsRawFd, subcommand: S, arg: &mut T) -> io::Result<c_int> {
+        (self.set_subcommand)(arg, subcommand);
+        unsafe { self.ioctl.ioctl(fd, arg) }
+    }
+}
+
+/// An `ioctl` whose argument `struct` is extensible, and stores its own size.
+///
+/// Newer kernel interfaces often make their argument `struct`s extensible by putting a `size` (or
+/// version) field in them, which the kernel uses to determine which version of the `struct` it
+/// was passed.
+/// For these `ioctl`s, the request code only encodes the size of the original (base) `struct`,
+/// and stays the same when the `struct` is extended.
+///
+/// [`VersionedIoctl`] is created with [`Ioctl::with_versioned_arg`] from the [`Ioctl`] declared
+/// with the base `struct`, and takes the extended `struct` `Ext` as its argument.
+/// Before performing the `ioctl`, it stores `size_of::<Ext>()` in the argument, so that the size
+/// field can't get out of sync with the type that is actually passed.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use uoctl::*;
+///
+/// // The original version of the `struct`, as used in the `ioctl` definition.
+/// #[repr(C)]
+/// struct FooArgsV0 {
+///     size: u32,
+///     flags: u32,
+/// }
+///
+/// // A later version that added a field.
+/// #[repr(C)]
+/// #[derive(Default)]
+/// struct FooArgsV1 {
+///     size: u32,
+///     flags: u32,
+///     timeout_ns: u64,
+/// }
+///
+/// const FOO_IOCTL_CONFIGURE: VersionedIoctl<FooArgsV1> =
+///     _IOWR::<FooArgsV0>(b'F', 0x01).with_versioned_arg(|arg, size| arg.size = size);
+///
+/// let dev = File::open("/dev/foo")?;
+/// let mut args = FooArgsV1 {
+///     timeout_ns: 1_000_000,
+///     ..Default::default()
+/// };
+/// unsafe { FOO_IOCTL_CONFIGURE.ioctl(&dev, &mut args)? };
+/// assert_eq!(args.size, 16);
+/// # std::io::Result::Ok(())
+/// ```
+pub struct VersionedIoctl<Ext> {
+    ioctl: Ioctl<*mut Ext>,
+    set_size: fn(&mut Ext, u32),
+}
+
+impl<Ext> Copy for VersionedIoctl<Ext> {}
+impl<Ext> Clone for VersionedIoctl<Ext> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Ext> fmt::Debug for VersionedIoctl<Ext> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VersionedIoctl")
+            .field("request", &format_args!("{:#x}", self.ioctl.request()))
+            .field("size", &size_of::<Ext>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<Ext> VersionedIoctl<Ext> {
+    pub(crate) const fn new(ioctl: Ioctl<*mut Ext>, set_size: fn(&mut Ext, u32)) -> Self {
+        Self { ioctl, set_size }
+    }
+
+    /// Returns the underlying [`Ioctl`], which does not set the size field.
+    pub const fn inner(&self) -> Ioctl<*mut Ext> {
+        self.ioctl
+    }
+
+    /// Stores the size of `Ext` in `arg`, then performs the `ioctl`.
+    ///
+    /// Returns the value returned by the `ioctl(2)` invocation, or an I/O error if the call failed.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `Ext`
+    /// denotes a version of the argument `struct` that the kernel understands (or safely rejects),
+    /// and that `fd` is valid (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl(&self, fd: &impl AsRawFd, arg: &mut Ext) -> io::Result<c_int> {
+        (self.set_size)(arg, size_of::<Ext>() as u32);
+        unsafe { self.ioctl.ioctl(fd, arg) }
+    }
+}
+
+/// An `ioctl` argument `struct` containing a C union, whose active field is selected by a
+/// discriminant ("tag") field.
+///
+/// This is implemented for the argument `struct`, and used together with [`UnionVariant`] and
+/// [`IoctlUnion`] to access the union safely.
+///
+/// # Safety
+///
+/// An all-zero bit pattern has to be a valid value of `Self`.
+pub unsafe trait TaggedUnion {
+    /// The type of the discriminant field.
+    type Tag: Copy;
+
+    /// Returns the value of the discriminant field.
+    fn tag(&self) -> Self::Tag;
+
+    /// Sets the discriminant field to `tag`.
+    fn set_tag(&mut self, tag: Self::Tag);
+}
+
+/// A field of the union in the [`TaggedUnion`] `T`.
+///
+/// This is typically implemented for the type of the union field, and describes which values of
+/// the discriminant select it.
+///
+/// # Safety
+///
+/// [`UnionVariant::field`] has to return a pointer to a field of the union in `T` that has type
+/// `Self` and is properly aligned (so `T` must not be `#[repr(packed)]`).
+/// Whenever [`UnionVariant::accepts`] returns `true` for the discriminant of a `T`, that field
+/// has to contain a valid `Self` (in particular, it must be valid for any value the kernel may
+/// store in it when the discriminant has that value).
+pub unsafe trait UnionVariant<T: TaggedUnion> {
+    /// Returns whether `tag` selects this field of the union.
+    fn accepts(tag: T::Tag) -> bool;
+
+    /// Returns a pointer to this field of the union in `*arg`.
+    ///
+    /// # Safety
+    ///
+    /// `arg` has to point to a valid `T`.
+    unsafe fn field(arg: *mut T) -> *mut Self;
+}
+
+/// An `ioctl` argument `struct` with a tagged union that can be accessed safely.
+///
+/// Reading a union field is `unsafe` in Rust, since nothing guarantees that the field contains a
+/// valid value.
+/// Kernel APIs typically pair each union with a discriminant field that determines which union
+/// field is in use, and [`IoctlUnion`] uses that field to guard access to the union:
+///
+/// - [`IoctlUnion::new`] creates a zeroed `T`, sets the discriminant, and stores a value in the
+///   corresponding union field.
+/// - [`IoctlUnion::get`] and [`IoctlUnion::get_mut`] return the requested union field only if the
+///   discriminant selects it.
+///
+/// The argument `struct` has to implement [`TaggedUnion`], and each union field has to implement
+/// [`UnionVariant`].
+///
+/// # Example
+///
+/// V4L2's `v4l2_format` contains a union whose active field depends on the `type` field.
+///
+/// From `linux/videodev2.h` (simplified):
+///
+/// ```c
+/// struct v4l2_format {
+/// 	__u32	 type;
+/// 	union {
+/// 		struct v4l2_pix_format		pix;     /* V4L2_BUF_TYPE_VIDEO_CAPTURE */
+/// 		...
+/// 		__u8	raw_data[200];                   /* user-defined */
+/// 	} fmt;
+/// };
+/// ...
+/// #define VIDIOC_G_FMT		_IOWR('V',  4, struct v4l2_format)
+/// ```
+///
+/// ```no_run
+/// use std::fs::File;
+/// use uoctl::*;
+///
+/// #[repr(C)]
+/// #[derive(Clone, Copy, Default)]
+/// struct PixFormat {
+///     width: u32,
+///     height: u32,
+///     pixelformat: u32,
+///     field: u32,
+///     bytesperline: u32,
+///     sizeimage: u32,
+///     colorspace: u32,
+///     priv_: u32,
+///     flags: u32,
+///     enc: u32,
+///     quantization: u32,
+///     xfer_func: u32,
+/// }
+///
+/// #[repr(C)]
+/// union FormatUnion {
+///     pix: PixFormat,
+///     raw_data: [u8; 200],
+///     // `struct v4l2_window` contains pointers.
+///     _align: [*mut u8; 0],
+/// }
+///
+/// #[repr(C)]
+/// struct Format {
+///     ty: u32,
+///     fmt: FormatUnion,
+/// }
+///
+/// const V4L2_BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+/// const V4L2_BUF_TYPE_VIDEO_OUTPUT: u32 = 2;
+///
+/// // SAFETY: `Format` only contains integers, so all-zero is a valid value.
+/// unsafe impl TaggedUnion for Format {
+///     type Tag = u32;
+///     fn tag(&self) -> u32 {
+///         self.ty
+///     }
+///     fn set_tag(&mut self, tag: u32) {
+///         self.ty = tag;
+///     }
+/// }
+///
+/// // SAFETY: `pix` is used by the single-planar video buffer types, and only contains integers.
+/// unsafe impl UnionVariant<Format> for PixFormat {
+///     fn accepts(tag: u32) -> bool {
+///         matches!(tag, V4L2_BUF_TYPE_VIDEO_CAPTURE | V4L2_BUF_TYPE_VIDEO_OUTPUT)
+///     }
+///     unsafe fn field(arg: *mut Format) -> *mut Self {
+///         unsafe { &raw mut (*arg).fmt.pix }
+///     }
+/// }
+///
+/// const VIDIOC_G_FMT: Ioctl<*mut Format> = _IOWR(b'V', 4);
+///
+/// let dev = File::open("/dev/video0")?;
+///
+/// let mut format = IoctlUnion::new(V4L2_BUF_TYPE_VIDEO_CAPTURE, PixFormat::default());
+/// unsafe { VIDIOC_G_FMT.ioctl(&dev, format.as_mut_ptr())? };
+///
+/// let pix = format.get::<PixFormat>().unwrap();
+/// println!("{}x{}", pix.width, pix.height);
+/// # std::io::Result::Ok(())
+/// ```
+#[repr(transparent)]
+pub struct IoctlUnion<T> {
+    inner: T,
+}
+
+impl<T: TaggedUnion> IoctlUnion<T> {
+    /// Creates a zeroed `T` whose discriminant is set to `tag`, and whose union field `V` is set to
+    /// `value`.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `tag` doesn't select the union field `V`.
+    pub fn new<V: UnionVariant<T>>(tag: T::Tag, value: V) -> Self {
+        assert!(
+            V::accepts(tag),
+            "the discriminant does not select this union field"
+        );
+        // SAFETY: `TaggedUnion` requires an all-zero `T` to be valid.
+        let mut inner: T = unsafe { mem::zeroed() };
+        inner.set_tag(tag);
+        // SAFETY: `V::field` points to an aligned field of type `V` in `inner`.
+        unsafe { V::field(&mut inner).write(value) };
+        Self { inner }
+    }
+
+    /// Returns the union field `V`, or [`None`] if the discriminant doesn't select it.
+    pub fn get<V: UnionVariant<T>>(&self) -> Option<&V> {
+        if V::accepts(self.inner.tag()) {
+            // SAFETY: `UnionVariant` guarantees that the field contains a valid `V` when the
+            // discriminant selects it. `field` only needs a `*mut` pointer for computing the
+            // address; nothing is written through it.
+            Some(unsafe { &*V::field(&self.inner as *const T as *mut T) })
+        } else {
+            None
+        }
+    }
+
+    /// Returns a mutable reference to the union field `V`, or [`None`] if the discriminant
+    /// doesn't select it.
+    pub fn get_mut<V: UnionVariant<T>>(&mut self) -> Option<&mut V> {
+        if V::accepts(self.inner.tag()) {
+            // SAFETY: see `get`.
+            Some(unsafe { &mut *V::field(&mut self.inner) })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the discriminant of the argument.
+    pub fn tag(&self) -> T::Tag {
+        self.inner.tag()
+    }
+
+    /// Returns a pointer to the argument, for passing it to an `ioctl`.
+    ///
+    /// The kernel may change the discriminant as well as the union; [`IoctlUnion::get`] accounts
+    /// for that.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        &mut self.inner
+    }
+
+    /// Returns a reference to the argument.
+    pub fn as_inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns the argument.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+/// Finds the largest argument size accepted by a driver that versions its `struct`s by size.
+///
+/// Some drivers extend their `ioctl` argument `struct`s over time and encode the `struct`
+/// version solely in the argument size (and thus the request code).
+/// Kernels that don't know about a newer, larger version typically reject it with `ENOTTY`
+/// (since they don't recognize the request code), `EINVAL`, or `E2BIG`.
+///
+/// This function tries each of the candidate `sizes`, largest first, by performing the `ioctl`
+/// returned by `make_ioctl(size)` with a zeroed buffer of `size` bytes.
+/// It returns the first size the driver accepts, or `None` if it rejects all of them.
+///
+/// # Errors
+///
+/// Errors other than `ENOTTY`, `EINVAL`, and `E2BIG` are returned as-is.
+///
+/// # Safety
+///
+/// This function performs arbitrary `ioctl`s on an arbitrary file descriptor.
+/// The caller has to ensure that the `ioctl`s returned by `make_ioctl` are safe to perform with a
+/// zeroed buffer of the given size (in particular, they must not have any side effects that the
+/// caller isn't prepared for), and that `fd` is valid (open) and belongs to the driver it
+/// expects.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use uoctl::*;
+///
+/// const SIZE_V1: usize = 16;
+/// const SIZE_V2: usize = 24;
+/// const SIZE_V3: usize = 40;
+///
+/// let dev = File::open("/dev/some-device")?;
+/// let size = unsafe {
+///     negotiate_version(
+///         &dev,
+///         |size| _IOC(_IOC_READ_WRITE, b'z', 0x01, size),
+///         &[SIZE_V1, SIZE_V2, SIZE_V3],
+///     )?
+/// };
+/// match size {
+///     Some(SIZE_V3) => println!("driver supports the latest version"),
+///     Some(size) => println!("falling back to the {size}-byte version"),
+///     None => println!("driver does not support this `ioctl`"),
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+pub unsafe fn negotiate_version(
+    fd: &impl AsRawFd,
+    make_ioctl: impl Fn(usize) -> Ioctl<*mut u8>,
+    sizes: &[usize],
+) -> io::Result<Option<usize>> {
+    let mut sizes = sizes.to_vec();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.dedup();
+
+    let mut buf = vec![0u8; sizes.first().copied().unwrap_or(0)];
+    for size in sizes {
+        let buf = &mut buf[..size];
+        buf.fill(0);
+        match unsafe { make_ioctl(size).ioctl(fd, buf.as_mut_ptr()) } {
+            Ok(_) => return Ok(Some(size)),
+            Err(e)
+                if matches!(
+                    e.raw_os_error(),
+                    Some(libc::ENOTTY | libc::EINVAL | libc::E2BIG)
+                ) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+/// Takes ownership of several file descriptors returned by an `ioctl`.
+///
+/// Some `ioctl`s create multiple file descriptors at once, and return them in an array inside
+/// their argument `struct`.
+/// The caller becomes responsible for closing them, which is easy to get wrong if an error occurs
+/// halfway through processing the result.
+/// This function wraps each of them in an [`OwnedFd`] right away, so that they are closed when
+/// dropped.
+///
+/// # Panics
+///
+/// This function panics if any of the file descriptors is -1, which many `ioctl`s use to mark
+/// unused array entries. Such entries have to be filtered out by the caller.
+///
+/// # Safety
+///
+/// Every file descriptor in `raw` has to be open and owned by the caller, and must not appear more
+/// than once.
+/// After this call, `raw` must no longer be used to access or close them.
+///
+/// # Example
+///
+/// ```
+/// use std::{fs::File, io::{Read, Write}};
+/// use uoctl::*;
+///
+/// // `pipe(2)` stands in for an `ioctl` that returns several file descriptors.
+/// let mut fds = [0; 2];
+/// assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+///
+/// let [read, write] = <[_; 2]>::try_from(unsafe { adopt_fds(&fds) }).unwrap();
+/// File::from(write).write_all(b"hi")?;
+///
+/// let mut buf = String::new();
+/// File::from(read).read_to_string(&mut buf)?;
+/// assert_eq!(buf, "hi");
+/// # std::io::Result::Ok(())
+/// ```
+pub unsafe fn adopt_fds(raw: &[RawFd]) -> Vec<OwnedFd> {
+    assert!(
+        !raw.contains(&-1),
+        "cannot take ownership of file descriptor -1"
+    );
+    raw.iter()
+        .map(|&fd| unsafe { OwnedFd::from_raw_fd(fd) })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use crate::{_IOC, _IOC_READ_WRITE, _IOWR};
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Multiplexed {
+        op: u32,
+        value: u64,
+    }
+
+    const MULTIPLEXED: SubcommandIoctl<Multiplexed> =
+        SubcommandIoctl::new(_IOWR(b'z', 0x10), |arg, op| arg.op = op);
+
+    #[test]
+    fn sets_subcommand() {
+        let file = File::open("/dev/null").unwrap();
+
+        let mut arg = Multiplexed { op: 1, value: 123 };
+        let err = unsafe { MULTIPLEXED.ioctl(&file, 7, &mut arg).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+        assert_eq!(arg.op, 7);
+        assert_eq!(arg.value, 123);
+
+        // The sub-command is not encoded in the request code.
+        assert_eq!(MULTIPLEXED.inner().nr(), 0x10);
+        assert_eq!(
+            MULTIPLEXED.inner().request(),
+            _IOWR::<Multiplexed>(b'z', 0x10).request(),
+        );
+    }
+
+    #[test]
+    fn negotiate_version() {
+        const FIONREAD: Ioctl<*mut u8> = crate::tests::FIONREAD.cast_pointee();
+
+        // Pretend that the driver is an old kernel that only knows the 4-byte version of an
+        // `ioctl` (`FIONREAD`, which stores an `int`), and map every other size to an unknown
+        // request code.
+        let make_ioctl = |size| match size {
+            4 => FIONREAD,
+            _ => _IOC(_IOC_READ_WRITE, b'z', 0x11, size),
+        };
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let read = unsafe { OwnedFd::from_raw_fd(fds[0]) };
+        let _write = unsafe { OwnedFd::from_raw_fd(fds[1]) };
+
+        let size = unsafe { super::negotiate_version(&read, make_ioctl, &[4, 32, 16]) };
+        assert_eq!(size.unwrap(), Some(4));
+
+        let size = unsafe { super::negotiate_version(&read, make_ioctl, &[8, 32, 16]) };
+        assert_eq!(size.unwrap(), None);
+
+        let size = unsafe { super::negotiate_version(&read, make_ioctl, &[]) };
+        assert_eq!(size.unwrap(), None);
+
+        let err = unsafe { super::negotiate_version(&-1, make_ioctl, &[4, 8]) }.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    fn adopt_pipe_fds() {
+        let mut fds = [0; 4];
+        unsafe {
+            assert_eq!(libc::pipe(fds.as_mut_ptr()), 0);
+            assert_eq!(libc::pipe(fds[2..].as_mut_ptr()), 0);
+        }
+
+        let owned = unsafe { adopt_fds(&fds) };
+        assert_eq!(owned.len(), 4);
+        for (fd, raw) in owned.iter().zip(fds) {
+            assert_eq!(fd.as_raw_fd(), raw);
+        }
+
+        // The second pipe still works through the adopted descriptors.
+        let n = unsafe { libc::write(owned[3].as_raw_fd(), b"x".as_ptr().cast(), 1) };
+        assert_eq!(n, 1);
+        let mut byte = 0u8;
+        let n = unsafe { libc::read(owned[2].as_raw_fd(), (&raw mut byte).cast(), 1) };
+        assert_eq!(n, 1);
+        assert_eq!(byte, b'x');
+    }
+
+    #[test]
+    #[should_panic = "file descriptor -1"]
+    fn adopt_invalid_fd() {
+        let _ = unsafe { adopt_fds(&[-1]) };
+    }
+
+    #[repr(C)]
+    struct BaseArgs {
+        size: u32,
+        flags: u32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct ExtArgs {
+        size: u32,
+        flags: u32,
+        extra: [u64; 2],
+    }
+
+    const VERSIONED: VersionedIoctl<ExtArgs> =
+        _IOWR::<BaseArgs>(b'z', 0x12).with_versioned_arg(|arg, size| arg.size = size);
+
+    #[test]
+    fn sets_size() {
+        // The request code encodes the size of the base `struct`.
+        assert_eq!(
+            VERSIONED.inner().request(),
+            _IOWR::<BaseArgs>(b'z', 0x12).request(),
+        );
+
+        let file = File::open("/dev/null").unwrap();
+        let mut arg = ExtArgs {
+            flags: 3,
+            ..Default::default()
+        };
+        let err = unsafe { VERSIONED.ioctl(&file, &mut arg).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+        assert_eq!(arg.size, 24);
+        assert_eq!(arg.flags, 3);
+    }
+
+    #[test]
+    #[should_panic = "must not be smaller than the base `struct`"]
+    fn versioned_smaller_than_base() {
+        let _ = _IOWR::<ExtArgs>(b'z', 0x12).with_versioned_arg::<BaseArgs>(|_, _| {});
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
+    struct PixFormat {
+        width: u32,
+        height: u32,
+    }
+
+    #[repr(C)]
+    union FormatUnion {
+        pix: PixFormat,
+        raw_data: [u8; 16],
+    }
+
+    #[repr(C)]
+    struct Format {
+        ty: u32,
+        fmt: FormatUnion,
+    }
+
+    const TYPE_CAPTURE: u32 = 1;
+    const TYPE_OUTPUT: u32 = 2;
+    const TYPE_PRIVATE: u32 = 0x80;
+
+    unsafe impl TaggedUnion for Format {
+        type Tag = u32;
+        fn tag(&self) -> u32 {
+            self.ty
+        }
+        fn set_tag(&mut self, tag: u32) {
+            self.ty = tag;
+        }
+    }
+
+    unsafe impl UnionVariant<Format> for PixFormat {
+        fn accepts(tag: u32) -> bool {
+            matches!(tag, TYPE_CAPTURE | TYPE_OUTPUT)
+        }
+        unsafe fn field(arg: *mut Format) -> *mut Self {
+            unsafe { &raw mut (*arg).fmt.pix }
+        }
+    }
+
+    unsafe impl UnionVariant<Format> for [u8; 16] {
+        fn accepts(_: u32) -> bool {
+            // The raw bytes are always valid.
+            true
+        }
+        unsafe fn field(arg: *mut Format) -> *mut Self {
+            unsafe { &raw mut (*arg).fmt.raw_data }
+        }
+    }
+
+    #[test]
+    fn union_variants() {
+        let pix = PixFormat {
+            width: 640,
+            height: 480,
+        };
+        let mut format = IoctlUnion::new(TYPE_OUTPUT, pix);
+        assert_eq!(format.tag(), TYPE_OUTPUT);
+        assert_eq!(format.get::<PixFormat>(), Some(&pix));
+        assert_eq!(format.get::<[u8; 16]>().unwrap()[..4], 640u32.to_ne_bytes());
+
+        format.get_mut::<PixFormat>().unwrap().height = 360;
+        assert_eq!(unsafe { format.as_inner().fmt.pix.height }, 360);
+
+        // Simulate the kernel switching to a different type.
+        unsafe { (*format.as_mut_ptr()).ty = TYPE_PRIVATE };
+        assert_eq!(format.get::<PixFormat>(), None);
+        assert!(format.get_mut::<PixFormat>().is_none());
+        assert!(format.get::<[u8; 16]>().is_some());
+
+        let raw = IoctlUnion::<Format>::new(TYPE_PRIVATE, [0xAB; 16]);
+        assert_eq!(raw.get::<[u8; 16]>(), Some(&[0xAB; 16]));
+        assert_eq!(raw.get::<PixFormat>(), None);
+        assert_eq!(raw.into_inner().ty, TYPE_PRIVATE);
+    }
+
+    #[test]
+    #[should_panic = "does not select this union field"]
+    fn union_wrong_tag() {
+        let _ = IoctlUnion::<Format>::new(TYPE_PRIVATE, PixFormat::default());
+    }
+}
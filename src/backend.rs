@@ -0,0 +1,229 @@
+//! Pluggable implementations of the `ioctl` system call.
+
+use std::{ffi::c_int, io, os::fd::RawFd, ptr};
+
+/// Performs the `ioctl` system call on behalf of [`Ioctl::ioctl_with_backend`].
+///
+/// The default implementation is [`LibcBackend`], which calls libc's `ioctl(2)` wrapper, just
+/// like [`Ioctl::ioctl`].
+/// A custom implementation can be used to record the `ioctl`s performed by driver wrappers in
+/// unit tests and return canned results, or to forward them to a different transport.
+///
+/// The argument is passed to the backend as a [`usize`]: pointer arguments are passed as their
+/// address, and integer arguments are zero-extended.
+///
+/// [`Ioctl::ioctl`]: crate::Ioctl::ioctl
+/// [`Ioctl::ioctl_with_backend`]: crate::Ioctl::ioctl_with_backend
+///
+/// # Example
+///
+/// A backend that records every `ioctl` and reports success:
+///
+/// ```
+/// use std::{cell::RefCell, ffi::c_int, io, os::fd::RawFd};
+/// use uoctl::*;
+///
+/// #[derive(Default)]
+/// struct Recorder {
+///     calls: RefCell<Vec<(u32, usize)>>,
+/// }
+///
+/// impl IoctlBackend for Recorder {
+///     unsafe fn ioctl(&self, _fd: RawFd, request: u32, arg: usize) -> io::Result<c_int> {
+///         self.calls.borrow_mut().push((request, arg));
+///         Ok(0)
+///     }
+/// }
+///
+/// const UI_SET_EVBIT: Ioctl<c_int> = _IOW(b'U', 100).with_direct_arg();
+///
+/// let recorder = Recorder::default();
+/// unsafe { UI_SET_EVBIT.ioctl_with_backend(&recorder, &-1, 1)? };
+/// assert_eq!(*recorder.calls.borrow(), [(UI_SET_EVBIT.request(), 1)]);
+/// # std::io::Result::Ok(())
+/// ```
+pub trait IoctlBackend {
+    /// Performs the `ioctl` `request` on `fd`, passing `arg` as its argument.
+    ///
+    /// On success, returns the value returned by the `ioctl`. On error, returns an error with the
+    /// *errno* value reported by the `ioctl`.
+    ///
+    /// # Safety
+    ///
+    /// The caller has to uphold the safety requirements of [`Ioctl::ioctl`] for the given request
+    /// code, argument, and file descriptor.
+    ///
+    /// [`Ioctl::ioctl`]: crate::Ioctl::ioctl
+    unsafe fn ioctl(&self, fd: RawFd, request: u32, arg: usize) -> io::Result<c_int>;
+}
+
+/// The default [`IoctlBackend`], which calls libc's `ioctl(2)` wrapper.
+///
+/// With the `mock` feature, `ioctl`s on a `MockFd` are handled by its handler, just like with
+/// [`Ioctl::ioctl`].
+///
+/// [`Ioctl::ioctl`]: crate::Ioctl::ioctl
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LibcBackend;
+
+impl IoctlBackend for LibcBackend {
+    unsafe fn ioctl(&self, fd: RawFd, request: u32, arg: usize) -> io::Result<c_int> {
+        #[cfg(feature = "mock")]
+        if let Some(res) = crate::mock::intercept(fd, request, &arg) {
+            return res;
+        }
+        let res = unsafe { libc::ioctl(fd, request as _, arg) };
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    }
+}
+
+/// Converts an `ioctl` argument to the [`usize`] passed to an [`IoctlBackend`].
+///
+/// The bytes of `arg` end up in the low-order bytes of the [`usize`], so integers are
+/// zero-extended. Arguments larger than a [`usize`] are rejected at compile time.
+pub(crate) fn arg_bits<T>(arg: &T) -> usize {
+    const {
+        assert!(
+            size_of::<T>() <= size_of::<usize>(),
+            "`ioctl` arguments passed by value must not be larger than a `usize`",
+        );
+    }
+
+    match try_arg_bits(arg) {
+        Some(bits) => bits,
+        None => unreachable!(),
+    }
+}
+
+/// Converts an `ioctl` argument to a [`usize`] like [`arg_bits`], but returns [`None`] if it is
+/// larger than a [`usize`].
+pub(crate) fn try_arg_bits<T>(arg: &T) -> Option<usize> {
+    if size_of::<T>() > size_of::<usize>() {
+        return None;
+    }
+
+    let mut bits = [0; size_of::<usize>()];
+    let offset = if cfg!(target_endian = "big") {
+        bits.len() - size_of::<T>()
+    } else {
+        0
+    };
+    unsafe {
+        ptr::copy_nonoverlapping(
+            ptr::from_ref(arg).cast::<u8>(),
+            bits[offset..].as_mut_ptr(),
+            size_of::<T>(),
+        );
+    }
+    Some(usize::from_ne_bytes(bits))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, fs::File, io::Write, os::fd::FromRawFd};
+
+    use crate::{_IO, _IOR, Ioctl};
+
+    use super::*;
+
+    /// Records every `ioctl`, and returns the next canned result.
+    struct MockBackend {
+        calls: RefCell<Vec<(u32, usize)>>,
+        results: RefCell<Vec<io::Result<c_int>>>,
+    }
+
+    impl MockBackend {
+        fn new(mut results: Vec<io::Result<c_int>>) -> Self {
+            results.reverse();
+            Self {
+                calls: RefCell::new(Vec::new()),
+                results: RefCell::new(results),
+            }
+        }
+    }
+
+    impl IoctlBackend for MockBackend {
+        unsafe fn ioctl(&self, _fd: RawFd, request: u32, arg: usize) -> io::Result<c_int> {
+            self.calls.borrow_mut().push((request, arg));
+            self.results.borrow_mut().pop().unwrap()
+        }
+    }
+
+    #[test]
+    fn mock_backend() {
+        const GET: Ioctl<*mut u32> = _IOR(b'x', 1);
+        const SET: Ioctl<c_int> = _IO(b'x', 2).cast_arg();
+        const RESET: Ioctl = _IO(b'x', 3);
+
+        let backend = MockBackend::new(vec![
+            Ok(0),
+            Ok(7),
+            Err(io::Error::from_raw_os_error(libc::ENOTTY)),
+        ]);
+        let mut value = 0;
+        unsafe {
+            assert_eq!(
+                GET.ioctl_with_backend(&backend, &-1, &mut value).unwrap(),
+                0
+            );
+            assert_eq!(SET.ioctl_with_backend(&backend, &-1, 5).unwrap(), 7);
+            let err = RESET.ioctl_with_backend(&backend, &-1).unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+        }
+        assert_eq!(
+            *backend.calls.borrow(),
+            [
+                (GET.request(), &raw mut value as usize),
+                (SET.request(), 5),
+                (RESET.request(), 0),
+            ],
+        );
+    }
+
+    #[test]
+    fn libc_backend() {
+        use crate::tests::FIONREAD;
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (read, write) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+        (&write).write_all(b"hello").unwrap();
+
+        let mut bytes = 0;
+        unsafe {
+            FIONREAD
+                .ioctl_with_backend(&LibcBackend, &read, &mut bytes)
+                .unwrap()
+        };
+        assert_eq!(bytes, 5);
+
+        let err = unsafe { LibcBackend.ioctl(-1, FIONREAD.request(), 0).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    fn arg_bits() {
+        #[derive(Clone, Copy)]
+        #[repr(C)]
+        struct Bytes3([u8; 3]);
+
+        assert_eq!(super::arg_bits(&()), 0);
+        assert_eq!(super::arg_bits(&0xABu8), 0xAB);
+        assert_eq!(super::arg_bits(&-1i16), 0xFFFF);
+        assert_eq!(super::arg_bits(&0x12345678u32), 0x12345678);
+        assert_eq!(super::arg_bits(&usize::MAX), usize::MAX);
+        let bits = super::arg_bits(&Bytes3([1, 2, 3]));
+        if cfg!(target_endian = "little") {
+            assert_eq!(bits, 0x030201);
+        } else {
+            assert_eq!(bits, 0x010203);
+        }
+
+        assert_eq!(try_arg_bits(&0x1234u16), Some(0x1234));
+        assert_eq!(try_arg_bits(&[0usize; 2]), None);
+    }
+}
@@ -0,0 +1,267 @@
+//! `ioctl`s for CD-ROM drives (`/dev/sr*`).
+//!
+//! Drives are typically opened with `O_NONBLOCK`, which allows querying and controlling the drive
+//! even when no disc is inserted.
+//!
+//! From `linux/cdrom.h`:
+//!
+//! ```c
+//! #define CDROMREADTOCHDR		0x5305 /* Read TOC header
+//!                                            (struct cdrom_tochdr) */
+//! #define CDROMREADTOCENTRY	0x5306 /* Read TOC entry
+//!                                            (struct cdrom_tocentry) */
+//! #define CDROMEJECT		0x5309 /* Ejects the cdrom media */
+//! ...
+//! #define CDROMCLOSETRAY		0x5319	/* pendant of CDROMEJECT */
+//! ...
+//! #define CDROM_DRIVE_STATUS	0x5326  /* Get tray position, etc. */
+//! ...
+//! #define CDROM_GET_CAPABILITY	0x5331	/* get capabilities */
+//! ```
+//!
+//! # Example
+//!
+#![cfg_attr(feature = "std", doc = "```no_run")]
+#![cfg_attr(not(feature = "std"), doc = "```ignore")]
+//! use std::{fs::File, os::unix::fs::OpenOptionsExt};
+//! use uoctl::cdrom::*;
+//!
+//! let drive = File::options()
+//!     .read(true)
+//!     .custom_flags(libc::O_NONBLOCK)
+//!     .open("/dev/sr0")?;
+//!
+//! let caps = unsafe { CDROM_GET_CAPABILITY.ioctl(&drive)? };
+//! match unsafe { CDROM_DRIVE_STATUS.ioctl(&drive, CDSL_CURRENT)? } {
+//!     CDS_TRAY_OPEN if caps & CDC_CLOSE_TRAY != 0 => unsafe {
+//!         CDROMCLOSETRAY.ioctl(&drive)?;
+//!     },
+//!     CDS_DISC_OK => {
+//!         let mut hdr = cdrom_tochdr::default();
+//!         unsafe { CDROMREADTOCHDR.ioctl(&drive, &mut hdr)? };
+//!         println!("disc has tracks {} to {}", hdr.cdth_trk0, hdr.cdth_trk1);
+//!     }
+//!     status => println!("drive status: {status}"),
+//! }
+//! # std::io::Result::Ok(())
+//! ```
+
+#![allow(non_camel_case_types)]
+
+use core::ffi::c_int;
+
+use crate::{Ioctl, NoArgs};
+
+/// Reads the table of contents header.
+pub const CDROMREADTOCHDR: Ioctl<*mut cdrom_tochdr> = Ioctl::from_raw(0x5305);
+/// Reads an entry of the table of contents.
+///
+/// [`cdrom_tocentry::cdte_track`] and [`cdrom_tocentry::cdte_format`] have to be set by the
+/// caller.
+pub const CDROMREADTOCENTRY: Ioctl<*mut cdrom_tocentry> = Ioctl::from_raw(0x5306);
+/// Ejects the disc (opening the tray, if the drive has one).
+pub const CDROMEJECT: Ioctl<NoArgs> = Ioctl::from_raw(0x5309);
+/// Closes the tray.
+pub const CDROMCLOSETRAY: Ioctl<NoArgs> = Ioctl::from_raw(0x5319);
+/// Returns the status of the drive (`CDS_*`).
+///
+/// The argument selects the slot of a disc changer, and should be [`CDSL_CURRENT`] otherwise.
+pub const CDROM_DRIVE_STATUS: Ioctl<c_int> = Ioctl::from_raw(0x5326);
+/// Returns the capabilities of the drive, as a bitmask of `CDC_*` flags.
+pub const CDROM_GET_CAPABILITY: Ioctl<NoArgs> = Ioctl::from_raw(0x5331);
+
+/// [`CDROM_DRIVE_STATUS`] argument: the slot that is currently loaded.
+pub const CDSL_CURRENT: c_int = c_int::MAX;
+
+/// [`CDROM_DRIVE_STATUS`]: the drive can't report its status.
+pub const CDS_NO_INFO: c_int = 0;
+/// [`CDROM_DRIVE_STATUS`]: there is no disc in the drive.
+pub const CDS_NO_DISC: c_int = 1;
+/// [`CDROM_DRIVE_STATUS`]: the tray is open.
+pub const CDS_TRAY_OPEN: c_int = 2;
+/// [`CDROM_DRIVE_STATUS`]: the drive is not ready (yet).
+pub const CDS_DRIVE_NOT_READY: c_int = 3;
+/// [`CDROM_DRIVE_STATUS`]: there is a disc in the drive, and it's ready.
+pub const CDS_DISC_OK: c_int = 4;
+
+/// [`CDROM_GET_CAPABILITY`]: the drive can close its tray.
+pub const CDC_CLOSE_TRAY: c_int = 0x1;
+/// [`CDROM_GET_CAPABILITY`]: the drive can open its tray.
+pub const CDC_OPEN_TRAY: c_int = 0x2;
+/// [`CDROM_GET_CAPABILITY`]: the drive can be locked.
+pub const CDC_LOCK: c_int = 0x4;
+/// [`CDROM_GET_CAPABILITY`]: the drive's speed can be selected.
+pub const CDC_SELECT_SPEED: c_int = 0x8;
+/// [`CDROM_GET_CAPABILITY`]: the drive is a disc changer.
+pub const CDC_SELECT_DISC: c_int = 0x10;
+/// [`CDROM_GET_CAPABILITY`]: the drive can read multi-session discs.
+pub const CDC_MULTI_SESSION: c_int = 0x20;
+/// [`CDROM_GET_CAPABILITY`]: the drive can read the Medium Catalog Number.
+pub const CDC_MCN: c_int = 0x40;
+/// [`CDROM_GET_CAPABILITY`]: the drive can report media changes.
+pub const CDC_MEDIA_CHANGED: c_int = 0x80;
+/// [`CDROM_GET_CAPABILITY`]: the drive can play audio.
+pub const CDC_PLAY_AUDIO: c_int = 0x100;
+/// [`CDROM_GET_CAPABILITY`]: the drive can be reset.
+pub const CDC_RESET: c_int = 0x200;
+/// [`CDROM_GET_CAPABILITY`]: the drive can report its status via [`CDROM_DRIVE_STATUS`].
+pub const CDC_DRIVE_STATUS: c_int = 0x800;
+/// [`CDROM_GET_CAPABILITY`]: the drive supports generic packet commands.
+pub const CDC_GENERIC_PACKET: c_int = 0x1000;
+/// [`CDROM_GET_CAPABILITY`]: the drive can write CD-Rs.
+pub const CDC_CD_R: c_int = 0x2000;
+/// [`CDROM_GET_CAPABILITY`]: the drive can write CD-RWs.
+pub const CDC_CD_RW: c_int = 0x4000;
+/// [`CDROM_GET_CAPABILITY`]: the drive can read DVDs.
+pub const CDC_DVD: c_int = 0x8000;
+/// [`CDROM_GET_CAPABILITY`]: the drive can write DVD-Rs.
+pub const CDC_DVD_R: c_int = 0x10000;
+/// [`CDROM_GET_CAPABILITY`]: the drive can write DVD-RAMs.
+pub const CDC_DVD_RAM: c_int = 0x20000;
+
+/// [`cdrom_tocentry::cdte_format`]: address the track by logical block.
+pub const CDROM_LBA: u8 = 0x01;
+/// [`cdrom_tocentry::cdte_format`]: address the track by minute/second/frame.
+pub const CDROM_MSF: u8 = 0x02;
+/// [`cdrom_tocentry::cdte_track`]: the lead-out track.
+pub const CDROM_LEADOUT: u8 = 0xAA;
+
+/// The table of contents header, read with [`CDROMREADTOCHDR`].
+///
+/// ```c
+/// struct cdrom_tochdr
+/// {
+/// 	__u8	cdth_trk0;	/* start track */
+/// 	__u8	cdth_trk1;	/* end track */
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct cdrom_tochdr {
+    pub cdth_trk0: u8,
+    pub cdth_trk1: u8,
+}
+
+/// An address given as minute, second, and frame.
+///
+/// ```c
+/// struct cdrom_msf0
+/// {
+/// 	__u8	minute;
+/// 	__u8	second;
+/// 	__u8	frame;
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct cdrom_msf0 {
+    pub minute: u8,
+    pub second: u8,
+    pub frame: u8,
+}
+
+/// A track address, in the format selected by [`cdrom_tocentry::cdte_format`].
+///
+/// ```c
+/// union cdrom_addr
+/// {
+/// 	struct cdrom_msf0	msf;
+/// 	int			lba;
+/// };
+/// ```
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub union cdrom_addr {
+    pub msf: cdrom_msf0,
+    pub lba: c_int,
+}
+
+impl Default for cdrom_addr {
+    fn default() -> Self {
+        Self { lba: 0 }
+    }
+}
+
+/// An entry of the table of contents, read with [`CDROMREADTOCENTRY`].
+///
+/// ```c
+/// struct cdrom_tocentry
+/// {
+/// 	__u8	cdte_track;
+/// 	__u8	cdte_adr	:4;
+/// 	__u8	cdte_ctrl	:4;
+/// 	__u8	cdte_format;
+/// 	union cdrom_addr cdte_addr;
+/// 	__u8	cdte_datamode;
+/// };
+/// ```
+///
+/// The `cdte_adr` and `cdte_ctrl` bitfields share the byte `cdte_adr_ctrl`, and can be accessed
+/// with [`cdrom_tocentry::cdte_adr`] and [`cdrom_tocentry::cdte_ctrl`].
+#[derive(Clone, Copy, Default)]
+#[repr(C)]
+pub struct cdrom_tocentry {
+    pub cdte_track: u8,
+    pub cdte_adr_ctrl: u8,
+    pub cdte_format: u8,
+    pub cdte_addr: cdrom_addr,
+    pub cdte_datamode: u8,
+}
+
+impl cdrom_tocentry {
+    // C allocates bitfields starting at the least significant bit on little-endian targets, and
+    // at the most significant bit on big-endian ones.
+    const ADR_SHIFT: u32 = if cfg!(target_endian = "little") { 0 } else { 4 };
+    const CTRL_SHIFT: u32 = 4 - Self::ADR_SHIFT;
+
+    /// Returns the `cdte_adr` bitfield.
+    pub fn cdte_adr(&self) -> u8 {
+        (self.cdte_adr_ctrl >> Self::ADR_SHIFT) & 0xf
+    }
+
+    /// Returns the `cdte_ctrl` bitfield.
+    pub fn cdte_ctrl(&self) -> u8 {
+        (self.cdte_adr_ctrl >> Self::CTRL_SHIFT) & 0xf
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::{fs::File, os::unix::fs::OpenOptionsExt};
+
+    use super::*;
+
+    #[test]
+    fn layout() {
+        assert_eq!(size_of::<cdrom_tochdr>(), 2);
+        assert_eq!(size_of::<cdrom_addr>(), 4);
+        assert_eq!(size_of::<cdrom_tocentry>(), 12);
+    }
+
+    #[test]
+    fn bitfields() {
+        let mut entry = cdrom_tocentry::default();
+        if cfg!(target_endian = "little") {
+            entry.cdte_adr_ctrl = 0x41;
+        } else {
+            entry.cdte_adr_ctrl = 0x14;
+        }
+        assert_eq!(entry.cdte_adr(), 0x1);
+        assert_eq!(entry.cdte_ctrl(), 0x4);
+    }
+
+    #[test]
+    fn drive_status() {
+        // Most machines (and CI runners) don't have an optical drive.
+        let Ok(drive) = File::options()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/sr0")
+        else {
+            return;
+        };
+
+        let status = unsafe { CDROM_DRIVE_STATUS.ioctl(&drive, CDSL_CURRENT).unwrap() };
+        assert!((CDS_NO_INFO..=CDS_DISC_OK).contains(&status));
+    }
+}
@@ -0,0 +1,263 @@
+//! `ioctl`s for the evdev input device interface (`/dev/input/event*`).
+//!
+//! evdev is also implemented by FreeBSD, so this module is available on every platform.
+//!
+//! From `linux/input.h`:
+//!
+//! ```c
+//! /**
+//!  * EVIOCGMTSLOTS(len) - get MT slot values
+//!  * @len: size of the data buffer in bytes
+//!  *
+//!  * The ioctl buffer argument should be binary equivalent to
+//!  *
+//!  * struct input_mt_request_layout {
+//!  *	__u32 code;
+//!  *	__s32 values[num_slots];
+//!  * };
+//!  * ...
+//!  */
+//! #define EVIOCGMTSLOTS(len)	_IOC(_IOC_READ, 'E', 0x0a, len)
+//!
+//! #define EVIOCGKEY(len)		_IOC(_IOC_READ, 'E', 0x18, len)		/* get global key state */
+//! #define EVIOCGLED(len)		_IOC(_IOC_READ, 'E', 0x19, len)		/* get all LEDs */
+//! #define EVIOCGSND(len)		_IOC(_IOC_READ, 'E', 0x1a, len)		/* get all sounds status */
+//! #define EVIOCGSW(len)		_IOC(_IOC_READ, 'E', 0x1b, len)		/* get all switch states */
+//! ...
+//! #define EVIOCSFF		_IOW('E', 0x80, struct ff_effect)	/* send a force effect to a force feedback device */
+//! #define EVIOCRMFF		_IOW('E', 0x81, int)			/* Erase a force effect */
+//! #define EVIOCGEFFECTS		_IOR('E', 0x84, int)			/* Report number of effects playable at the same time */
+//! ```
+//!
+//! # Bitmasks
+//!
+//! Many evdev `ioctl`s fill a caller-provided buffer with a bitmask, and encode the length of the
+//! buffer (in bytes) in the request code.
+//! The kernel stores the bitmask as an array of `unsigned long`s, where bit *n* is set if the key,
+//! LED, or switch with code *n* is active.
+//! On little-endian platforms, this means that code *n* can be tested with
+//! `buf[n / 8] & (1 << (n % 8)) != 0`.
+//!
+//! # Example
+//!
+//! Checking whether the <kbd>A</kbd> key is currently held down.
+//!
+//! From `linux/input-event-codes.h`:
+//!
+//! ```c
+//! #define KEY_A			30
+//! ...
+//! #define KEY_MAX			0x2ff
+//! ```
+//!
+#![cfg_attr(feature = "std", doc = "```no_run")]
+#![cfg_attr(not(feature = "std"), doc = "```ignore")]
+//! use std::fs::File;
+//! use uoctl::evdev::*;
+//!
+//! const KEY_A: usize = 30;
+//! const KEY_MAX: usize = 0x2ff;
+//!
+//! let file = File::open("/dev/input/event0")?;
+//!
+//! let mut keys = [0u8; KEY_MAX / 8 + 1];
+//! unsafe { EVIOCGKEY(keys.len()).ioctl(&file, keys.as_mut_ptr())? };
+//!
+//! let pressed = keys[KEY_A / 8] & (1 << (KEY_A % 8)) != 0;
+//! println!("A is {}", if pressed { "pressed" } else { "released" });
+//! # std::io::Result::Ok(())
+//! ```
+
+#![allow(non_snake_case)]
+
+use core::ffi::c_int;
+#[cfg(feature = "std")]
+use std::{io, os::fd::AsRawFd};
+
+use crate::{_IOC, _IOC_READ, _IOR, _IOW, Ioctl, bitmask_ioctl};
+
+/// [`mt_slots`]: the slot that is currently being modified.
+pub const ABS_MT_SLOT: i32 = 0x2f;
+/// [`mt_slots`]: the X coordinate of the contact.
+pub const ABS_MT_POSITION_X: i32 = 0x35;
+/// [`mt_slots`]: the Y coordinate of the contact.
+pub const ABS_MT_POSITION_Y: i32 = 0x36;
+/// [`mt_slots`]: the tracking ID of the contact, or -1 if the slot is unused.
+pub const ABS_MT_TRACKING_ID: i32 = 0x39;
+
+/// Reads the values of a multitouch axis for `n_slots` slots.
+///
+/// **Note**: Unlike the C macro, which takes the buffer size in bytes, this function takes the
+/// number of slots, and computes the buffer size as `(n_slots + 1) * size_of::<i32>()`, since the
+/// buffer also contains the axis code in front of the slot values.
+///
+/// The first element of the buffer has to be set to the `ABS_MT_*` code to query, and the kernel
+/// fills the following `n_slots` elements with the value of that axis in each slot.
+/// [`mt_slots`] takes care of this.
+///
+/// # Panics
+///
+/// This function may panic when the buffer size exceeds the (platform-specific) maximum parameter
+/// size.
+pub const fn EVIOCGMTSLOTS(n_slots: usize) -> Ioctl<*mut i32> {
+    _IOC(_IOC_READ, b'E', 0x0a, (n_slots + 1) * size_of::<i32>())
+}
+
+/// Reads the values of the multitouch axis `abs_code` for the first `n_slots` slots.
+///
+/// Performs [`EVIOCGMTSLOTS`] and returns the slot values (without the axis code).
+/// If the device has fewer than `n_slots` slots, the surplus values are left at 0.
+///
+/// # Errors
+///
+/// Fails with `EINVAL` if the device is not a multitouch device, or if `abs_code` is not a
+/// multitouch axis.
+///
+/// # Panics
+///
+/// This function may panic when `n_slots` is so large that the buffer size exceeds the
+/// (platform-specific) maximum parameter size (see [`EVIOCGMTSLOTS`]).
+///
+/// # Safety
+///
+/// `fd` has to be valid (open) and refer to an evdev device.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::fs::File;
+/// use uoctl::evdev::*;
+///
+/// let touchscreen = File::open("/dev/input/event5")?;
+///
+/// let ids = unsafe { mt_slots(&touchscreen, ABS_MT_TRACKING_ID, 10)? };
+/// let xs = unsafe { mt_slots(&touchscreen, ABS_MT_POSITION_X, 10)? };
+/// for (slot, (id, x)) in ids.iter().zip(&xs).enumerate() {
+///     if *id != -1 {
+///         println!("slot {slot}: contact {id} at x={x}");
+///     }
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub unsafe fn mt_slots(fd: &impl AsRawFd, abs_code: i32, n_slots: usize) -> io::Result<Vec<i32>> {
+    let mut buf = vec![0; n_slots + 1];
+    buf[0] = abs_code;
+    unsafe { EVIOCGMTSLOTS(n_slots).ioctl(fd, buf.as_mut_ptr())? };
+    buf.remove(0);
+    Ok(buf)
+}
+
+/// Reads the global key state into a bitmask of `len` bytes.
+pub const fn EVIOCGKEY(len: usize) -> Ioctl<*mut u8> {
+    bitmask_ioctl(_IOC_READ, b'E', 0x18, len)
+}
+
+/// Reads the state of all LEDs into a bitmask of `len` bytes.
+pub const fn EVIOCGLED(len: usize) -> Ioctl<*mut u8> {
+    bitmask_ioctl(_IOC_READ, b'E', 0x19, len)
+}
+
+/// Reads the state of all sounds into a bitmask of `len` bytes.
+pub const fn EVIOCGSND(len: usize) -> Ioctl<*mut u8> {
+    bitmask_ioctl(_IOC_READ, b'E', 0x1a, len)
+}
+
+/// Reads the state of all switches into a bitmask of `len` bytes.
+pub const fn EVIOCGSW(len: usize) -> Ioctl<*mut u8> {
+    bitmask_ioctl(_IOC_READ, b'E', 0x1b, len)
+}
+
+/// Uploads a force feedback effect to the device.
+///
+/// If `ff_effect::id` is -1, the kernel allocates a new effect and writes its ID back to the
+/// argument, so this is declared with a `*mut` pointer, despite the C header using `_IOW`.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub const EVIOCSFF: Ioctl<*mut libc::ff_effect> = _IOW(b'E', 0x80).cast_mut();
+
+/// Erases the force feedback effect with the given ID.
+///
+/// The effect ID is passed directly, not behind a pointer.
+pub const EVIOCRMFF: Ioctl<c_int> = _IOW(b'E', 0x81).with_direct_arg();
+
+/// Reads the number of force feedback effects the device can play at the same time.
+pub const EVIOCGEFFECTS: Ioctl<*mut c_int> = _IOR(b'E', 0x84);
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::fs::{self, File};
+
+    use super::*;
+
+    #[test]
+    fn bitmask_ioctls() {
+        for (ioctl, nr) in [
+            (EVIOCGKEY(96), 0x18),
+            (EVIOCGLED(2), 0x19),
+            (EVIOCGSND(1), 0x1a),
+            (EVIOCGSW(4), 0x1b),
+        ] {
+            assert_eq!(ioctl.ty(), b'E');
+            assert_eq!(ioctl.nr(), nr);
+        }
+
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        assert_eq!(EVIOCGKEY(96).request(), 0x80604518);
+    }
+
+    #[test]
+    fn mt_slots_size() {
+        assert_eq!(EVIOCGMTSLOTS(10).ty(), b'E');
+        assert_eq!(EVIOCGMTSLOTS(10).nr(), 0x0a);
+
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        {
+            // `EVIOCGMTSLOTS(44)` in C.
+            assert_eq!(EVIOCGMTSLOTS(10).request(), 0x802c450a);
+            assert_eq!(EVIOCGMTSLOTS(0).request(), 0x8004450a);
+        }
+    }
+
+    #[test]
+    fn read_mt_slots() {
+        // Only runs if there are input devices; non-multitouch devices reject the `ioctl`.
+        let Ok(entries) = fs::read_dir("/dev/input") else {
+            return;
+        };
+        for entry in entries {
+            let path = entry.unwrap().path();
+            if !path
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .starts_with("event")
+            {
+                continue;
+            }
+            let Ok(dev) = File::open(&path) else {
+                continue;
+            };
+
+            match unsafe { mt_slots(&dev, ABS_MT_TRACKING_ID, 4) } {
+                Ok(ids) => assert_eq!(ids.len(), 4),
+                Err(e) => assert_eq!(e.raw_os_error(), Some(libc::EINVAL), "{}", path.display()),
+            }
+        }
+    }
+
+    #[test]
+    fn force_feedback_ioctls() {
+        assert_eq!(EVIOCRMFF.ty(), b'E');
+        assert_eq!(EVIOCRMFF.nr(), 0x81);
+        assert_eq!(EVIOCGEFFECTS.ty(), b'E');
+        assert_eq!(EVIOCGEFFECTS.nr(), 0x84);
+
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        {
+            assert_eq!(EVIOCSFF.request(), 0x40304580);
+            assert_eq!(EVIOCRMFF.request(), 0x40044581);
+            assert_eq!(EVIOCGEFFECTS.request(), 0x80044584);
+        }
+    }
+}
@@ -0,0 +1,990 @@
+//! Tools for translating `ioctl` definitions from C headers.
+//!
+//! [`parse_ioctl_macro`] turns a line like `#define VIDIOC_QUERYCAP _IOR('V', 0, struct
+//! v4l2_capability)` into its components, which can then be used to build an [`Ioctl`] (once the
+//! size of the argument type is known).
+//! [`emit_rust_binding`] goes the other way, and turns an `ioctl` into the Rust source code of a
+//! binding for it.
+//!
+//! [`assert_matches_c`] and [`assert_golden`] can be used in tests to check that bindings match the
+//! C headers they were translated from.
+
+use std::{error::Error, fmt};
+
+use crate::{_IOC, _IOC_NONE, _IOC_READ, _IOC_READ_WRITE, _IOC_WRITE, Dir, Ioctl, IoctlId, NoArgs};
+
+/// The components of an `ioctl` definition parsed by [`parse_ioctl_macro`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedIoctl {
+    /// The name of the `ioctl`, if the input was a `#define`.
+    pub name: Option<String>,
+    /// The direction of the `ioctl`.
+    pub dir: Dir,
+    /// The `ioctl` type (or group).
+    pub ty: u8,
+    /// The `ioctl` number.
+    pub nr: u8,
+    /// The size of the `ioctl` argument.
+    pub size: ParsedSize,
+}
+
+impl ParsedIoctl {
+    /// Builds an [`Ioctl`] from the parsed components and the given argument size.
+    ///
+    /// Since the size of a C type can't be determined from the header text alone, it has to be
+    /// supplied by the caller (unless [`ParsedIoctl::size`] is [`ParsedSize::Zero`] or
+    /// [`ParsedSize::Bytes`], in which case the argument should match that size).
+    ///
+    /// # Panics
+    ///
+    /// This method may panic when `size` exceeds [`MAX_ARG_SIZE`][crate::MAX_ARG_SIZE].
+    pub fn to_ioctl<T: ?Sized>(&self, size: usize) -> Ioctl<T> {
+        _IOC(self.dir, self.ty, self.nr, size)
+    }
+}
+
+/// The argument size of an `ioctl`, as written in the C header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsedSize {
+    /// The `ioctl` was declared with `_IO`, and has no argument size.
+    Zero,
+    /// The argument size is the size of the named C type.
+    ///
+    /// `_IOR`, `_IOW`, and `_IOWR` take the argument type directly, while `_IOC` needs an explicit
+    /// `sizeof(type)`. Whitespace in the type name is normalized to single spaces, so this will
+    /// contain something like `struct v4l2_capability`.
+    SizeOf(String),
+    /// The argument size was given as an integer literal.
+    Bytes(usize),
+    /// The argument size is an expression that can't be evaluated, like the `len` parameter of a
+    /// polymorphic `ioctl` macro.
+    Expr(String),
+}
+
+/// An error returned by [`parse_ioctl_macro`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The input is not an invocation of `_IO`, `_IOR`, `_IOW`, `_IOWR`, or `_IOC`.
+    NotAnIoctlMacro,
+    /// The macro was invoked with the wrong number of arguments.
+    ArgumentCount {
+        /// The number of arguments the macro takes.
+        expected: usize,
+        /// The number of arguments that were passed.
+        found: usize,
+    },
+    /// The direction passed to `_IOC` is not a combination of the `_IOC_*` constants.
+    InvalidDirection(String),
+    /// The `ioctl` type is not a valid (8-bit) type.
+    InvalidType(String),
+    /// The `ioctl` type is an integer that doesn't fit in the 8 bits reserved for it.
+    ///
+    /// `ioctl`s using an encoding scheme with a wider type can't be built with [`_IOC`], and
+    /// have to use [`Ioctl::from_raw`] instead.
+    TypeOutOfRange(u64),
+    /// The `ioctl` number is not a valid (8-bit) number.
+    InvalidNumber(String),
+    /// The input references a symbol whose value is not known.
+    UnknownSymbol(String),
+    /// The input has unbalanced parentheses or quotes.
+    Syntax,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::NotAnIoctlMacro => {
+                f.write_str("expected an invocation of `_IO`, `_IOR`, `_IOW`, `_IOWR`, or `_IOC`")
+            }
+            ParseError::ArgumentCount { expected, found } => {
+                write!(f, "expected {expected} macro arguments, found {found}")
+            }
+            ParseError::InvalidDirection(s) => write!(f, "invalid `ioctl` direction `{s}`"),
+            ParseError::InvalidType(s) => write!(f, "invalid `ioctl` type `{s}`"),
+            ParseError::TypeOutOfRange(n) => write!(
+                f,
+                "`ioctl` type {n:#x} does not fit in 8 bits (use `Ioctl::from_raw` for encodings \
+                 with wider types)",
+            ),
+            ParseError::InvalidNumber(s) => write!(f, "invalid `ioctl` number `{s}`"),
+            ParseError::UnknownSymbol(s) => write!(f, "unknown symbol `{s}`"),
+            ParseError::Syntax => f.write_str("unbalanced parentheses or quotes"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses an `ioctl` definition written with the C `_IOx` macros.
+///
+/// The input can either be a bare macro invocation like `_IOR('V', 0, struct v4l2_capability)`,
+/// or a complete `#define` line (including function-like macros like
+/// `#define UI_GET_SYSNAME(len) _IOC(...)`). Comments are ignored.
+///
+/// The `ioctl` type and number can be character literals (`'V'`), integer literals (`0xAE`), or
+/// symbols. Symbols are resolved from a small built-in table of `ioctl` groups that are commonly
+/// referenced by name (like `KVMIO`); use [`parse_ioctl_macro_with`] to resolve other symbols.
+///
+/// # Errors
+///
+/// Returns an error if the input is not a (supported) `ioctl` definition, if it references a
+/// symbol that can't be resolved, or if the type or number don't fit in their 8-bit fields
+/// ([`ParseError::TypeOutOfRange`]).
+///
+/// # Examples
+///
+/// ```
+/// use uoctl::{_IOC_READ, header::*};
+///
+/// let parsed = parse_ioctl_macro(
+///     "#define VIDIOC_QUERYCAP		 _IOR('V',  0, struct v4l2_capability)",
+/// )?;
+/// assert_eq!(parsed.name.as_deref(), Some("VIDIOC_QUERYCAP"));
+/// assert_eq!(parsed.dir, _IOC_READ);
+/// assert_eq!(parsed.ty, b'V');
+/// assert_eq!(parsed.nr, 0);
+/// assert_eq!(parsed.size, ParsedSize::SizeOf("struct v4l2_capability".into()));
+/// # Ok::<_, ParseError>(())
+/// ```
+pub fn parse_ioctl_macro(text: &str) -> Result<ParsedIoctl, ParseError> {
+    parse_ioctl_macro_with(text, known_symbol)
+}
+
+/// Parses an `ioctl` definition, resolving symbols with `resolve`.
+///
+/// This works like [`parse_ioctl_macro`], except that symbolic `ioctl` types and numbers are
+/// looked up by calling `resolve` (instead of using the built-in table).
+///
+/// # Example
+///
+/// ```
+/// use uoctl::header::*;
+///
+/// let parsed = parse_ioctl_macro_with(
+///     "#define DMA_BUF_IOCTL_SYNC	_IOW(DMA_BUF_BASE, 0, struct dma_buf_sync)",
+///     |symbol| match symbol {
+///         "DMA_BUF_BASE" => Some(b'b'),
+///         _ => None,
+///     },
+/// )?;
+/// assert_eq!(parsed.ty, b'b');
+/// # Ok::<_, ParseError>(())
+/// ```
+pub fn parse_ioctl_macro_with(
+    text: &str,
+    resolve: impl Fn(&str) -> Option<u8>,
+) -> Result<ParsedIoctl, ParseError> {
+    let text = strip_comments(text)?;
+    let mut rest = text.trim();
+
+    let mut name = None;
+    if let Some(directive) = rest.strip_prefix('#') {
+        let define = directive.trim_start();
+        let Some(define) = define.strip_prefix("define") else {
+            return Err(ParseError::NotAnIoctlMacro);
+        };
+        let define = define.trim_start();
+        let end = define
+            .find(|c: char| !is_ident_char(c))
+            .unwrap_or(define.len());
+        if end == 0 {
+            return Err(ParseError::NotAnIoctlMacro);
+        }
+        name = Some(define[..end].to_string());
+        rest = &define[end..];
+        if rest.starts_with('(') {
+            // Function-like macro; skip the parameter list.
+            let close = rest.find(')').ok_or(ParseError::Syntax)?;
+            rest = &rest[close + 1..];
+        }
+        rest = rest.trim();
+    }
+
+    let rest = strip_parens(rest);
+    let Some(open) = rest.find('(') else {
+        return Err(ParseError::NotAnIoctlMacro);
+    };
+    let macro_name = rest[..open].trim();
+    let Some(inner) = rest[open + 1..].strip_suffix(')') else {
+        return Err(ParseError::Syntax);
+    };
+    let args = split_args(inner)?;
+
+    let check_args = |expected| {
+        if args.len() == expected {
+            Ok(())
+        } else {
+            Err(ParseError::ArgumentCount {
+                expected,
+                found: args.len(),
+            })
+        }
+    };
+    let (dir, size) = match macro_name {
+        "_IO" => {
+            check_args(2)?;
+            (_IOC_NONE, ParsedSize::Zero)
+        }
+        "_IOR" | "_IOW" | "_IOWR" => {
+            check_args(3)?;
+            let dir = match macro_name {
+                "_IOR" => _IOC_READ,
+                "_IOW" => _IOC_WRITE,
+                _ => _IOC_READ_WRITE,
+            };
+            (dir, ParsedSize::SizeOf(normalize_whitespace(args[2])))
+        }
+        "_IOC" => {
+            check_args(4)?;
+            (parse_dir(args[0])?, parse_size(args[3]))
+        }
+        _ => return Err(ParseError::NotAnIoctlMacro),
+    };
+    let (ty, nr) = match macro_name {
+        "_IOC" => (args[1], args[2]),
+        _ => (args[0], args[1]),
+    };
+
+    Ok(ParsedIoctl {
+        name,
+        dir,
+        ty: parse_byte(ty, &resolve).map_err(|e| match e {
+            ParseError::InvalidNumber(s) => match parse_int(&s) {
+                Some(n) if n > 0xff => ParseError::TypeOutOfRange(n),
+                _ => ParseError::InvalidType(s),
+            },
+            e => e,
+        })?,
+        nr: parse_byte(nr, &resolve)?,
+        size,
+    })
+}
+
+/// Generates the Rust source code of a `const` binding for an `ioctl`.
+///
+/// Returns a single line like `pub const NAME: Ioctl<*mut TYPE> = _IOR(0x56, 0x00);`, which uses
+/// [`_IO`], [`_IOR`], [`_IOW`], or [`_IOWR`] depending on the direction of `ioctl`.
+///
+/// Since the argument type can't be derived from the request code, it has to be supplied as
+/// `type_name`, and is used as the pointee type of the argument.
+/// The generated binding derives the argument size from `type_name`, so the type has to have the
+/// size that is encoded in `ioctl` for the binding to produce the same request code.
+/// `type_name` is ignored for `ioctl`s without a direction, which are emitted as [`_IO`].
+///
+/// Request codes that aren't [well-formed][Ioctl::is_well_formed] (like most legacy `ioctl`s) are
+/// emitted as a call to [`Ioctl::from_raw`] with `type_name` as the (direct) argument type.
+///
+/// [`_IO`]: crate::_IO
+/// [`_IOR`]: crate::_IOR
+/// [`_IOW`]: crate::_IOW
+/// [`_IOWR`]: crate::_IOWR
+///
+/// # Example
+///
+/// ```
+/// use uoctl::{header::*, *};
+///
+/// let parsed = parse_ioctl_macro("#define VIDIOC_QUERYCAP _IOR('V',  0, struct v4l2_capability)")?;
+/// let ioctl = parsed.to_ioctl::<NoArgs>(104);
+/// assert_eq!(
+///     emit_rust_binding("VIDIOC_QUERYCAP", ioctl.id(), "v4l2_capability"),
+///     "pub const VIDIOC_QUERYCAP: Ioctl<*mut v4l2_capability> = _IOR(0x56, 0x00);",
+/// );
+/// # Ok::<_, ParseError>(())
+/// ```
+pub fn emit_rust_binding(name: &str, ioctl: IoctlId, type_name: &str) -> String {
+    let request = ioctl.request();
+    let ioctl = Ioctl::<NoArgs>::from_raw(request);
+    let (ty, nr) = (ioctl.ty(), ioctl.nr());
+    if !ioctl.is_well_formed() {
+        return format!("pub const {name}: Ioctl<{type_name}> = Ioctl::from_raw({request:#x});");
+    }
+
+    let dir = ioctl.dir();
+    if dir == _IOC_NONE {
+        return format!("pub const {name}: Ioctl = _IO({ty:#04x}, {nr:#04x});");
+    }
+    let (arg, ctor) = if dir == _IOC_READ {
+        ("*mut", "_IOR")
+    } else if dir == _IOC_WRITE {
+        ("*const", "_IOW")
+    } else {
+        ("*mut", "_IOWR")
+    };
+    format!("pub const {name}: Ioctl<{arg} {type_name}> = {ctor}({ty:#04x}, {nr:#04x});")
+}
+
+/// Asserts that the request code `ioctl` encodes the given direction, type, number, and size.
+///
+/// This is meant to be used in tests, to check that a binding matches the values declared in the C
+/// header.
+///
+/// # Panics
+///
+/// Panics if any of the fields differ. The panic message lists every field that differs.
+///
+/// # Example
+///
+/// From `linux/videodev2.h`:
+///
+/// ```c
+/// #define VIDIOC_QUERYCAP		 _IOR('V',  0, struct v4l2_capability)
+/// ```
+///
+/// ```
+/// use uoctl::{header::assert_matches_c, *};
+///
+/// # type v4l2_capability = [u8; 104];
+/// const VIDIOC_QUERYCAP: Ioctl<*mut v4l2_capability> = _IOR(b'V', 0);
+///
+/// assert_matches_c(VIDIOC_QUERYCAP.request(), _IOC_READ, b'V', 0, 104);
+/// ```
+#[track_caller]
+pub fn assert_matches_c(ioctl: u32, dir: Dir, ty: u8, nr: u8, size: usize) {
+    let mismatches = field_mismatches(ioctl, dir, ty, nr, size);
+    if !mismatches.is_empty() {
+        panic!(
+            "`ioctl` {ioctl:#x} does not match its C definition:\n{}",
+            mismatches.join("\n"),
+        );
+    }
+}
+
+/// Asserts that the `ioctl`s listed in a golden fixture have the expected request codes.
+///
+/// `fixture` contains one `NAME = 0xNNNNNNNN` line per `ioctl`, where the request codes have been
+/// extracted from the C headers (for example, by compiling and running a C program that prints
+/// them).
+/// Empty lines and lines starting with `#` are ignored.
+///
+/// `lookup` is called with each name, and should return the request code of the binding with
+/// that name, or [`None`] if there is no such binding.
+///
+/// Since request codes differ between architectures, a fixture is only valid for the
+/// [`Encoding`][crate::Encoding] it was generated with.
+///
+/// # Panics
+///
+/// Panics if `fixture` is malformed, if `lookup` returns [`None`], or if any request code doesn't
+/// match the fixture.
+/// The panic message lists every `ioctl` that doesn't match, along with the fields that differ.
+///
+/// # Example
+///
+/// ```
+/// use uoctl::{header::assert_golden, *};
+///
+/// const KVMIO: u8 = 0xAE;
+/// const KVM_GET_API_VERSION: Ioctl = _IO(KVMIO, 0x00);
+/// const KVM_CREATE_VM: Ioctl = _IO(KVMIO, 0x01);
+///
+/// # if uoctl::encoding() != Encoding::LinuxGeneric { return; }
+/// let fixture = "
+///     KVM_GET_API_VERSION = 0x0000ae00
+///     KVM_CREATE_VM = 0x0000ae01
+/// ";
+/// assert_golden(fixture, |name| match name {
+///     "KVM_GET_API_VERSION" => Some(KVM_GET_API_VERSION.request()),
+///     "KVM_CREATE_VM" => Some(KVM_CREATE_VM.request()),
+///     _ => None,
+/// });
+/// ```
+#[track_caller]
+pub fn assert_golden(fixture: &str, lookup: impl Fn(&str) -> Option<u32>) {
+    let mut failures = Vec::new();
+    for (i, line) in fixture.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parsed = line.split_once('=').and_then(|(name, code)| {
+            let code = code.trim();
+            let hex = code
+                .strip_prefix("0x")
+                .or_else(|| code.strip_prefix("0X"))?;
+            Some((name.trim(), u32::from_str_radix(hex, 16).ok()?))
+        });
+        let Some((name, golden)) = parsed else {
+            panic!("malformed golden fixture line {}: `{line}`", i + 1);
+        };
+        let Some(actual) = lookup(name) else {
+            failures.push(format!("{name}: no binding found"));
+            continue;
+        };
+
+        let (dir, ty, nr, size) = Ioctl::<NoArgs>::from_raw(golden).as_tuple();
+        let mismatches = field_mismatches(actual, Dir(dir), ty, nr, size as usize);
+        if actual != golden {
+            failures.push(format!(
+                "{name}: expected {golden:#010x}, found {actual:#010x}\n{}",
+                mismatches.join("\n"),
+            ));
+        }
+    }
+    if !failures.is_empty() {
+        panic!(
+            "{} `ioctl`(s) don't match the golden fixture:\n{}",
+            failures.len(),
+            failures.join("\n"),
+        );
+    }
+}
+
+/// Returns a description of every field of `ioctl` that doesn't match the expected value.
+fn field_mismatches(ioctl: u32, dir: Dir, ty: u8, nr: u8, size: usize) -> Vec<String> {
+    let (actual_dir, actual_ty, actual_nr, actual_size) =
+        Ioctl::<NoArgs>::from_raw(ioctl).as_tuple();
+    let actual_dir = Dir(actual_dir);
+    let actual_size = actual_size as usize;
+
+    let mut mismatches = Vec::new();
+    if actual_dir != dir {
+        mismatches.push(format!("  dir: expected {dir:?}, found {actual_dir:?}"));
+    }
+    if actual_ty != ty {
+        mismatches.push(format!(
+            "  type: expected {ty:#04x}, found {actual_ty:#04x}"
+        ));
+    }
+    if actual_nr != nr {
+        mismatches.push(format!("  nr: expected {nr:#04x}, found {actual_nr:#04x}"));
+    }
+    if actual_size != size {
+        mismatches.push(format!("  size: expected {size}, found {actual_size}"));
+    }
+    mismatches
+}
+
+/// `ioctl` groups that are commonly referenced by name.
+fn known_symbol(symbol: &str) -> Option<u8> {
+    Some(match symbol {
+        "KVMIO" => 0xAE,
+        "UINPUT_IOCTL_BASE" => b'U',
+        "SECCOMP_IOC_MAGIC" => b'!',
+        "VFIO_TYPE" => b';',
+        "DRM_IOCTL_BASE" => b'd',
+        "DMA_BUF_BASE" => b'b',
+        "BTRFS_IOCTL_MAGIC" => 0x94,
+        "UFFDIO" => 0xAA,
+        _ => return None,
+    })
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Removes C and C++-style comments (outside of character and string literals).
+fn strip_comments(text: &str) -> Result<String, ParseError> {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prev == '*' => break,
+                        Some(c) => prev = c,
+                        None => return Err(ParseError::Syntax),
+                    }
+                }
+                out.push(' ');
+            }
+            '/' if chars.peek() == Some(&'/') => break,
+            '\'' | '"' => {
+                out.push(c);
+                loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            out.push('\\');
+                            out.extend(chars.next());
+                        }
+                        Some(q) if q == c => {
+                            out.push(q);
+                            break;
+                        }
+                        Some(other) => out.push(other),
+                        None => return Err(ParseError::Syntax),
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// Strips balanced parentheses surrounding `s`.
+fn strip_parens(mut s: &str) -> &str {
+    loop {
+        s = s.trim();
+        match s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+            // Only strip them if they actually belong together (`(a) + (b)` must stay intact).
+            Some(inner) if split_args(inner).is_ok_and(|_| parens_balanced(inner)) => s = inner,
+            _ => return s,
+        }
+    }
+}
+
+fn parens_balanced(s: &str) -> bool {
+    let mut depth = 0usize;
+    let mut in_char = false;
+    let mut escaped = false;
+    for c in s.chars() {
+        if in_char {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '\'' => in_char = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '\'' => in_char = true,
+            '(' => depth += 1,
+            ')' => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => {}
+        }
+    }
+    depth == 0 && !in_char
+}
+
+/// Splits macro arguments at top-level commas.
+fn split_args(s: &str) -> Result<Vec<&str>, ParseError> {
+    let mut args = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    let mut in_char = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        if in_char {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '\'' => in_char = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '\'' => in_char = true,
+            '(' => depth += 1,
+            ')' => depth = depth.checked_sub(1).ok_or(ParseError::Syntax)?,
+            ',' if depth == 0 => {
+                args.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 || in_char {
+        return Err(ParseError::Syntax);
+    }
+    args.push(s[start..].trim());
+    Ok(args)
+}
+
+fn parse_dir(s: &str) -> Result<Dir, ParseError> {
+    let mut read = false;
+    let mut write = false;
+    let mut none = false;
+    for part in s.split('|') {
+        match strip_parens(part) {
+            "_IOC_NONE" | "IOC_VOID" => none = true,
+            "_IOC_READ" | "IOC_OUT" => read = true,
+            "_IOC_WRITE" | "IOC_IN" => write = true,
+            "IOC_INOUT" => (read, write) = (true, true),
+            _ => return Err(ParseError::InvalidDirection(normalize_whitespace(s))),
+        }
+    }
+    Ok(match (none, read, write) {
+        (true, false, false) => _IOC_NONE,
+        (false, true, false) => _IOC_READ,
+        (false, false, true) => _IOC_WRITE,
+        (false, true, true) => _IOC_READ_WRITE,
+        _ => return Err(ParseError::InvalidDirection(normalize_whitespace(s))),
+    })
+}
+
+fn parse_size(s: &str) -> ParsedSize {
+    let s = strip_parens(s);
+    if let Some(ty) = s.strip_prefix("sizeof") {
+        let ty = strip_parens(ty);
+        if !ty.is_empty() && parens_balanced(ty) {
+            return ParsedSize::SizeOf(normalize_whitespace(ty));
+        }
+    }
+    match parse_int(s) {
+        Some(n) => match usize::try_from(n) {
+            Ok(n) => ParsedSize::Bytes(n),
+            Err(_) => ParsedSize::Expr(normalize_whitespace(s)),
+        },
+        None => ParsedSize::Expr(normalize_whitespace(s)),
+    }
+}
+
+/// Parses an 8-bit `ioctl` type or number.
+fn parse_byte(s: &str, resolve: &impl Fn(&str) -> Option<u8>) -> Result<u8, ParseError> {
+    let s = strip_parens(s);
+    let invalid = || ParseError::InvalidNumber(normalize_whitespace(s));
+    if s.starts_with('\'') {
+        return parse_char(s).ok_or_else(invalid);
+    }
+    if s.starts_with(|c: char| c.is_ascii_digit()) {
+        return parse_int(s)
+            .and_then(|n| u8::try_from(n).ok())
+            .ok_or_else(invalid);
+    }
+    if !s.is_empty() && s.chars().all(is_ident_char) {
+        return resolve(s).ok_or_else(|| ParseError::UnknownSymbol(s.to_string()));
+    }
+    Err(invalid())
+}
+
+/// Parses a C character literal containing a single byte.
+fn parse_char(s: &str) -> Option<u8> {
+    let inner = s.strip_prefix('\'')?.strip_suffix('\'')?;
+    let Some(escape) = inner.strip_prefix('\\') else {
+        let mut chars = inner.chars();
+        let c = chars.next()?;
+        if chars.next().is_some() {
+            // Multi-character constants have an implementation-defined value.
+            return None;
+        }
+        return u8::try_from(c).ok();
+    };
+    match escape {
+        "\\" => Some(b'\\'),
+        "'" => Some(b'\''),
+        "\"" => Some(b'"'),
+        "?" => Some(b'?'),
+        "a" => Some(0x07),
+        "b" => Some(0x08),
+        "f" => Some(0x0C),
+        "n" => Some(b'\n'),
+        "r" => Some(b'\r'),
+        "t" => Some(b'\t'),
+        "v" => Some(0x0B),
+        _ => {
+            if let Some(hex) = escape.strip_prefix('x') {
+                u8::from_str_radix(hex, 16).ok()
+            } else if (1..=3).contains(&escape.len()) {
+                u8::from_str_radix(escape, 8).ok()
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Parses a C integer literal (decimal, hexadecimal, or octal, with an optional suffix).
+fn parse_int(s: &str) -> Option<u64> {
+    let s = s.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if s.len() > 1 && s.starts_with('0') {
+        u64::from_str_radix(&s[1..], 8).ok()
+    } else {
+        s.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[track_caller]
+    fn check(text: &str, name: Option<&str>, dir: Dir, ty: u8, nr: u8, size: ParsedSize) {
+        assert_eq!(
+            parse_ioctl_macro(text),
+            Ok(ParsedIoctl {
+                name: name.map(String::from),
+                dir,
+                ty,
+                nr,
+                size,
+            }),
+        );
+    }
+
+    #[test]
+    fn header_lines() {
+        check(
+            "#define VIDIOC_QUERYCAP		 _IOR('V',  0, struct v4l2_capability)",
+            Some("VIDIOC_QUERYCAP"),
+            _IOC_READ,
+            b'V',
+            0,
+            ParsedSize::SizeOf("struct v4l2_capability".into()),
+        );
+        check(
+            "#define KVM_RUN                   _IO(KVMIO,   0x80)",
+            Some("KVM_RUN"),
+            _IOC_NONE,
+            0xAE,
+            0x80,
+            ParsedSize::Zero,
+        );
+        check(
+            "_IOWR(KVMIO, 0x4b, struct kvm_run)",
+            None,
+            _IOC_READ_WRITE,
+            0xAE,
+            0x4b,
+            ParsedSize::SizeOf("struct kvm_run".into()),
+        );
+        check(
+            "#define UI_DEV_SETUP _IOW(UINPUT_IOCTL_BASE, 3, struct uinput_setup)",
+            Some("UI_DEV_SETUP"),
+            _IOC_WRITE,
+            b'U',
+            3,
+            ParsedSize::SizeOf("struct uinput_setup".into()),
+        );
+        check(
+            "#define RNDGETENTCNT	_IOR( 'R', 0x00, int )",
+            Some("RNDGETENTCNT"),
+            _IOC_READ,
+            b'R',
+            0,
+            ParsedSize::SizeOf("int".into()),
+        );
+        check(
+            "#define EVIOCGKEY(len)		_IOC(_IOC_READ, 'E', 0x18, len)		/* get global key state */",
+            Some("EVIOCGKEY"),
+            _IOC_READ,
+            b'E',
+            0x18,
+            ParsedSize::Expr("len".into()),
+        );
+        check(
+            "#define UI_GET_SYSNAME(len)	_IOC(_IOC_READ, UINPUT_IOCTL_BASE, 44, len)",
+            Some("UI_GET_SYSNAME"),
+            _IOC_READ,
+            b'U',
+            44,
+            ParsedSize::Expr("len".into()),
+        );
+        check(
+            "#define EVIOCGMASK		_IOR('E', 0x92, struct input_mask)	/* Get event-masks */",
+            Some("EVIOCGMASK"),
+            _IOC_READ,
+            b'E',
+            0x92,
+            ParsedSize::SizeOf("struct input_mask".into()),
+        );
+        check(
+            "#define JSIOCGAXMAP		_IOR('j', 0x32, __u8[ABS_CNT])		/* get axis mapping */",
+            Some("JSIOCGAXMAP"),
+            _IOC_READ,
+            b'j',
+            0x32,
+            ParsedSize::SizeOf("__u8[ABS_CNT]".into()),
+        );
+        check(
+            "_IOC(_IOC_READ|_IOC_WRITE, 'H', 0x07, sizeof(struct hidraw_report_descriptor))",
+            None,
+            _IOC_READ_WRITE,
+            b'H',
+            0x07,
+            ParsedSize::SizeOf("struct hidraw_report_descriptor".into()),
+        );
+        check(
+            "_IOC(IOC_VOID, 't', 10, 4)",
+            None,
+            _IOC_NONE,
+            b't',
+            10,
+            ParsedSize::Bytes(4),
+        );
+    }
+
+    #[test]
+    fn char_literals() {
+        let ty = |text| parse_ioctl_macro(text).map(|p| p.ty);
+        assert_eq!(ty("_IO('\\x42', 0)"), Ok(0x42));
+        assert_eq!(ty("_IO('\\'', 0)"), Ok(b'\''));
+        assert_eq!(ty("_IO('\\\\', 0)"), Ok(b'\\'));
+        assert_eq!(ty("_IO('\\0', 0)"), Ok(0));
+        assert_eq!(ty("_IO('\\177', 0)"), Ok(0o177));
+        assert_eq!(ty("_IO(',', 0)"), Ok(b','));
+        assert_eq!(ty("_IO(')', 0)"), Ok(b')'));
+        assert_eq!(ty("_IO((';'), 0)"), Ok(b';'));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_ioctl_macro(
+                "#define SECCOMP_IOCTL_NOTIF_RECV	SECCOMP_IOWR(0, struct seccomp_notif)"
+            ),
+            Err(ParseError::NotAnIoctlMacro),
+        );
+        assert_eq!(
+            parse_ioctl_macro("#define FIONREAD	0x541B"),
+            Err(ParseError::NotAnIoctlMacro),
+        );
+        assert_eq!(
+            parse_ioctl_macro("_IOR('V', 0)"),
+            Err(ParseError::ArgumentCount {
+                expected: 3,
+                found: 2,
+            }),
+        );
+        assert_eq!(
+            parse_ioctl_macro("_IO(FOO_MAGIC, 0)"),
+            Err(ParseError::UnknownSymbol("FOO_MAGIC".into())),
+        );
+        assert_eq!(
+            parse_ioctl_macro("_IO('V', 0x100)"),
+            Err(ParseError::InvalidNumber("0x100".into())),
+        );
+        assert_eq!(
+            parse_ioctl_macro("_IO(0x1234, 0)"),
+            Err(ParseError::TypeOutOfRange(0x1234)),
+        );
+        assert_eq!(
+            ParseError::TypeOutOfRange(0x1234).to_string(),
+            "`ioctl` type 0x1234 does not fit in 8 bits (use `Ioctl::from_raw` for encodings with \
+             wider types)",
+        );
+        assert_eq!(
+            parse_ioctl_macro("_IO('VV', 0)"),
+            Err(ParseError::InvalidType("'VV'".into())),
+        );
+        assert_eq!(
+            parse_ioctl_macro("_IOC(_IOC_NONE|_IOC_READ, 'V', 0, 0)"),
+            Err(ParseError::InvalidDirection("_IOC_NONE|_IOC_READ".into())),
+        );
+        assert_eq!(
+            parse_ioctl_macro("_IOR('V', 0, struct foo"),
+            Err(ParseError::Syntax),
+        );
+    }
+
+    #[test]
+    fn to_ioctl() {
+        let parsed = parse_ioctl_macro("_IOR('R', 0x00, int)").unwrap();
+        assert_eq!(
+            parsed.to_ioctl::<*mut i32>(4).request(),
+            crate::_IOR::<i32>(b'R', 0x00).request(),
+        );
+    }
+
+    #[test]
+    fn emit() {
+        assert_eq!(
+            emit_rust_binding("KVM_RUN", crate::_IO(0xAE, 0x80).id(), "unused"),
+            "pub const KVM_RUN: Ioctl = _IO(0xae, 0x80);",
+        );
+        assert_eq!(
+            emit_rust_binding("RNDGETENTCNT", crate::_IOR::<i32>(b'R', 0x00).id(), "c_int"),
+            "pub const RNDGETENTCNT: Ioctl<*mut c_int> = _IOR(0x52, 0x00);",
+        );
+        assert_eq!(
+            emit_rust_binding(
+                "RNDADDTOENTCNT",
+                crate::_IOW::<i32>(b'R', 0x01).id(),
+                "c_int"
+            ),
+            "pub const RNDADDTOENTCNT: Ioctl<*const c_int> = _IOW(0x52, 0x01);",
+        );
+        assert_eq!(
+            emit_rust_binding("FOO", crate::_IOWR::<[u8; 16]>(b'x', 0x20).id(), "[u8; 16]"),
+            "pub const FOO: Ioctl<*mut [u8; 16]> = _IOWR(0x78, 0x20);",
+        );
+        assert_eq!(
+            emit_rust_binding(
+                "BOGUS",
+                crate::_IOC::<NoArgs>(_IOC_NONE, b'x', 0x20, 8).id(),
+                "u64",
+            ),
+            format!(
+                "pub const BOGUS: Ioctl<u64> = Ioctl::from_raw({:#x});",
+                crate::_IOC::<NoArgs>(_IOC_NONE, b'x', 0x20, 8).request(),
+            ),
+        );
+    }
+
+    #[test]
+    fn emit_round_trip() {
+        let parsed = parse_ioctl_macro("#define RNDGETENTCNT _IOR( 'R', 0x00, int )").unwrap();
+        let ioctl = parsed.to_ioctl::<NoArgs>(4);
+        let binding = emit_rust_binding(parsed.name.as_deref().unwrap(), ioctl.id(), "c_int");
+        assert_eq!(
+            binding,
+            "pub const RNDGETENTCNT: Ioctl<*mut c_int> = _IOR(0x52, 0x00);",
+        );
+    }
+
+    #[test]
+    fn matches_c() {
+        assert_matches_c(
+            crate::_IOR::<u32>(b'V', 0x10).request(),
+            _IOC_READ,
+            b'V',
+            0x10,
+            4,
+        );
+        assert_matches_c(crate::_IO(0xAE, 0x01).request(), _IOC_NONE, 0xAE, 0x01, 0);
+    }
+
+    #[test]
+    #[should_panic = "  dir: expected _IOC_WRITE, found _IOC_READ\n  size: expected 8, found 4"]
+    fn matches_c_mismatch() {
+        assert_matches_c(
+            crate::_IOR::<u32>(b'V', 0x10).request(),
+            _IOC_WRITE,
+            b'V',
+            0x10,
+            8,
+        );
+    }
+
+    #[test]
+    fn golden() {
+        let ioctl = crate::_IOWR::<u64>(b'x', 0x20);
+        let fixture = format!(
+            "# comment\n\nFOO = {:#010x}\n  BAR={:#x}  \n",
+            ioctl.request(),
+            0x5401
+        );
+        assert_golden(&fixture, |name| match name {
+            "FOO" => Some(ioctl.request()),
+            "BAR" => Some(0x5401),
+            _ => None,
+        });
+    }
+
+    #[test]
+    #[should_panic = "FOO: no binding found"]
+    fn golden_missing() {
+        assert_golden("FOO = 0x5401", |_| None);
+    }
+
+    #[test]
+    #[should_panic = "malformed golden fixture line 2: `FOO 0x5401`"]
+    fn golden_malformed() {
+        assert_golden("\nFOO 0x5401", |_| Some(0x5401));
+    }
+
+    #[test]
+    #[should_panic = "  nr: expected 0x21, found 0x20"]
+    fn golden_mismatch() {
+        let fixture = format!("FOO = {:#x}", crate::_IOWR::<u64>(b'x', 0x21).request());
+        assert_golden(&fixture, |_| {
+            Some(crate::_IOWR::<u64>(b'x', 0x20).request())
+        });
+    }
+}
@@ -0,0 +1,96 @@
+//! `ioctl`s for the legacy joystick interface (`/dev/input/js*`).
+//!
+//! New code should generally prefer the [`evdev`][crate::evdev] interface, which exposes the same
+//! devices in more detail.
+//!
+//! From `linux/joystick.h`:
+//!
+//! ```c
+//! #define JSIOCGVERSION		_IOR('j', 0x01, __u32)				/* get driver version */
+//!
+//! #define JSIOCGAXES		_IOR('j', 0x11, __u8)				/* get number of axes */
+//! #define JSIOCGBUTTONS		_IOR('j', 0x12, __u8)				/* get number of buttons */
+//! #define JSIOCGNAME(len)		_IOC(_IOC_READ, 'j', 0x13, len)			/* get identifier string */
+//! ```
+//!
+//! # Example
+//!
+#![cfg_attr(feature = "std", doc = "```no_run")]
+#![cfg_attr(not(feature = "std"), doc = "```ignore")]
+//! use std::fs::File;
+//! use uoctl::joystick::*;
+//!
+//! let js = File::open("/dev/input/js0")?;
+//!
+//! let mut name = [0u8; 128];
+//! let len = unsafe { JSIOCGNAME(name.len()).ioctl(&js, name.as_mut_ptr())? };
+//! let name = String::from_utf8_lossy(&name[..len as usize]);
+//!
+//! let (mut axes, mut buttons) = (0, 0);
+//! unsafe {
+//!     JSIOCGAXES.ioctl(&js, &mut axes)?;
+//!     JSIOCGBUTTONS.ioctl(&js, &mut buttons)?;
+//! }
+//! println!("{name}: {axes} axes, {buttons} buttons");
+//! # std::io::Result::Ok(())
+//! ```
+
+#![allow(non_snake_case)]
+
+use crate::{_IOC, _IOC_READ, _IOR, Ioctl};
+
+/// Reads the version of the joystick driver.
+pub const JSIOCGVERSION: Ioctl<*mut u32> = _IOR(b'j', 0x01);
+
+/// Reads the number of axes of the joystick.
+pub const JSIOCGAXES: Ioctl<*mut u8> = _IOR(b'j', 0x11);
+
+/// Reads the number of buttons of the joystick.
+pub const JSIOCGBUTTONS: Ioctl<*mut u8> = _IOR(b'j', 0x12);
+
+/// Reads the name of the joystick into a buffer of `len` bytes.
+///
+/// Returns the number of bytes written to the buffer, including the terminating NUL byte if it
+/// fit.
+///
+/// # Panics
+///
+/// This function may panic when `len` exceeds [`MAX_ARG_SIZE`][crate::MAX_ARG_SIZE].
+pub const fn JSIOCGNAME(len: usize) -> Ioctl<*mut u8> {
+    _IOC(_IOC_READ, b'j', 0x13, len)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    #[test]
+    fn codes() {
+        assert_eq!(JSIOCGNAME(128).ty(), b'j');
+        assert_eq!(JSIOCGNAME(128).nr(), 0x13);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(JSIOCGVERSION.request(), 0x80046a01);
+            assert_eq!(JSIOCGAXES.request(), 0x80016a11);
+            assert_eq!(JSIOCGBUTTONS.request(), 0x80016a12);
+            assert_eq!(JSIOCGNAME(128).request(), 0x80806a13);
+        }
+    }
+
+    #[test]
+    fn read_axes() {
+        // Only run when a joystick is connected.
+        let Ok(js) = File::open("/dev/input/js0") else {
+            return;
+        };
+
+        let mut axes = 0;
+        unsafe { JSIOCGAXES.ioctl(&js, &mut axes).unwrap() };
+        let mut version = 0;
+        unsafe { JSIOCGVERSION.ioctl(&js, &mut version).unwrap() };
+        assert_ne!(version, 0);
+    }
+}
@@ -25,8 +25,8 @@
 //! #define VIDIOC_QUERYCAP		 _IOR('V',  0, struct v4l2_capability)
 //! ```
 //!
-//! ```no_run
-//! use std::mem::MaybeUninit;
+#![cfg_attr(feature = "std", doc = "```no_run")]
+#![cfg_attr(not(feature = "std"), doc = "```ignore")]
 //! use uoctl::*;
 //!
 //! #[repr(C)]
@@ -45,11 +45,7 @@
 //! // Use as follows:
 //!
 //! # let fd = 123;
-//! let capability = unsafe {
-//!     let mut capability = MaybeUninit::uninit();
-//!     VIDIOC_QUERYCAP.ioctl(&fd, capability.as_mut_ptr())?;
-//!     capability.assume_init()
-//! };
+//! let capability = unsafe { VIDIOC_QUERYCAP.read(&fd)? };
 //! # std::io::Result::Ok(())
 //! ```
 //!
@@ -61,6 +57,17 @@
 //!
 //! For example, FreeBSD implements a variety of compatible interfaces like *evdev* and *V4L2*.
 //!
+//! Both the Linux and the BSD encoding reserve 8 bits for the `ioctl` type (or group) and number,
+//! so both are passed as a [`u8`].
+//! Request codes of encoding schemes that use a wider group (or that don't follow these schemes at
+//! all) can still be used by computing them manually and passing them to [`Ioctl::from_raw`].
+//!
+//! # `no_std` support
+//!
+//! Disabling the default `std` feature makes this library `no_std`.
+//! [`Ioctl`], [`Dir`], and the functions for building and decoding request codes remain available,
+//! but all methods that perform an `ioctl` (and the helpers built on them) require `std`.
+//!
 //! # Safety
 //!
 //! To safely perform an `ioctl`, the actual behavior of the kernel-side has to match the behavior
@@ -101,6 +108,16 @@
 //!
 //! ***TL;DR**: don't worry about it kitten :)*
 
+#![cfg_attr(not(feature = "std"), no_std)]
+// The C header excerpts in the documentation are copied verbatim, tabs included.
+#![allow(clippy::tabs_in_doc_comments)]
+
+// The unit tests use `std` even when the `std` feature is disabled.
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+
+// The README examples perform `ioctl`s, which requires the `std` feature.
+#[cfg(feature = "std")]
 #[doc = include_str!("../README.md")]
 mod readme {}
 
@@ -122,7 +139,73 @@ mod platform;
 #[path = "platform/bsd.rs"]
 mod platform;
 
-use std::{ffi::c_int, fmt, io, marker::PhantomData, ops::BitOr, os::fd::AsRawFd};
+#[cfg(feature = "std")]
+mod affine;
+#[cfg(feature = "std")]
+mod arg;
+#[cfg(feature = "std")]
+mod backend;
+mod macros;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "mock")]
+mod mock;
+#[cfg(feature = "std")]
+mod resource;
+
+#[cfg(feature = "std")]
+pub use affine::ThreadAffineFd;
+#[cfg(feature = "std")]
+pub use arg::{
+    IoctlUnion, SubcommandIoctl, TaggedUnion, UnionVariant, VersionedIoctl, adopt_fds,
+    negotiate_version,
+};
+#[cfg(feature = "std")]
+pub use backend::{IoctlBackend, LibcBackend};
+#[doc(hidden)]
+pub use macros::__private;
+#[cfg(feature = "std")]
+pub use resource::IoctlResource;
+
+#[cfg(feature = "mmap")]
+pub use mmap::MappedBuffer;
+#[cfg(feature = "mock")]
+pub use mock::MockFd;
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod cdrom;
+pub mod evdev;
+#[cfg(feature = "std")]
+pub mod header;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod joystick;
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "android")))]
+pub mod net;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod nvme;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod pidfd;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod ppp;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub mod seccomp;
+#[cfg(all(feature = "std", any(target_os = "linux", target_os = "android")))]
+pub mod uinput;
+
+use core::{
+    borrow::Borrow, cmp::Ordering, error::Error, ffi::c_int, fmt, hash, marker::PhantomData,
+    ops::BitOr,
+};
+#[cfg(feature = "std")]
+use std::{
+    ffi::{c_short, c_void},
+    io,
+    mem::{self, MaybeUninit},
+    num::NonZero,
+    os::fd::{AsFd, AsRawFd, FromRawFd, OwnedFd, RawFd},
+    ptr::{self, NonNull},
+    time::Duration,
+};
 
 /// An `ioctl`.
 ///
@@ -150,6 +233,118 @@ impl<T: ?Sized> Clone for Ioctl<T> {
     }
 }
 
+impl<T: ?Sized> AsRef<u32> for Ioctl<T> {
+    fn as_ref(&self) -> &u32 {
+        &self.request
+    }
+}
+
+/// Borrows the request code of an [`Ioctl`].
+///
+/// Any comparison or hashing implemented for [`Ioctl`] has to be based on the request code alone,
+/// so that it agrees with the one of [`u32`].
+impl<T: ?Sized> Borrow<u32> for Ioctl<T> {
+    fn borrow(&self) -> &u32 {
+        &self.request
+    }
+}
+
+/// Returns the request code of an [`Ioctl`] (like [`Ioctl::request`]).
+impl<T: ?Sized> From<Ioctl<T>> for u32 {
+    fn from(ioctl: Ioctl<T>) -> Self {
+        ioctl.request
+    }
+}
+
+/// Compares the request codes of two [`Ioctl`]s.
+///
+/// Only [`Ioctl`]s with the same argument type can be compared, since comparisons across types
+/// would prevent the argument type from being inferred in expressions like
+/// `ioctl == _IOR(b'V', 0)`.
+/// Use [`Ioctl::id`] to compare `ioctl`s with different argument types.
+impl<T: ?Sized> PartialEq for Ioctl<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.request == other.request
+    }
+}
+
+impl<T: ?Sized> Eq for Ioctl<T> {}
+
+/// Orders [`Ioctl`]s by their raw request code.
+impl<T: ?Sized> PartialOrd for Ioctl<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: ?Sized> Ord for Ioctl<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.request.cmp(&other.request)
+    }
+}
+
+/// Hashes the request code, consistent with the [`Hash`][hash::Hash] implementation of [`u32`].
+///
+/// This allows [`Ioctl`]s to be used as keys in a `HashMap<u32, _>` via [`Borrow<u32>`].
+impl<T: ?Sized> hash::Hash for Ioctl<T> {
+    #[inline]
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.request.hash(state);
+    }
+}
+
+/// Shows the decoded fields of the request code, along with the raw value.
+///
+/// The `ioctl` type is shown as a character if it is printable ASCII.
+///
+/// # Example
+///
+/// ```
+/// use uoctl::*;
+///
+/// const VIDIOC_QUERYCAP: Ioctl<*mut [u8; 104]> = _IOR(b'V', 0);
+///
+/// # if uoctl::encoding() == Encoding::LinuxGeneric {
+/// assert_eq!(
+///     format!("{VIDIOC_QUERYCAP:?}"),
+///     "Ioctl { dir: _IOC_READ, ty: 'V' (0x56), nr: 0, size: 104, request: 0x80685600 }",
+/// );
+/// # }
+/// ```
+impl<T: ?Sized> fmt::Debug for Ioctl<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Decoded { dir, ty, nr, size } = self.decode();
+        let mut s = f.debug_struct("Ioctl");
+        s.field("dir", &dir);
+        if ty.is_ascii_graphic() {
+            s.field("ty", &format_args!("{:?} ({ty:#04x})", ty as char));
+        } else {
+            s.field("ty", &format_args!("{ty:#04x}"));
+        }
+        s.field("nr", &nr)
+            .field("size", &size)
+            .field("request", &format_args!("{:#x}", self.request))
+            .finish()
+    }
+}
+
+/// Formats the request code in lowercase hexadecimal.
+impl<T: ?Sized> fmt::LowerHex for Ioctl<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.request, f)
+    }
+}
+
+/// Formats the request code in uppercase hexadecimal.
+impl<T: ?Sized> fmt::UpperHex for Ioctl<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.request, f)
+    }
+}
+
 impl<T: ?Sized> Ioctl<T> {
     /// Creates an [`Ioctl`] object from a raw request code and an arbitrary argument type.
     ///
@@ -177,7 +372,8 @@ impl<T: ?Sized> Ioctl<T> {
     ///     ...
     /// ```
     ///
-    /// ```
+    #[cfg_attr(feature = "std", doc = "```")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
     /// use std::io;
     /// use std::fs::File;
     /// use std::ffi::c_int;
@@ -202,6 +398,27 @@ impl<T: ?Sized> Ioctl<T> {
         }
     }
 
+    /// Creates an [`Ioctl`] from its direction, type, number, and argument size.
+    ///
+    /// This is equivalent to [`_IOC`], but can be more readable where the [`Ioctl`] type is
+    /// already spelled out.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `size` exceeds [`MAX_ARG_SIZE`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// let ioctl = Ioctl::<*mut [u8; 104]>::from_parts(_IOC_READ, b'V', 0, 104);
+    /// assert_eq!(ioctl, _IOR(b'V', 0));
+    /// ```
+    pub const fn from_parts(dir: Dir, ty: u8, nr: u8, size: usize) -> Self {
+        _IOC(dir, ty, nr, size)
+    }
+
     /// Changes the `ioctl` argument type to `T2`.
     ///
     /// This can be used for `ioctl`s that incorrectly declare their type, or for `ioctl`s that take
@@ -224,7 +441,8 @@ impl<T: ?Sized> Ioctl<T> {
     /// #define KVM_CREATE_VM             _IO(KVMIO,   0x01) /* returns a VM fd */
     /// ```
     ///
-    /// ```no_run
+    #[cfg_attr(feature = "std", doc = "```no_run")]
+    #[cfg_attr(not(feature = "std"), doc = "```ignore")]
     /// use std::fs::File;
     /// use std::ffi::c_int;
     /// use uoctl::*;
@@ -238,10 +456,8 @@ impl<T: ?Sized> Ioctl<T> {
     ///
     /// let file = File::open("/dev/kvm")?;
     ///
-    /// let vm_fd = unsafe { KVM_CREATE_VM.ioctl(&file, vm_type)? };
-    /// println!("created new VM with file descriptor {vm_fd}");
-    ///
-    /// unsafe { libc::close(vm_fd) };
+    /// let vm = unsafe { KVM_CREATE_VM.ioctl_owned_fd(&file, vm_type)? };
+    /// println!("created new VM: {vm:?}");
     /// # std::io::Result::Ok(())
     /// ```
     pub const fn cast_arg<T2>(self) -> Ioctl<T2> {
@@ -251,6 +467,50 @@ impl<T: ?Sized> Ioctl<T> {
         }
     }
 
+    /// Changes the `ioctl` argument type to the pointer type `P`, checking that the size of its
+    /// pointee matches the size encoded in the request code.
+    ///
+    /// [`Ioctl::cast_arg`] can change the argument type arbitrarily, so a typo can easily result in
+    /// an [`Ioctl`] whose argument type doesn't match the request code, which the kernel will then
+    /// access out of bounds.
+    /// This method can be used instead, when the new argument type is a pointer.
+    /// Arguments passed directly (rather than behind a pointer) aren't described by the size in
+    /// the request code, so they have to use [`Ioctl::cast_arg`].
+    ///
+    /// Note that legacy `ioctl`s created with [`Ioctl::from_raw`] often don't encode any size at
+    /// all, and will always fail this check.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SizeMismatch`] error if the size of `P`'s pointee differs from the size encoded
+    /// in the request code.
+    ///
+    /// # Example
+    ///
+    /// Since this is a `const fn`, it can also check the size at compile time:
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// const RNDGETENTCNT_RAW: Ioctl<*mut u8> = _IOC(_IOC_READ, b'R', 0x00, 4);
+    /// const RNDGETENTCNT: Ioctl<*mut i32> = match RNDGETENTCNT_RAW.try_cast_arg() {
+    ///     Ok(ioctl) => ioctl,
+    ///     Err(_) => panic!("wrong argument size"),
+    /// };
+    ///
+    /// let err = RNDGETENTCNT_RAW.try_cast_arg::<*mut u64>().err().unwrap();
+    /// assert_eq!(err, SizeMismatch { encoded: 4, actual: 8 });
+    /// ```
+    pub const fn try_cast_arg<P: IoctlPointer>(self) -> Result<Ioctl<P>, SizeMismatch> {
+        let encoded = self.size();
+        let actual = size_of::<P::Pointee>();
+        if encoded == actual {
+            Ok(self.cast_arg())
+        } else {
+            Err(SizeMismatch { encoded, actual })
+        }
+    }
+
     /// Returns the `ioctl` request code.
     ///
     /// This is passed to `ioctl(2)` as its second argument.
@@ -265,580 +525,5019 @@ impl<T: ?Sized> Ioctl<T> {
     pub const fn request(self) -> u32 {
         self.request
     }
-}
 
-impl<T> Ioctl<*const T> {
-    /// Changes the [`Ioctl`] argument type to be passed directly instead of behind a pointer.
+    /// Returns the `ioctl` type (or group) encoded in the request code.
     ///
-    /// Does not change the request code.
+    /// This is the `ty` argument that was passed to [`_IOC`], [`_IOR`], etc.
     ///
-    /// Many linux headers define `ioctl`s like `_IOW('U', 100, int)`, but then expect the `int`
-    /// argument to be passed as a direct argument to `ioctl(2)` instead of passing a pointer.
-    /// This method can be used to bind to these `ioctl`s.
+    /// For legacy `ioctl`s created with [`Ioctl::from_raw`], this returns whatever happens to be
+    /// stored in the bits of the request code that are used for the type.
+    pub const fn ty(self) -> u8 {
+        platform::_IOC_TYPE(self.request) as u8
+    }
+
+    /// Returns the `ioctl` type (or group) encoded in the request code as a [`char`].
+    ///
+    /// Most `ioctl` groups are ASCII characters (like `'V'` for V4L2), so this is useful for
+    /// diagnostics. Types that aren't ASCII are converted as Latin-1 (like `ty() as char`).
     ///
     /// # Example
     ///
-    /// `uinput` defines several `ioctl`s where this method is useful:
+    /// ```
+    /// use uoctl::*;
     ///
-    /// ```c
-    /// #define UI_SET_EVBIT		_IOW(UINPUT_IOCTL_BASE, 100, int)
+    /// assert_eq!(_IO(b'V', 0).group_char(), 'V');
     /// ```
+    pub const fn group_char(self) -> char {
+        self.ty() as char
+    }
+
+    /// Returns the `ioctl` number encoded in the request code.
+    ///
+    /// This is the `nr` argument that was passed to [`_IOC`], [`_IOR`], etc.
+    ///
+    /// For legacy `ioctl`s created with [`Ioctl::from_raw`], this returns whatever happens to be
+    /// stored in the bits of the request code that are used for the number.
+    pub const fn nr(self) -> u8 {
+        platform::_IOC_NR(self.request) as u8
+    }
+
+    /// Returns the direction encoded in the request code.
+    ///
+    /// This is the direction that was passed to [`_IOC`], or implied by [`_IO`], [`_IOR`], etc.
+    ///
+    /// For legacy `ioctl`s created with [`Ioctl::from_raw`], this returns whatever happens to be
+    /// stored in the bits of the request code that are used for the direction, which may not be a
+    /// valid combination of the `_IOC_*` constants.
+    ///
+    /// # Example
     ///
     /// ```
-    /// use std::ffi::c_int;
-    /// use uoctl::{Ioctl, _IOW};
+    /// use uoctl::*;
     ///
-    /// const UI_SET_EVBIT: Ioctl<c_int> = _IOW(b'U', 100).with_direct_arg();
+    /// assert_eq!(_IOR::<u32>(b'V', 0).dir(), _IOC_READ);
+    /// assert_eq!(_IOWR::<u32>(b'V', 0).dir(), _IOC_READ | _IOC_WRITE);
+    /// assert_eq!(_IO(b'V', 0).dir(), _IOC_NONE);
     /// ```
-    #[inline]
-    pub const fn with_direct_arg(self) -> Ioctl<T> {
-        self.cast_arg()
+    pub const fn dir(self) -> Dir {
+        Dir(platform::_IOC_DIR(self.request))
     }
 
-    /// Casts the [`Ioctl`] so that it takes a `*mut` pointer instead of a `*const` pointer.
+    /// Returns the argument size encoded in the request code.
     ///
-    /// This can be used to fix an `ioctl` that is incorrectly declared as only *reading* its
-    /// argument through the pointer (with [`_IOW`]), when in reality the kernel can *write* through
-    /// the pointer as well.
+    /// This is the size of the type passed to [`_IOR`], [`_IOW`], or [`_IOWR`] (or the `size`
+    /// passed to [`_IOC`]), and 0 for `ioctl`s created with [`_IO`].
     ///
-    /// An ioctl that writes through its pointer argument, but is incorrectly declared as
-    /// [`Ioctl<*const T>`] will generally cause UB when invoked with an immutable reference.
+    /// For legacy `ioctl`s created with [`Ioctl::from_raw`], this returns whatever happens to be
+    /// stored in the bits of the request code that are used for the size.
     ///
-    /// Also see [`Ioctl::cast_const`] for the opposite direction.
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// const VIDIOC_QUERYCAP: Ioctl<*mut [u8; 104]> = _IOR(b'V', 0);
+    ///
+    /// assert_eq!(VIDIOC_QUERYCAP.size(), 104);
+    /// assert_eq!(_IO(b'V', 0).size(), 0);
+    /// ```
+    pub const fn size(self) -> usize {
+        platform::_IOC_SIZE(self.request) as usize
+    }
+
+    /// Returns whether the argument size encoded in the request code is equal to `size_of::<U>()`.
+    ///
+    /// This is a cheap sanity check for bindings that are put together manually (for example, via
+    /// [`Ioctl::from_raw`]), and is meant to be used with [`debug_assert!`] to catch mistakes made
+    /// when transcribing C headers.
+    ///
+    /// `ioctl`s without an argument (created with [`_IO`]) encode a size of 0, as do many legacy
+    /// `ioctl`s, so this check is meaningless for them.
+    /// [`verify_binding!`] performs a more thorough check at compile time.
     ///
     /// # Example
     ///
-    /// The `EVIOCSFF` ioctl from evdev is incorrectly declared with [`_IOW`], but may write to the
-    /// data, as documented here:
+    /// ```
+    /// use uoctl::*;
     ///
-    /// > “request” must be EVIOCSFF.
-    /// >
-    /// > “effect” points to a structure describing the effect to upload. The effect is uploaded, but
-    /// > not played. **The content of effect may be modified.**
+    /// const RNDGETENTCNT: Ioctl<*mut i32> = Ioctl::from_raw(0x80045200);
     ///
-    /// <https://www.kernel.org/doc/html/latest/input/ff.html>
+    /// # if encoding() == Encoding::LinuxGeneric {
+    /// debug_assert!(RNDGETENTCNT.check_size::<i32>());
+    /// assert!(!RNDGETENTCNT.check_size::<i64>());
+    /// # }
+    /// ```
+    pub const fn check_size<U>(self) -> bool {
+        self.size() == size_of::<U>()
+    }
+
+    /// Returns whether the request code uses the 32-bit ("compat") layout of its argument.
+    ///
+    /// 64-bit kernels also handle `ioctl`s issued by 32-bit processes. The bit layout of the
+    /// request code is identical for both, but `ioctl`s whose argument contains pointer- or
+    /// `long`-sized fields encode a different argument size, which is the only way to tell the
+    /// two apart.
+    /// This is useful for shims and emulators that translate `ioctl`s issued by 32-bit processes.
+    ///
+    /// `compat_size` and `native_size` are the sizes of the argument in the 32-bit and 64-bit
+    /// layout, respectively.
+    /// Returns `true` if the encoded size is `compat_size` and differs from `native_size`.
+    /// If both sizes are equal, the layouts are indistinguishable (and identical), so this returns
+    /// `false`.
+    ///
+    /// # Example
+    ///
+    /// `PPPIOCGIDLE` takes a `struct ppp_idle`, which contains two `time_t`s: it is 8 bytes large
+    /// for 32-bit processes, and 16 bytes large for 64-bit processes.
     ///
     /// ```
-    /// use std::ffi::c_void as ff_effect;
-    /// use uoctl::{Ioctl, _IOW};
+    /// use uoctl::*;
     ///
-    /// pub const EVIOCSFF: Ioctl<*mut ff_effect> = _IOW(b'E', 0x80).cast_mut();
+    /// const fn PPPIOCGIDLE(size: usize) -> Ioctl<*mut u8> {
+    ///     _IOC(_IOC_READ, b't', 63, size)
+    /// }
+    ///
+    /// assert!(PPPIOCGIDLE(8).is_compat(8, 16));
+    /// assert!(!PPPIOCGIDLE(16).is_compat(8, 16));
     /// ```
-    #[inline]
-    pub const fn cast_mut(self) -> Ioctl<*mut T> {
-        self.cast_arg()
+    pub const fn is_compat(self, compat_size: usize, native_size: usize) -> bool {
+        self.size() == compat_size && compat_size != native_size
     }
-}
 
-impl<T> Ioctl<*mut T> {
-    /// Casts the [`Ioctl`] so that it takes a `*const` pointer instead of a `*mut` pointer.
+    /// Re-encodes the request code with a new argument size, keeping the direction, type, and
+    /// number.
     ///
-    /// This performs the opposite operation of [`Ioctl::cast_mut`], and can be used when an `ioctl`
-    /// is incorrectly declared as writing to its argument (yielding an [`Ioctl<*mut T>`]) when it
-    /// actually only reads from it.
+    /// This is useful for polymorphic `ioctl`s (like `UI_GET_SYSNAME(len)`), which accept buffers of
+    /// any size: the [`Ioctl`] can be declared once, and the size filled in at the call site.
     ///
-    /// Only use this method if you are sure it is correct! If the `ioctl` *does* write through the
-    /// pointer, the result is likely UB!
-    #[inline]
-    pub const fn cast_const(self) -> Ioctl<*const T> {
-        self.cast_arg()
+    /// # Panics
+    ///
+    /// This method panics if `size` exceeds [`MAX_ARG_SIZE`].
+    ///
+    /// # Example
+    ///
+    /// From `linux/uinput.h`:
+    ///
+    /// ```c
+    /// #define UI_GET_SYSNAME(len)	_IOC(_IOC_READ, UINPUT_IOCTL_BASE, 44, len)
+    /// ```
+    ///
+    /// ```
+    /// use std::ffi::c_char;
+    /// use uoctl::*;
+    ///
+    /// const UI_GET_SYSNAME: Ioctl<*mut c_char> = _IOC(_IOC_READ, b'U', 44, 0);
+    ///
+    /// let ioctl = UI_GET_SYSNAME.with_size(16);
+    /// assert_eq!(ioctl, _IOC(_IOC_READ, b'U', 44, 16));
+    /// assert_eq!(ioctl.size(), 16);
+    /// ```
+    pub const fn with_size(self, size: usize) -> Self {
+        _IOC(self.dir(), self.ty(), self.nr(), size)
     }
-}
 
-impl Ioctl<NoArgs> {
-    /// Performs an `ioctl` that doesn't take an argument.
+    /// Decodes the request code into its direction, type, number, and size.
     ///
-    /// On success, returns the value returned by the `ioctl` syscall. On error (when `ioctl`
-    /// returns -1), returns the error from *errno*.
+    /// This returns the same values as [`Ioctl::dir`], [`Ioctl::ty`], [`Ioctl::nr`], and
+    /// [`Ioctl::size`].
     ///
-    /// Note that the actual `ioctl(2)` call performed will pass 0 as a dummy argument to the
-    /// `ioctl`. This is because some Linux `ioctl`s are declared without an argument, but will fail
-    /// unless they receive 0 as their argument (eg. `KVM_GET_API_VERSION`). There should be no harm
-    /// in passing this argument unconditionally, as the kernel will typically just ignore excess
-    /// arguments.
+    /// # Example
     ///
-    /// # Safety
+    /// ```
+    /// use uoctl::*;
     ///
-    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
-    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
-    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
-    /// it expects.
-    pub unsafe fn ioctl(self, fd: &impl AsRawFd) -> io::Result<c_int> {
-        let res = unsafe { libc::ioctl(fd.as_raw_fd(), self.request as _, 0) };
-        if res == -1 {
-            Err(io::Error::last_os_error())
-        } else {
-            Ok(res)
+    /// const VIDIOC_QUERYCAP: Ioctl<*mut [u8; 104]> = _IOR(b'V', 0);
+    ///
+    /// let decoded = VIDIOC_QUERYCAP.decode();
+    /// assert_eq!(decoded, Decoded { dir: _IOC_READ, ty: b'V', nr: 0, size: 104 });
+    ///
+    /// let handler = match decoded {
+    ///     Decoded { ty: b'V', nr: 0, .. } => "querycap",
+    ///     Decoded { ty: b'V', .. } => "other v4l2",
+    ///     _ => "unknown",
+    /// };
+    /// assert_eq!(handler, "querycap");
+    /// ```
+    pub const fn decode(self) -> Decoded {
+        Decoded {
+            dir: self.dir(),
+            ty: self.ty(),
+            nr: self.nr(),
+            size: self.size(),
         }
     }
-}
 
-impl<T> Ioctl<T> {
-    /// Performs an `ioctl` that takes an argument of type `T`.
+    /// Renders the [`Ioctl`] as the C macro invocation that defines it.
     ///
-    /// Returns the value returned by the `ioctl(2)` invocation, or an I/O error if the call failed.
+    /// The macro is chosen based on [`Ioctl::dir`]: `_IO`, `_IOR`, `_IOW`, or `_IOWR`.
+    /// Since the argument type is not stored in the request code, the argument size is emitted as
+    /// a comment in its place.
+    /// `ioctl`s that don't transfer data but still encode a size are rendered as `_IOC`.
     ///
-    /// For many `ioctl`s, `T` will be a pointer to the actual argument.
-    /// The caller must ensure that it points to valid data that conforms to the requirements of the
-    /// `ioctl`.
+    /// For legacy `ioctl`s created with [`Ioctl::from_raw`], the result is built from whatever
+    /// happens to be stored in the corresponding bits of the request code.
     ///
-    /// # Safety
+    /// # Example
     ///
-    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
-    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
-    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
-    /// it expects.
-    pub unsafe fn ioctl(self, fd: &impl AsRawFd, arg: T) -> io::Result<c_int> {
-        let res = unsafe { libc::ioctl(fd.as_raw_fd(), self.request as _, arg) };
-        if res == -1 {
-            Err(io::Error::last_os_error())
+    /// ```
+    /// use std::ffi::c_int;
+    /// use uoctl::*;
+    ///
+    /// const VIDIOC_QUERYCAP: Ioctl<*mut [u8; 104]> = _IOR(b'V', 0);
+    /// const UI_DEV_CREATE: Ioctl = _IO(b'U', 1);
+    /// const UI_SET_EVBIT: Ioctl<c_int> = _IOW(b'U', 100).with_direct_arg();
+    ///
+    /// assert_eq!(VIDIOC_QUERYCAP.to_c_macro(), "_IOR('V', 0, /* 104 bytes */)");
+    /// assert_eq!(UI_DEV_CREATE.to_c_macro(), "_IO('U', 1)");
+    /// assert_eq!(UI_SET_EVBIT.to_c_macro(), "_IOW('U', 100, /* 4 bytes */)");
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn to_c_macro(self) -> String {
+        let Decoded { dir, ty, nr, size } = self.decode();
+        let ty = match ty {
+            b'\'' | b'\\' => format!("'\\{}'", ty as char),
+            _ if ty.is_ascii_graphic() || ty == b' ' => format!("'{}'", ty as char),
+            _ => format!("{ty:#04x}"),
+        };
+        let arg = if size == 1 {
+            "/* 1 byte */".to_string()
         } else {
-            Ok(res)
+            format!("/* {size} bytes */")
+        };
+        match (dir.is_read(), dir.is_write()) {
+            (false, false) if size == 0 => format!("_IO({ty}, {nr})"),
+            (false, false) => format!("_IOC(_IOC_NONE, {ty}, {nr}, {arg})"),
+            (true, false) => format!("_IOR({ty}, {nr}, {arg})"),
+            (false, true) => format!("_IOW({ty}, {nr}, {arg})"),
+            (true, true) => format!("_IOWR({ty}, {nr}, {arg})"),
         }
     }
-}
 
-/// Indicates that an [`Ioctl`] does not take any arguments.
-///
-/// This is used as the type parameter of [`Ioctl`] by the [`_IO`] and [`_IOC`] functions.
-/// [`Ioctl<NoArgs>`] comes with its own, separate `IOCTL.ioctl(fd)` method that only takes the file
-/// descriptor as an argument.
+    /// Returns the `ioctl` type and number, combined into a 16-bit key.
+    ///
+    /// This is equivalent to calling [`group_number_key`] with [`Ioctl::ty`] and [`Ioctl::nr`], and
+    /// can be used as a compact key for lookup tables that don't care about the direction or size
+    /// of an `ioctl`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// const RNDGETENTCNT: Ioctl<*mut i32> = _IOR(b'R', 0x00);
+    ///
+    /// assert_eq!(RNDGETENTCNT.group_number_key(), 0x5200);
+    /// ```
+    pub const fn group_number_key(self) -> u16 {
+        group_number_key(self.ty(), self.nr())
+    }
+
+    /// Returns the raw fields encoded in the request code as a tuple of
+    /// `(direction, type, number, size)`.
+    ///
+    /// The direction is returned as its raw, platform-specific bits.
+    /// This is meant for concisely `match`ing on request codes, for example in code that dispatches
+    /// `ioctl`s based on their type and number.
+    ///
+    /// For legacy `ioctl`s created with [`Ioctl::from_raw`], the fields contain whatever happens to
+    /// be stored in the corresponding bits of the request code.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// const VIDIOC_QUERYCAP: Ioctl<*mut [u8; 104]> = _IOR(b'V', 0);
+    ///
+    /// let name = match VIDIOC_QUERYCAP.as_tuple() {
+    ///     (_, b'V', 0, _) => "VIDIOC_QUERYCAP",
+    ///     (_, b'V', _, _) => "some other V4L2 ioctl",
+    ///     _ => "unknown",
+    /// };
+    /// assert_eq!(name, "VIDIOC_QUERYCAP");
+    /// ```
+    pub const fn as_tuple(self) -> (u32, u8, u8, u32) {
+        (
+            platform::_IOC_DIR(self.request),
+            self.ty(),
+            self.nr(),
+            platform::_IOC_SIZE(self.request),
+        )
+    }
+
+    /// Returns whether the direction and size encoded in the request code are consistent.
+    ///
+    /// An `ioctl` is considered well-formed if:
+    ///
+    /// - Its direction is one of [`_IOC_NONE`], [`_IOC_READ`], [`_IOC_WRITE`], or
+    ///   [`_IOC_READ_WRITE`].
+    /// - It has a size of 0 if and only if its direction is [`_IOC_NONE`]. On BSDs, an [`_IOC_NONE`]
+    ///   `ioctl` may also have the size of an `int`, since that's how [`_IOWINT`] encodes its
+    ///   argument.
+    /// - Its size does not exceed [`MAX_ARG_SIZE`].
+    ///
+    /// Every `ioctl` created with [`_IO`], [`_IOR`], [`_IOW`], or [`_IOWR`] with a non-zero-sized
+    /// argument type is well-formed.
+    /// Legacy `ioctl`s created with [`Ioctl::from_raw`] don't necessarily follow the encoding
+    /// scheme, and may or may not be considered well-formed.
+    ///
+    /// # Example
+    ///
+    /// Since this is a `const fn`, it can be used to check bindings at compile time:
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// const RNDGETENTCNT: Ioctl<*mut i32> = _IOR(b'R', 0x00);
+    /// const _: () = assert!(RNDGETENTCNT.is_well_formed());
+    ///
+    /// // An `ioctl` without a direction can't transfer any data.
+    /// const BOGUS: Ioctl = _IOC(_IOC_NONE, b'R', 0x00, 8);
+    /// assert!(!BOGUS.is_well_formed());
+    /// ```
+    pub const fn is_well_formed(self) -> bool {
+        let dir = platform::_IOC_DIR(self.request);
+        let size = self.size();
+        if size > platform::MAX_ARG_SIZE {
+            return false;
+        }
+
+        if dir == platform::_IOC_NONE {
+            size == 0 || (platform::IOC_VOID_INT && size == size_of::<c_int>())
+        } else if dir == platform::_IOC_READ
+            || dir == platform::_IOC_WRITE
+            || dir == platform::_IOC_READ | platform::_IOC_WRITE
+        {
+            size != 0
+        } else {
+            false
+        }
+    }
+
+    /// Performs the `ioctl` by issuing the system call directly, bypassing the libc wrapper.
+    ///
+    /// On x86-64 and AArch64, the system call instruction is emitted with inline assembly, so the
+    /// file descriptor, request code, and `arg` are passed in exactly the registers the kernel's
+    /// system call ABI prescribes.
+    /// On other architectures, `syscall(2)` is used.
+    /// This can be useful when libc's `ioctl` wrapper is unavailable or intercepted, or when
+    /// running under a seccomp filter that needs to observe specific argument values.
+    ///
+    /// The argument is passed as a raw `usize`, so pointers and integers have to be converted by
+    /// the caller.
+    ///
+    /// On success, returns the value returned by the system call. On error, returns the *errno*
+    /// value reported by the kernel (*errno* itself is not modified on x86-64 and AArch64).
+    ///
+    /// This method is only available on Linux and Android, and requires the `raw-syscall` feature.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `arg`
+    /// is a valid argument for it, and that `fd` is valid (open) and belongs to the driver it
+    /// expects.
+    #[cfg(all(
+        feature = "raw-syscall",
+        any(target_os = "linux", target_os = "android")
+    ))]
+    pub unsafe fn ioctl_syscall(self, fd: std::os::fd::RawFd, arg: usize) -> Result<c_int, c_int> {
+        let res = unsafe { platform::raw_ioctl(fd, self.request, arg) };
+        // The kernel returns errors as values in the range `-4095..=-1`.
+        if (-4095..0).contains(&res) {
+            Err(-res as c_int)
+        } else {
+            Ok(res as c_int)
+        }
+    }
+
+    /// Returns an [`IoctlId`] identifying this `ioctl`, independent of its argument type.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    /// use uoctl::*;
+    ///
+    /// const RNDGETENTCNT: Ioctl<*mut i32> = _IOR(b'R', 0x00);
+    /// const RNDCLEARPOOL: Ioctl = _IO(b'R', 0x06);
+    ///
+    /// let names = HashMap::from([
+    ///     (RNDGETENTCNT.id(), "RNDGETENTCNT"),
+    ///     (RNDCLEARPOOL.id(), "RNDCLEARPOOL"),
+    /// ]);
+    /// assert_eq!(names[&RNDCLEARPOOL.id()], "RNDCLEARPOOL");
+    /// ```
+    pub const fn id(self) -> IoctlId {
+        IoctlId(self.request)
+    }
+}
+
+impl<T> Ioctl<*const T> {
+    /// Changes the [`Ioctl`] argument type to be passed directly instead of behind a pointer.
+    ///
+    /// Does not change the request code.
+    ///
+    /// Many linux headers define `ioctl`s like `_IOW('U', 100, int)`, but then expect the `int`
+    /// argument to be passed as a direct argument to `ioctl(2)` instead of passing a pointer.
+    /// This method can be used to bind to these `ioctl`s.
+    ///
+    /// # Example
+    ///
+    /// `uinput` defines several `ioctl`s where this method is useful:
+    ///
+    /// ```c
+    /// #define UI_SET_EVBIT		_IOW(UINPUT_IOCTL_BASE, 100, int)
+    /// ```
+    ///
+    /// ```
+    /// use std::ffi::c_int;
+    /// use uoctl::{Ioctl, _IOW};
+    ///
+    /// const UI_SET_EVBIT: Ioctl<c_int> = _IOW(b'U', 100).with_direct_arg();
+    /// ```
+    ///
+    /// # Passing structs by value
+    ///
+    /// `T` does not have to be a primitive type: a few `ioctl`s take a small `struct` by value.
+    /// Calling [`with_direct_arg`][Ioctl::with_direct_arg] on an [`Ioctl<*const SmallStruct>`]
+    /// results in an [`Ioctl<SmallStruct>`], whose [`Ioctl::ioctl`] method passes the `struct`
+    /// through the variadic `ioctl(2)` function by value.
+    ///
+    /// However, the kernel only ever receives a single `unsigned long` as the `ioctl` argument, and
+    /// how a `struct` passed through `...` is laid out in registers or on the stack depends on the
+    /// platform ABI.
+    /// Only structs that are no larger than a pointer, and only contain integer or pointer fields,
+    /// will reliably arrive at the kernel intact.
+    /// Larger structs will be truncated (or worse), and structs containing floating-point fields
+    /// may be passed in entirely different registers.
+    /// If a driver really does expect a larger value, it has to be passed behind a pointer instead.
+    ///
+    /// ```
+    /// use uoctl::{Ioctl, _IOW};
+    ///
+    /// #[repr(C)]
+    /// struct Pair {
+    ///     a: u16,
+    ///     b: u16,
+    /// }
+    ///
+    /// const SET_PAIR: Ioctl<Pair> = _IOW(b'x', 1).with_direct_arg();
+    /// ```
+    #[inline]
+    pub const fn with_direct_arg(self) -> Ioctl<T> {
+        self.cast_arg()
+    }
+
+    /// Casts the [`Ioctl`] so that it takes a `*mut` pointer instead of a `*const` pointer.
+    ///
+    /// This can be used to fix an `ioctl` that is incorrectly declared as only *reading* its
+    /// argument through the pointer (with [`_IOW`]), when in reality the kernel can *write* through
+    /// the pointer as well.
+    ///
+    /// An ioctl that writes through its pointer argument, but is incorrectly declared as
+    /// [`Ioctl<*const T>`] will generally cause UB when invoked with an immutable reference.
+    ///
+    /// Also see [`Ioctl::cast_const`] for the opposite direction.
+    ///
+    /// # Example
+    ///
+    /// The `EVIOCSFF` ioctl from evdev is incorrectly declared with [`_IOW`], but may write to the
+    /// data, as documented here:
+    ///
+    /// > “request” must be EVIOCSFF.
+    /// >
+    /// > “effect” points to a structure describing the effect to upload. The effect is uploaded, but
+    /// > not played. **The content of effect may be modified.**
+    ///
+    /// <https://www.kernel.org/doc/html/latest/input/ff.html>
+    ///
+    /// ```
+    /// use std::ffi::c_void as ff_effect;
+    /// use uoctl::{Ioctl, _IOW};
+    ///
+    /// pub const EVIOCSFF: Ioctl<*mut ff_effect> = _IOW(b'E', 0x80).cast_mut();
+    /// ```
+    #[inline]
+    pub const fn cast_mut(self) -> Ioctl<*mut T> {
+        self.cast_arg()
+    }
+
+    /// Changes the type the argument points to, keeping the pointer `*const`.
+    ///
+    /// Does not change the request code, so `U` should have the same size as `T`.
+    /// [`Ioctl::try_cast_arg`] checks this, and can be used instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// #[repr(C)]
+    /// struct Name([u8; 16]);
+    ///
+    /// const SET_NAME: Ioctl<*const Name> = _IOW::<[u8; 16]>(b'x', 1).cast_pointee();
+    /// ```
+    #[inline]
+    pub const fn cast_pointee<U>(self) -> Ioctl<*const U> {
+        self.cast_arg()
+    }
+
+    /// Performs an `ioctl` that reads a `T` from its argument.
+    ///
+    /// This passes `value` as a `*const T`.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::{fs::File, mem};
+    /// use libc::uinput_setup;
+    /// use uoctl::*;
+    ///
+    /// const UI_DEV_SETUP: Ioctl<*const uinput_setup> = _IOW(b'U', 3);
+    ///
+    /// let uinput = File::options().write(true).open("/dev/uinput")?;
+    ///
+    /// let mut setup: uinput_setup = unsafe { mem::zeroed() };
+    /// setup.name[0] = b'A' as _;
+    /// unsafe { UI_DEV_SETUP.write(&uinput, &setup)? };
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn write(self, fd: &impl AsRawFd, value: &T) -> io::Result<c_int> {
+        unsafe { self.ioctl(fd, value) }
+    }
+
+    /// Performs the `ioctl` with a [`NonNull`] pointer to the argument.
+    ///
+    /// This is equivalent to passing `arg.as_ptr()` to [`Ioctl::ioctl`].
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    /// Additionally, `arg` has to point to a valid `T` that can be read for the duration of the
+    /// `ioctl`.
+    #[cfg(feature = "std")]
+    pub unsafe fn ioctl_non_null(self, fd: &impl AsRawFd, arg: NonNull<T>) -> io::Result<c_int> {
+        unsafe { self.ioctl(fd, arg.as_ptr()) }
+    }
+}
+
+impl<T> Ioctl<*mut T> {
+    /// Creates an [`Ioctl`] from a raw request code, checking the encoded argument size against
+    /// `T`.
+    ///
+    /// Unlike [`Ioctl::from_raw`], this returns an error if the request code encodes a size that
+    /// differs from the size of `T`, which catches request codes that were copied together with
+    /// the wrong argument type.
+    /// Request codes that encode a size of 0 are accepted, since legacy `ioctl`s often don't
+    /// encode any size at all.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SizeMismatch`] error if the size encoded in `request` is nonzero and differs
+    /// from the size of `T`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::ffi::c_int;
+    /// use uoctl::*;
+    ///
+    /// // `EVIOCGVERSION`, `_IOR('E', 0x01, int)`
+    /// let ioctl = Ioctl::<*mut c_int>::try_from_raw(0x80044501).unwrap();
+    /// assert_eq!(ioctl.request(), 0x80044501);
+    ///
+    /// let err = Ioctl::<*mut u64>::try_from_raw(0x80044501).unwrap_err();
+    /// assert_eq!(err, SizeMismatch { encoded: 4, actual: 8 });
+    ///
+    /// // `FIONREAD` doesn't encode a size, so it is accepted with any argument type.
+    /// assert!(Ioctl::<*mut c_int>::try_from_raw(0x541B).is_ok());
+    /// ```
+    pub const fn try_from_raw(request: u32) -> Result<Self, SizeMismatch> {
+        let ioctl = Self::from_raw(request);
+        let encoded = ioctl.size();
+        let actual = size_of::<T>();
+        if encoded == 0 || encoded == actual {
+            Ok(ioctl)
+        } else {
+            Err(SizeMismatch { encoded, actual })
+        }
+    }
+
+    /// Casts the [`Ioctl`] so that it takes a `*const` pointer instead of a `*mut` pointer.
+    ///
+    /// This performs the opposite operation of [`Ioctl::cast_mut`], and can be used when an `ioctl`
+    /// is incorrectly declared as writing to its argument (yielding an [`Ioctl<*mut T>`]) when it
+    /// actually only reads from it.
+    ///
+    /// Only use this method if you are sure it is correct! If the `ioctl` *does* write through the
+    /// pointer, the result is likely UB!
+    #[inline]
+    pub const fn cast_const(self) -> Ioctl<*const T> {
+        self.cast_arg()
+    }
+
+    /// Changes the type the argument points to, keeping the pointer `*mut`.
+    ///
+    /// Does not change the request code, so `U` should have the same size as `T`.
+    /// [`Ioctl::try_cast_arg`] checks this, and can be used instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::ffi::c_char;
+    /// use uoctl::*;
+    ///
+    /// #[repr(C)]
+    /// struct Name([u8; 16]);
+    ///
+    /// const GET_NAME: Ioctl<*mut Name> = _IOR::<[c_char; 16]>(b'x', 1).cast_pointee();
+    /// ```
+    #[inline]
+    pub const fn cast_pointee<U>(self) -> Ioctl<*mut U> {
+        self.cast_arg()
+    }
+
+    /// Performs a query `ioctl` and checks whether the kernel reports the expected `struct`
+    /// version.
+    ///
+    /// Some subsystems version their `ioctl` argument `struct`s by including a `size` or `version`
+    /// field that the kernel fills in.
+    /// This method passes a zero-initialized `T` to the `ioctl`, extracts the kernel-reported value
+    /// with `field`, and returns whether it is equal to `expected`.
+    /// Wrappers can use this to detect kernels with a different `struct` version up front, and fall
+    /// back to a different code path (or fail gracefully).
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    ///
+    /// Additionally, an all-zero bit pattern has to be a valid value of `T` (both before and after
+    /// the kernel has filled it in), and a zeroed `T` has to be a valid argument for the `ioctl`.
+    ///
+    /// # Example
+    ///
+    /// From `drm/drm.h`:
+    ///
+    /// ```c
+    /// struct drm_version {
+    /// 	int version_major;	  /**< Major version */
+    /// 	int version_minor;	  /**< Minor version */
+    /// 	int version_patchlevel;	  /**< Patch level */
+    /// 	__kernel_size_t name_len;	  /**< Length of name buffer */
+    /// 	char __user *name;	  /**< Name of driver */
+    /// 	__kernel_size_t date_len;	  /**< Length of date buffer */
+    /// 	char __user *date;	  /**< User-space buffer to hold date */
+    /// 	__kernel_size_t desc_len;	  /**< Length of desc buffer */
+    /// 	char __user *desc;	  /**< User-space buffer to hold desc */
+    /// };
+    /// // ...
+    /// #define DRM_IOCTL_VERSION		DRM_IOWR(0x00, struct drm_version)
+    /// ```
+    ///
+    /// ```no_run
+    /// use std::{ffi::{c_char, c_int}, fs::File};
+    /// use uoctl::*;
+    ///
+    /// #[repr(C)]
+    /// struct DrmVersion {
+    ///     version_major: c_int,
+    ///     version_minor: c_int,
+    ///     version_patchlevel: c_int,
+    ///     name_len: usize,
+    ///     name: *mut c_char,
+    ///     date_len: usize,
+    ///     date: *mut c_char,
+    ///     desc_len: usize,
+    ///     desc: *mut c_char,
+    /// }
+    ///
+    /// const DRM_IOCTL_VERSION: Ioctl<*mut DrmVersion> = _IOWR(b'd', 0x00);
+    ///
+    /// let card = File::open("/dev/dri/card0")?;
+    /// let is_v1 = unsafe {
+    ///     DRM_IOCTL_VERSION.check_struct_version(&card, 1, |v| v.version_major as u32)?
+    /// };
+    /// if !is_v1 {
+    ///     println!("unsupported driver interface version");
+    /// }
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn check_struct_version(
+        self,
+        fd: &impl AsRawFd,
+        expected: u32,
+        field: impl Fn(&T) -> u32,
+    ) -> io::Result<bool> {
+        let mut arg = MaybeUninit::<T>::zeroed();
+        unsafe {
+            self.ioctl(fd, arg.as_mut_ptr())?;
+            Ok(field(arg.assume_init_ref()) == expected)
+        }
+    }
+
+    /// Performs an `ioctl` that fills a caller-sized array, reading into the elements of `v`.
+    ///
+    /// Some `ioctl`s (like evdev's `EVIOCGKEY(len)`) fill an array whose size is determined by
+    /// the caller, and encode the size of that array (in bytes) in the request code.
+    /// This method re-encodes the request code of `self` with the byte length of `v` (ie.
+    /// `v.len() * size_of::<T>()`), passes the buffer of `v` to the `ioctl`, and returns `v`
+    /// once the `ioctl` has completed.
+    ///
+    /// The length of `v` is not changed by this method, so `v` has to be sized (and initialized)
+    /// before calling it.
+    /// If the `ioctl` returns the number of elements it has written, the caller can
+    /// [`truncate`][Vec::truncate] `v` accordingly.
+    ///
+    /// # Panics
+    ///
+    /// This method will panic if the byte length of `v` exceeds the (platform-specific) maximum
+    /// parameter size.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct array element type, and that `fd` is valid (open) and belongs to the
+    /// driver it expects.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use uoctl::{*, evdev::EVIOCGKEY};
+    ///
+    /// const KEY_MAX: usize = 0x2ff;
+    ///
+    /// let file = File::open("/dev/input/event0")?;
+    ///
+    /// // The size encoded in `EVIOCGKEY(0)` is replaced with the length of the `Vec`.
+    /// let keys = unsafe { EVIOCGKEY(0).read_into_vec(&file, vec![0u8; KEY_MAX / 8 + 1])? };
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn read_into_vec(self, fd: &impl AsRawFd, mut v: Vec<T>) -> io::Result<Vec<T>> {
+        let ioctl = self.with_size(size_of_val(v.as_slice()));
+        unsafe { ioctl.ioctl(fd, v.as_mut_ptr())? };
+        Ok(v)
+    }
+
+    /// Performs an `ioctl` that takes a pointer to an array, passing the elements of `slice`.
+    ///
+    /// Unlike [`Ioctl::read_into_vec`], this method does not change the request code, but checks
+    /// that the size encoded in it matches the byte length of `slice`.
+    /// Polymorphic `ioctl`s can be re-encoded with [`Ioctl::with_size`] first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error without performing the `ioctl` if the size
+    /// encoded in the request code differs from `size_of_val(slice)`.
+    /// Otherwise, returns any error reported by the `ioctl`.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct array element type, and that `fd` is valid (open) and belongs to the
+    /// driver it expects.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use uoctl::{*, evdev::EVIOCGKEY};
+    ///
+    /// const KEY_MAX: usize = 0x2ff;
+    ///
+    /// let file = File::open("/dev/input/event0")?;
+    ///
+    /// let mut keys = [0u8; KEY_MAX / 8 + 1];
+    /// unsafe { EVIOCGKEY(keys.len()).ioctl_slice(&file, &mut keys)? };
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn ioctl_slice(self, fd: &impl AsRawFd, slice: &mut [T]) -> io::Result<c_int> {
+        if self.size() != size_of_val(slice) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                SizeMismatch {
+                    encoded: self.size(),
+                    actual: size_of_val(slice),
+                },
+            ));
+        }
+        unsafe { self.ioctl(fd, slice.as_mut_ptr()) }
+    }
+
+    /// Performs a read-only `ioctl` into a caller-provided slot, and returns a reference to the
+    /// initialized value.
+    ///
+    /// This is meant for reading many values in a row without a per-call allocation or copy: the
+    /// slots can come from a pre-allocated buffer, ring buffer, or arena, and are filled in place.
+    ///
+    /// Since the kernel doesn't read the argument of an [`_IOR`] `ioctl`, the slot doesn't have to
+    /// be initialized beforehand.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the request code of `self` encodes the [`_IOC_WRITE`] direction
+    /// (eg. because it was created with [`_IOWR`]), since those `ioctl`s read the (possibly
+    /// uninitialized) slot.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    ///
+    /// Additionally, a successful `ioctl` has to fully initialize the slot with a valid `T`, and
+    /// the `ioctl` must not read from the slot (this can't be checked for legacy `ioctl`s that
+    /// were created with [`Ioctl::from_raw`]).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::{fs::File, mem::MaybeUninit};
+    /// use uoctl::*;
+    ///
+    /// const RNDGETENTCNT: Ioctl<*mut i32> = _IOR(b'R', 0x00);
+    ///
+    /// let file = File::open("/dev/random")?;
+    ///
+    /// let mut samples = [MaybeUninit::uninit(); 64];
+    /// for slot in &mut samples {
+    ///     let entropy = unsafe { RNDGETENTCNT.read_into_slot(&file, slot)? };
+    ///     println!("{entropy}");
+    /// }
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn read_into_slot<'a>(
+        self,
+        fd: &impl AsRawFd,
+        slot: &'a mut MaybeUninit<T>,
+    ) -> io::Result<&'a mut T> {
+        assert!(
+            !self.dir().is_write(),
+            "`read_into_slot` cannot be used with `ioctl`s that read their argument",
+        );
+        unsafe {
+            self.ioctl(fd, slot.as_mut_ptr())?;
+            Ok(slot.assume_init_mut())
+        }
+    }
+
+    /// Performs an `ioctl` that writes a `T` to its argument, and returns it.
+    ///
+    /// This passes a pointer to uninitialized memory to the `ioctl`, and assumes that the kernel
+    /// has initialized it once the `ioctl` succeeds.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    /// Additionally, the `ioctl` has to fully initialize the `T` when it succeeds.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the `ioctl` is declared as reading its argument (via [`_IOW`] or
+    /// [`_IOWR`]), since the kernel would read uninitialized memory.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use uoctl::*;
+    ///
+    /// const RNDGETENTCNT: Ioctl<*mut i32> = _IOR(b'R', 0x00);
+    ///
+    /// let file = File::open("/dev/random")?;
+    /// let entropy = unsafe { RNDGETENTCNT.read(&file)? };
+    /// println!("{entropy} bits of entropy available");
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn read(self, fd: &impl AsRawFd) -> io::Result<T> {
+        assert!(
+            !self.dir().is_write(),
+            "`read` cannot be used with `ioctl`s that read their argument",
+        );
+        let mut value = MaybeUninit::uninit();
+        unsafe {
+            self.ioctl(fd, value.as_mut_ptr())?;
+            Ok(value.assume_init())
+        }
+    }
+
+    /// Performs an `ioctl` that reads a `T` from its argument, and may write back to it.
+    ///
+    /// This passes `value` as a `*mut T`, and is typically used with [`_IOWR`] `ioctl`s, which
+    /// receive a request and return the result in the same `struct`.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    ///
+    /// # Example
+    ///
+    /// From `linux/kvm.h`:
+    ///
+    /// ```c
+    /// struct kvm_msr_list {
+    /// 	__u32 nmsrs; /* number of msrs in entries */
+    /// 	__u32 indices[];
+    /// };
+    /// ...
+    /// #define KVM_GET_MSR_INDEX_LIST    _IOWR(KVMIO, 0x02, struct kvm_msr_list)
+    /// ```
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use uoctl::*;
+    ///
+    /// #[repr(C)]
+    /// struct MsrList {
+    ///     nmsrs: u32,
+    ///     indices: [u32; 256],
+    /// }
+    ///
+    /// const KVMIO: u8 = 0xAE;
+    /// const KVM_GET_MSR_INDEX_LIST: Ioctl<*mut MsrList> =
+    ///     _IOWR::<u32>(KVMIO, 0x02).cast_arg();
+    ///
+    /// let kvm = File::open("/dev/kvm")?;
+    ///
+    /// let mut list = MsrList { nmsrs: 256, indices: [0; 256] };
+    /// unsafe { KVM_GET_MSR_INDEX_LIST.modify(&kvm, &mut list)? };
+    /// println!("MSRs: {:x?}", &list.indices[..list.nmsrs as usize]);
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn modify(self, fd: &impl AsRawFd, value: &mut T) -> io::Result<c_int> {
+        unsafe { self.ioctl(fd, value) }
+    }
+
+    /// Performs the `ioctl` with a [`NonNull`] pointer to the argument.
+    ///
+    /// This is equivalent to passing `arg.as_ptr()` to [`Ioctl::ioctl`], and is meant for code
+    /// that keeps track of its buffers as [`NonNull`] pointers.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    /// Additionally, `arg` has to point to memory that is valid for the accesses the `ioctl`
+    /// performs, for the duration of the `ioctl`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{ffi::c_int, fs::File, ptr::NonNull};
+    /// use uoctl::*;
+    ///
+    /// const FIONREAD: Ioctl<*mut c_int> = Ioctl::from_raw(libc::FIONREAD as _);
+    ///
+    /// let file = File::open("/dev/ptmx")?;
+    /// let buffer = NonNull::from(Box::leak(Box::new(c_int::MAX)));
+    /// unsafe {
+    ///     FIONREAD.ioctl_non_null(&file, buffer)?;
+    ///     assert_ne!(*buffer.as_ptr(), c_int::MAX);
+    ///     drop(Box::from_raw(buffer.as_ptr()));
+    /// }
+    /// # std::io::Result::Ok(())
+    /// ```
+    #[cfg(feature = "std")]
+    pub unsafe fn ioctl_non_null(self, fd: &impl AsRawFd, arg: NonNull<T>) -> io::Result<c_int> {
+        unsafe { self.ioctl(fd, arg.as_ptr()) }
+    }
+
+    /// Turns this [`Ioctl`] for an extensible `struct` into a [`VersionedIoctl`] that takes the
+    /// extended `struct` `Ext` as its argument.
+    ///
+    /// The request code (and thus the encoded size of the base `struct`) is not changed.
+    /// `set_size` has to store the size it is passed in the size field of `Ext`; it is called with
+    /// `size_of::<Ext>()` before every `ioctl`.
+    ///
+    /// See [`VersionedIoctl`] for an example.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `Ext` is smaller than the base `struct` `T`.
+    #[cfg(feature = "std")]
+    pub const fn with_versioned_arg<Ext>(self, set_size: fn(&mut Ext, u32)) -> VersionedIoctl<Ext> {
+        assert!(
+            size_of::<Ext>() >= size_of::<T>(),
+            "the extended `struct` must not be smaller than the base `struct`",
+        );
+        VersionedIoctl::new(self.cast_arg(), set_size)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Ioctl<*const libc::timespec> {
+    /// Performs an `ioctl` that takes a pointer to a `timespec`, converting it from a [`Duration`].
+    ///
+    /// [`Duration`] and `timespec` both have nanosecond precision, so the conversion is exact.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if the number of seconds in `duration`
+    /// exceeds the range of `time_t`, in addition to any error returned by the `ioctl`.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that the
+    /// `ioctl` does take a `timespec`, and that `fd` is valid (open) and belongs to the driver it
+    /// expects.
+    pub unsafe fn ioctl_duration(self, fd: &impl AsRawFd, duration: Duration) -> io::Result<c_int> {
+        let ts = duration_to_timespec(duration)?;
+        unsafe { self.ioctl(fd, &ts) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Ioctl<*const libc::timeval> {
+    /// Performs an `ioctl` that takes a pointer to a `timeval`, converting it from a [`Duration`].
+    ///
+    /// `timeval` only has microsecond precision, so `duration` is rounded up to whole
+    /// microseconds, so that timeouts never end up shorter than requested.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`io::ErrorKind::InvalidInput`] error if the number of seconds in `duration`
+    /// exceeds the range of `time_t`, in addition to any error returned by the `ioctl`.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that the
+    /// `ioctl` does take a `timeval`, and that `fd` is valid (open) and belongs to the driver it
+    /// expects.
+    pub unsafe fn ioctl_duration(self, fd: &impl AsRawFd, duration: Duration) -> io::Result<c_int> {
+        let tv = duration_to_timeval(duration)?;
+        unsafe { self.ioctl(fd, &tv) }
+    }
+}
+
+#[cfg(feature = "std")]
+fn duration_seconds(secs: u64) -> io::Result<libc::time_t> {
+    libc::time_t::try_from(secs).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{secs} seconds do not fit in a `time_t`"),
+        )
+    })
+}
+
+#[cfg(feature = "std")]
+fn duration_to_timespec(duration: Duration) -> io::Result<libc::timespec> {
+    // Some targets have private padding fields in `timespec`.
+    let mut ts: libc::timespec = unsafe { mem::zeroed() };
+    ts.tv_sec = duration_seconds(duration.as_secs())?;
+    // Always below 10⁹, so this fits even where `tv_nsec` is 32 bits wide.
+    ts.tv_nsec = duration.subsec_nanos() as _;
+    Ok(ts)
+}
+
+#[cfg(feature = "std")]
+fn duration_to_timeval(duration: Duration) -> io::Result<libc::timeval> {
+    let mut secs = duration.as_secs();
+    let mut micros = duration.subsec_nanos().div_ceil(1000);
+    if micros == 1_000_000 {
+        secs = secs.checked_add(1).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "duration does not fit in a `timeval`",
+            )
+        })?;
+        micros = 0;
+    }
+
+    let mut tv: libc::timeval = unsafe { mem::zeroed() };
+    tv.tv_sec = duration_seconds(secs)?;
+    tv.tv_usec = micros as _;
+    Ok(tv)
+}
+
+/// Takes ownership of a file descriptor returned by an `ioctl`.
+///
+/// # Safety
+///
+/// If `res` is non-negative, it has to be an open file descriptor owned by the caller.
+#[cfg(feature = "std")]
+unsafe fn owned_fd(res: c_int) -> io::Result<OwnedFd> {
+    if res < 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("`ioctl` returned invalid file descriptor {res}"),
+        ));
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(res) })
+}
+
+/// Sets `FD_CLOEXEC` on `fd`, closing it on failure.
+#[cfg(feature = "std")]
+fn set_cloexec(fd: OwnedFd) -> io::Result<OwnedFd> {
+    if unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(fd)
+}
+
+#[cfg(feature = "std")]
+impl Ioctl<NoArgs> {
+    /// Performs an `ioctl` that doesn't take an argument.
+    ///
+    /// On success, returns the value returned by the `ioctl` syscall. On error (when `ioctl`
+    /// returns -1), returns the error from *errno*.
+    ///
+    /// Note that the actual `ioctl(2)` call performed will pass 0 as a dummy argument to the
+    /// `ioctl`. This is because some Linux `ioctl`s are declared without an argument, but will fail
+    /// unless they receive 0 as their argument (eg. `KVM_GET_API_VERSION`). There should be no harm
+    /// in passing this argument unconditionally, as the kernel will typically just ignore excess
+    /// arguments.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    pub unsafe fn ioctl(self, fd: &impl AsRawFd) -> io::Result<c_int> {
+        #[cfg(feature = "mock")]
+        if let Some(res) = mock::intercept(fd.as_raw_fd(), self.request, &0usize) {
+            return res;
+        }
+        let res = unsafe { libc::ioctl(fd.as_raw_fd(), self.request as _, 0) };
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument through an [`IoctlBackend`].
+    ///
+    /// See [`Ioctl::ioctl_with_backend`][Ioctl::<T>::ioctl_with_backend] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl_with_backend(
+        self,
+        backend: &(impl IoctlBackend + ?Sized),
+        fd: &impl AsRawFd,
+    ) -> io::Result<c_int> {
+        unsafe { backend.ioctl(fd.as_raw_fd(), self.request, 0) }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument by calling `call` instead of `ioctl(2)`.
+    ///
+    /// `call` is passed the request code and a null pointer as the argument.
+    /// See [`Ioctl::ioctl_with`][Ioctl::<T>::ioctl_with] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor (the one `call`
+    /// uses).
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that the
+    /// file descriptor is valid (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl_with(
+        self,
+        call: impl FnOnce(u32, *mut c_void) -> c_int,
+    ) -> io::Result<c_int> {
+        let res = call(self.request, ptr::null_mut());
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument, retrying it if it fails with `EINTR`.
+    ///
+    /// See [`Ioctl::ioctl_retry`][Ioctl::<T>::ioctl_retry] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl_retry(self, fd: &impl AsRawFd) -> io::Result<c_int> {
+        unsafe { self.ioctl_retry_n(fd, usize::MAX) }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument, retrying it up to `max_retries` times if
+    /// it fails with `EINTR`.
+    ///
+    /// See [`Ioctl::ioctl_retry_n`][Ioctl::<T>::ioctl_retry_n] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl_retry_n(self, fd: &impl AsRawFd, max_retries: usize) -> io::Result<c_int> {
+        let mut retries = 0;
+        loop {
+            match unsafe { self.ioctl(fd) } {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted && retries < max_retries => {
+                    retries += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument, and converts its return value to `R`.
+    ///
+    /// See [`Ioctl::ioctl_as`][Ioctl::<T>::ioctl_as] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl_as<R: FromIoctlReturn>(self, fd: &impl AsRawFd) -> io::Result<R> {
+        unsafe { self.ioctl(fd).and_then(R::from_ioctl_return) }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument, and discards its return value.
+    ///
+    /// See [`Ioctl::ioctl_unit`][Ioctl::<T>::ioctl_unit] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl_unit(self, fd: &impl AsRawFd) -> io::Result<()> {
+        unsafe { self.ioctl_as(fd) }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument on a raw file descriptor.
+    ///
+    /// See [`Ioctl::ioctl_raw`][Ioctl::<T>::ioctl_raw] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    pub unsafe fn ioctl_raw(self, fd: RawFd) -> io::Result<c_int> {
+        unsafe { self.ioctl(&fd) }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument on a file descriptor implementing
+    /// [`AsFd`].
+    ///
+    /// See [`Ioctl::ioctl_fd`][Ioctl::<T>::ioctl_fd] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// belongs to the driver it expects.
+    pub unsafe fn ioctl_fd(self, fd: impl AsFd) -> io::Result<c_int> {
+        unsafe { self.ioctl(&fd.as_fd()) }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument and returns a new file descriptor.
+    ///
+    /// See [`Ioctl::ioctl_owned_fd`][Ioctl::<T>::ioctl_owned_fd] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    /// Additionally, the `ioctl` has to return a new file descriptor owned by the caller on success.
+    pub unsafe fn ioctl_owned_fd(self, fd: &impl AsRawFd) -> io::Result<OwnedFd> {
+        unsafe { self.ioctl(fd).and_then(|res| owned_fd(res)) }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument and returns a new file descriptor, and
+    /// sets `FD_CLOEXEC` on it.
+    ///
+    /// See [`Ioctl::ioctl_owned_fd_cloexec`][Ioctl::<T>::ioctl_owned_fd_cloexec] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    /// Additionally, the `ioctl` has to return a new file descriptor owned by the caller on success.
+    pub unsafe fn ioctl_owned_fd_cloexec(self, fd: &impl AsRawFd) -> io::Result<OwnedFd> {
+        unsafe { self.ioctl_owned_fd(fd).and_then(set_cloexec) }
+    }
+
+    /// Performs an `ioctl` that doesn't take an argument, and returns errors as [`IoctlError`]s.
+    ///
+    /// See [`Ioctl::try_ioctl`][Ioctl::<T>::try_ioctl] for details.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, and that `fd`
+    /// is valid (open) and belongs to the driver it expects.
+    pub unsafe fn try_ioctl(self, fd: &impl AsRawFd) -> Result<c_int, IoctlError> {
+        unsafe { self.ioctl(fd).map_err(IoctlError::from_io) }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Ioctl<T> {
+    /// Performs an `ioctl` that takes an argument of type `T`.
+    ///
+    /// Returns the value returned by the `ioctl(2)` invocation, or an I/O error if the call failed.
+    ///
+    /// For many `ioctl`s, `T` will be a pointer to the actual argument.
+    /// The caller must ensure that it points to valid data that conforms to the requirements of the
+    /// `ioctl`.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    ///
+    /// # Panics and unwinding
+    ///
+    /// This method does not catch panics, and `ioctl(2)` itself never unwinds.
+    ///
+    /// `arg` is dropped after the `ioctl(2)` call has returned (and after *errno* has been read),
+    /// so if `T` has a [`Drop`] implementation, it runs exactly once, and can neither observe the
+    /// kernel still using the argument nor clobber the returned error.
+    /// If dropping `arg` panics, the panic propagates to the caller and the result of the `ioctl`
+    /// is lost, but the `ioctl` has still been performed.
+    pub unsafe fn ioctl(self, fd: &impl AsRawFd, arg: T) -> io::Result<c_int> {
+        unsafe {
+            ioctl_with_arg(fd.as_raw_fd(), self.request, arg, |fd, request, arg| {
+                let res = libc::ioctl(fd, request as _, arg);
+                if res == -1 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(res)
+                }
+            })
+        }
+    }
+
+    /// Performs an `ioctl` that takes an argument of type `T` through an [`IoctlBackend`].
+    ///
+    /// This works like [`Ioctl::ioctl`], but lets `backend` perform the actual system call, which
+    /// allows substituting it in tests.
+    /// With [`LibcBackend`], this performs the same `ioctl(2)` call as [`Ioctl::ioctl`].
+    ///
+    /// `arg` is passed to the backend as a [`usize`] (see [`IoctlBackend`]), and is dropped after
+    /// the backend has returned.
+    /// `T` must not be larger than a [`usize`]; larger argument types are rejected at compile
+    /// time.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    pub unsafe fn ioctl_with_backend(
+        self,
+        backend: &(impl IoctlBackend + ?Sized),
+        fd: &impl AsRawFd,
+        arg: T,
+    ) -> io::Result<c_int> {
+        let res = unsafe { backend.ioctl(fd.as_raw_fd(), self.request, backend::arg_bits(&arg)) };
+        drop(arg);
+        res
+    }
+
+    /// Performs an `ioctl` that takes an argument of type `T` by calling `call` instead of
+    /// `ioctl(2)`.
+    ///
+    /// `call` is passed the request code and the argument, and has to perform the `ioctl` on the
+    /// file descriptor it is meant for.
+    /// Like `ioctl(2)`, it has to return -1 and set *errno* on error.
+    /// This allows substituting the libc wrapper, for example with `syscall(2)` in environments
+    /// where the variadic `ioctl` function is intercepted or unavailable, or with a sandboxed shim.
+    ///
+    /// Pointer arguments are passed as-is, and integer arguments are zero-extended to the size of
+    /// a pointer.
+    /// `T` must not be larger than a pointer; larger argument types are rejected at compile time.
+    /// `arg` is dropped after `call` has returned.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor (the one `call`
+    /// uses).
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that the file descriptor is valid (open) and belongs
+    /// to the driver it expects.
+    ///
+    /// # Example
+    ///
+    /// Performing `FIONREAD` via `syscall(2)`:
+    ///
+    /// ```
+    /// use std::{ffi::c_int, fs::File, os::fd::AsRawFd};
+    /// use uoctl::*;
+    ///
+    /// const FIONREAD: Ioctl<*mut c_int> = Ioctl::from_raw(libc::FIONREAD as _);
+    ///
+    /// let file = File::open("/dev/ptmx")?;
+    /// let mut bytes = c_int::MAX;
+    /// unsafe {
+    ///     FIONREAD.ioctl_with(&mut bytes, |request, arg| {
+    ///         libc::syscall(libc::SYS_ioctl, file.as_raw_fd(), request, arg) as c_int
+    ///     })?;
+    /// }
+    /// assert_ne!(bytes, c_int::MAX);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn ioctl_with(
+        self,
+        arg: T,
+        call: impl FnOnce(u32, *mut c_void) -> c_int,
+    ) -> io::Result<c_int> {
+        let res = call(self.request, backend::arg_bits(&arg) as *mut c_void);
+        let res = if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(res)
+        };
+        drop(arg);
+        res
+    }
+
+    /// Performs an `ioctl` whose return value of 0 indicates that there is no data.
+    ///
+    /// Some `ioctl`s return a positive value (like a count) on success, but use 0 to indicate a
+    /// distinct successful state, like "nothing available".
+    /// This method returns `Ok(None)` when the `ioctl` returns 0, and `Ok(Some(n))` for any other
+    /// successful return value.
+    /// Errors are reported just like with [`Ioctl::ioctl`].
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    ///
+    /// # Example
+    ///
+    /// `KVM_CHECK_EXTENSION` returns 0 if an extension is unsupported, or a positive value
+    /// describing the extension otherwise.
+    ///
+    /// From `linux/kvm.h`:
+    ///
+    /// ```c
+    /// #define KVM_CHECK_EXTENSION       _IO(KVMIO,   0x03)
+    /// ...
+    /// #define KVM_CAP_NR_VCPUS 9       /* returns recommended max vcpus per vm */
+    /// ```
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::ffi::c_int;
+    /// use uoctl::*;
+    ///
+    /// const KVMIO: u8 = 0xAE;
+    /// const KVM_CHECK_EXTENSION: Ioctl<c_int> = _IO(KVMIO, 0x03).cast_arg();
+    /// const KVM_CAP_NR_VCPUS: c_int = 9;
+    ///
+    /// let file = File::open("/dev/kvm")?;
+    ///
+    /// match unsafe { KVM_CHECK_EXTENSION.ioctl_nonzero(&file, KVM_CAP_NR_VCPUS)? } {
+    ///     Some(n) => println!("recommended maximum number of vCPUs: {n}"),
+    ///     None => println!("`KVM_CAP_NR_VCPUS` is not supported"),
+    /// }
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn ioctl_nonzero(
+        self,
+        fd: &impl AsRawFd,
+        arg: T,
+    ) -> io::Result<Option<NonZero<c_int>>> {
+        unsafe { self.ioctl(fd, arg).map(NonZero::new) }
+    }
+
+    /// Performs the `ioctl`, and converts its return value to `R`.
+    ///
+    /// Some `ioctl`s return meaningful data (like a count) on success, and this method converts it
+    /// to a dedicated type via [`FromIoctlReturn`].
+    /// Errors are reported just like with [`Ioctl::ioctl`], and so are errors from the conversion.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    ///
+    /// # Example
+    ///
+    /// `KVM_CHECK_EXTENSION` with `KVM_CAP_NR_MEMSLOTS` returns the maximum number of memory slots.
+    ///
+    /// From `linux/kvm.h`:
+    ///
+    /// ```c
+    /// #define KVM_CHECK_EXTENSION       _IO(KVMIO,   0x03)
+    /// ...
+    /// #define KVM_CAP_NR_MEMSLOTS 10   /* returns max memory slots per vm */
+    /// ```
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::ffi::c_int;
+    /// use uoctl::*;
+    ///
+    /// const KVMIO: u8 = 0xAE;
+    /// const KVM_CHECK_EXTENSION: Ioctl<c_int> = _IO(KVMIO, 0x03).cast_arg();
+    /// const KVM_CAP_NR_MEMSLOTS: c_int = 10;
+    ///
+    /// let file = File::open("/dev/kvm")?;
+    ///
+    /// let slots: usize = unsafe { KVM_CHECK_EXTENSION.ioctl_as(&file, KVM_CAP_NR_MEMSLOTS)? };
+    /// let mut used = vec![false; slots];
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn ioctl_as<R: FromIoctlReturn>(self, fd: &impl AsRawFd, arg: T) -> io::Result<R> {
+        unsafe { self.ioctl(fd, arg).and_then(R::from_ioctl_return) }
+    }
+
+    /// Performs the `ioctl`, and discards its return value.
+    ///
+    /// Most `ioctl`s return 0 on success, which carries no information.
+    /// This method can be used for them to avoid accidentally relying on the return value.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    pub unsafe fn ioctl_unit(self, fd: &impl AsRawFd, arg: T) -> io::Result<()> {
+        unsafe { self.ioctl_as(fd, arg) }
+    }
+
+    /// Performs the `ioctl` on a raw file descriptor.
+    ///
+    /// This is meant for code that manages the lifetime of the file descriptor elsewhere (like
+    /// async runtime integrations), and only has a [`RawFd`] at hand.
+    /// [`Ioctl::ioctl`] should be preferred whenever possible.
+    /// (Since [`RawFd`] implements [`AsRawFd`], `ioctl(&fd, arg)` is equivalent to this method.)
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    pub unsafe fn ioctl_raw(self, fd: RawFd, arg: T) -> io::Result<c_int> {
+        unsafe { self.ioctl(&fd, arg) }
+    }
+
+    /// Performs the `ioctl` on a file descriptor implementing [`AsFd`].
+    ///
+    /// Unlike [`Ioctl::ioctl`], this takes the file descriptor by value, so a [`BorrowedFd`] can
+    /// be passed directly, as well as references to types like [`File`] or [`OwnedFd`].
+    /// Since [`AsFd`] guarantees that the file descriptor stays open while it is borrowed, the
+    /// caller doesn't have to ensure that it is valid.
+    ///
+    /// [`BorrowedFd`]: std::os::fd::BorrowedFd
+    /// [`File`]: std::fs::File
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` belongs to the driver it expects.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{ffi::c_int, fs::File, os::fd::AsFd};
+    /// use uoctl::*;
+    ///
+    /// const FIONREAD: Ioctl<*mut c_int> = Ioctl::from_raw(libc::FIONREAD as _);
+    ///
+    /// let file = File::open("/dev/ptmx")?;
+    /// let mut bytes = c_int::MAX;
+    /// unsafe { FIONREAD.ioctl_fd(file.as_fd(), &mut bytes)? };
+    /// assert_ne!(bytes, c_int::MAX);
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn ioctl_fd(self, fd: impl AsFd, arg: T) -> io::Result<c_int> {
+        unsafe { self.ioctl(&fd.as_fd(), arg) }
+    }
+
+    /// Performs an `ioctl` that returns a new file descriptor, and takes ownership of it.
+    ///
+    /// Many `ioctl`s that create kernel objects (like `KVM_CREATE_VM` and `KVM_CREATE_VCPU`) return
+    /// a file descriptor referring to the new object.
+    /// This method wraps it in an [`OwnedFd`], which closes it when dropped.
+    ///
+    /// If the `ioctl` succeeds, but returns a negative value, an [`io::ErrorKind::InvalidData`]
+    /// error is returned.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    /// Additionally, the `ioctl` has to return a new file descriptor owned by the caller on success.
+    ///
+    /// # Example
+    ///
+    /// From `linux/kvm.h`:
+    ///
+    /// ```c
+    /// #define KVMIO 0xAE
+    /// ...
+    /// #define KVM_CREATE_VM             _IO(KVMIO,   0x01) /* returns a VM fd */
+    /// ```
+    ///
+    /// ```no_run
+    /// use std::fs::File;
+    /// use std::ffi::c_int;
+    /// use uoctl::*;
+    ///
+    /// const KVMIO: u8 = 0xAE;
+    /// const KVM_CREATE_VM: Ioctl<c_int> = _IO(KVMIO, 0x01).cast_arg();
+    ///
+    /// let file = File::open("/dev/kvm")?;
+    ///
+    /// // Closed when `vm` is dropped.
+    /// let vm = unsafe { KVM_CREATE_VM.ioctl_owned_fd(&file, 0)? };
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn ioctl_owned_fd(self, fd: &impl AsRawFd, arg: T) -> io::Result<OwnedFd> {
+        unsafe { self.ioctl(fd, arg).and_then(|res| owned_fd(res)) }
+    }
+
+    /// Performs an `ioctl` that returns a new file descriptor, takes ownership of it, and sets
+    /// `FD_CLOEXEC` on it.
+    ///
+    /// This works like [`Ioctl::ioctl_owned_fd`], but additionally sets the close-on-exec flag with
+    /// `fcntl(2)`, so that the file descriptor isn't inherited by programs started with
+    /// `execve(2)`.
+    /// If `fcntl(2)` fails, the file descriptor is closed and the error is returned.
+    ///
+    /// Since the flag can only be set after the `ioctl` returns, there is a brief window where the
+    /// file descriptor lacks `FD_CLOEXEC`. If another thread calls `fork(2)` and `execve(2)` during
+    /// that window, the new program will inherit it.
+    /// Many drivers avoid this by creating the file descriptor with `O_CLOEXEC` in the first place
+    /// (or accept a flag for this in their argument), which should be preferred where available.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    /// Additionally, the `ioctl` has to return a new file descriptor owned by the caller on success.
+    pub unsafe fn ioctl_owned_fd_cloexec(self, fd: &impl AsRawFd, arg: T) -> io::Result<OwnedFd> {
+        unsafe { self.ioctl_owned_fd(fd, arg).and_then(set_cloexec) }
+    }
+
+    /// Performs the `ioctl`, and returns errors as [`IoctlError`]s.
+    ///
+    /// This works like [`Ioctl::ioctl`], but the error can be matched on without inspecting the
+    /// raw OS error code of an [`io::Error`].
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::{ffi::c_int, fs::File};
+    /// use uoctl::*;
+    ///
+    /// const RNDGETENTCNT: Ioctl<*mut c_int> = _IOR(b'R', 0x00);
+    ///
+    /// let file = File::open("/dev/null")?;
+    /// let mut bytes = 0;
+    /// match unsafe { RNDGETENTCNT.try_ioctl(&file, &mut bytes) } {
+    ///     Ok(_) => println!("{bytes} bytes of entropy available"),
+    ///     Err(IoctlError::NotSupported) => println!("not a random device"),
+    ///     Err(e) => return Err(e.into()),
+    /// }
+    /// # std::io::Result::Ok(())
+    /// ```
+    pub unsafe fn try_ioctl(self, fd: &impl AsRawFd, arg: T) -> Result<c_int, IoctlError> {
+        unsafe { self.ioctl(fd, arg).map_err(IoctlError::from_io) }
+    }
+
+    /// Waits for `fd` to become ready with `poll(2)`, then performs the `ioctl`.
+    ///
+    /// This is useful with non-blocking file descriptors whose `ioctl`s don't block, but fail with
+    /// `EAGAIN` until the device is ready (for example, `VIDIOC_DQBUF` on a non-blocking V4L2
+    /// device).
+    ///
+    /// `events` is passed to `poll(2)` and selects the kind of readiness to wait for, typically
+    /// `libc::POLLIN` (for dequeuing data from the driver) or `libc::POLLOUT` (for queuing data).
+    /// If `timeout` is [`None`], this method waits indefinitely. Otherwise, it waits for at least
+    /// `timeout` (rounded up to whole milliseconds), and returns `Ok(None)` if `fd` did not become
+    /// ready in time.
+    ///
+    /// If `poll(2)` reports `POLLERR` or `POLLHUP`, the `ioctl` is still performed, so that the
+    /// driver can report the actual error condition.
+    /// If `fd` is not an open file descriptor (`POLLNVAL`), an `EBADF` error is returned.
+    ///
+    /// Errors from `poll(2)` are returned as-is, including `EINTR`.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    pub unsafe fn ioctl_poll(
+        self,
+        fd: &impl AsRawFd,
+        arg: T,
+        events: c_short,
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<c_int>> {
+        let timeout = match timeout {
+            None => -1,
+            // Round up, so that we never wait for less than the requested time.
+            Some(timeout) => {
+                c_int::try_from(timeout.as_nanos().div_ceil(1_000_000)).unwrap_or(c_int::MAX)
+            }
+        };
+        let mut pollfd = libc::pollfd {
+            fd: fd.as_raw_fd(),
+            events,
+            revents: 0,
+        };
+        match unsafe { libc::poll(&mut pollfd, 1, timeout) } {
+            -1 => return Err(io::Error::last_os_error()),
+            0 => return Ok(None),
+            _ => {}
+        }
+        if pollfd.revents & libc::POLLNVAL != 0 {
+            return Err(io::Error::from_raw_os_error(libc::EBADF));
+        }
+
+        unsafe { self.ioctl(fd, arg).map(Some) }
+    }
+
+    /// Performs the `ioctl` with `fd` temporarily switched to blocking or non-blocking mode.
+    ///
+    /// Some `ioctl`s behave differently depending on whether `O_NONBLOCK` is set: on a blocking
+    /// file descriptor, they wait until the driver is ready, while on a non-blocking one, they fail
+    /// with `EAGAIN` instead.
+    /// If `blocking` is `true`, this method clears `O_NONBLOCK` for the duration of the `ioctl`,
+    /// otherwise it sets it.
+    /// The original flags are restored afterwards, even if the `ioctl` fails.
+    ///
+    /// If `fd` is already in the requested mode, no flags are changed.
+    /// Otherwise, this performs two additional `fcntl(2)` calls to change the mode, and one to
+    /// restore it.
+    ///
+    /// Note that `O_NONBLOCK` is a property of the open file description, so the change is visible
+    /// to every file descriptor referring to it (including ones in other processes) while the
+    /// `ioctl` is running.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `ioctl` fails, or if the flags of `fd` cannot be queried or changed.
+    /// If the `ioctl` fails *and* the flags cannot be restored, the error of the `ioctl` is
+    /// returned.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    pub unsafe fn ioctl_blocking(
+        self,
+        fd: &impl AsRawFd,
+        arg: T,
+        blocking: bool,
+    ) -> io::Result<c_int> {
+        let raw = fd.as_raw_fd();
+        let flags = unsafe { libc::fcntl(raw, libc::F_GETFL) };
+        if flags == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let new_flags = if blocking {
+            flags & !libc::O_NONBLOCK
+        } else {
+            flags | libc::O_NONBLOCK
+        };
+        if new_flags == flags {
+            return unsafe { self.ioctl(fd, arg) };
+        }
+
+        if unsafe { libc::fcntl(raw, libc::F_SETFL, new_flags) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let res = unsafe { self.ioctl(fd, arg) };
+        if unsafe { libc::fcntl(raw, libc::F_SETFL, flags) } == -1 && res.is_ok() {
+            return Err(io::Error::last_os_error());
+        }
+        res
+    }
+
+    /// Performs the `ioctl`, falling back to `fallback` if the driver doesn't support it.
+    ///
+    /// Drivers often introduce a new version of an `ioctl` (like `FOO2`), while keeping the
+    /// original one (`FOO`) for compatibility.
+    /// This method can be used to prefer the new version, but still support older kernels: if
+    /// performing `self` fails with `ENOTTY` or `EINVAL` (which drivers return for unknown
+    /// `ioctl`s), `fallback` is performed with the same argument.
+    ///
+    /// Note that `EINVAL` might also be returned when the driver *does* support `self`, but
+    /// rejects the argument.
+    /// In that case, `fallback` will still be attempted.
+    ///
+    /// # Safety
+    ///
+    /// This method performs arbitrary `ioctl`s on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of *both* `ioctl`s are met, that `T`
+    /// denotes the correct argument type for both of them, and that `fd` is valid (open) and
+    /// belongs to the driver it expects.
+    pub unsafe fn ioctl_or(self, fallback: Ioctl<T>, fd: &impl AsRawFd, arg: T) -> io::Result<c_int>
+    where
+        T: Copy,
+    {
+        match unsafe { self.ioctl(fd, arg) } {
+            Err(e) if matches!(e.raw_os_error(), Some(libc::ENOTTY | libc::EINVAL)) => unsafe {
+                fallback.ioctl(fd, arg)
+            },
+            res => res,
+        }
+    }
+
+    /// Performs the `ioctl`, retrying it for as long as it fails with `EINTR`.
+    ///
+    /// `ioctl`s that block (like `VIDIOC_DQBUF` on a blocking V4L2 device) fail with `EINTR` when
+    /// the calling thread receives a signal while waiting, unless the signal handler was installed
+    /// with `SA_RESTART`.
+    /// This method performs the `ioctl` again in that case, with the same argument.
+    ///
+    /// [`Ioctl::ioctl`] does not retry, so it can be used when the caller needs to react to
+    /// signals promptly.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    /// Additionally, it has to be safe to perform the `ioctl` again after it was interrupted.
+    /// Drivers typically ensure this, but an interrupted `ioctl` may have already modified the data
+    /// behind a pointer argument.
+    pub unsafe fn ioctl_retry(self, fd: &impl AsRawFd, arg: T) -> io::Result<c_int>
+    where
+        T: Copy,
+    {
+        unsafe { self.ioctl_retry_n(fd, arg, usize::MAX) }
+    }
+
+    /// Performs the `ioctl`, retrying it up to `max_retries` times if it fails with `EINTR`.
+    ///
+    /// This works like [`Ioctl::ioctl_retry`], but gives up once the `ioctl` has been retried
+    /// `max_retries` times, and returns the last `EINTR` error.
+    /// This prevents a program that receives signals at a high rate from retrying forever.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    /// Additionally, it has to be safe to perform the `ioctl` again after it was interrupted (see
+    /// [`Ioctl::ioctl_retry`]).
+    pub unsafe fn ioctl_retry_n(
+        self,
+        fd: &impl AsRawFd,
+        arg: T,
+        max_retries: usize,
+    ) -> io::Result<c_int>
+    where
+        T: Copy,
+    {
+        let mut retries = 0;
+        loop {
+            match unsafe { self.ioctl(fd, arg) } {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted && retries < max_retries => {
+                    retries += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Performs the `ioctl`, using *errno* instead of the return value to detect failure.
+    ///
+    /// [`Ioctl::ioctl`] treats a return value of -1 as an error.
+    /// Some `ioctl`s can legitimately return -1 as data, however, which makes a failure
+    /// indistinguishable from a successful call by return value alone.
+    /// This method clears *errno* before performing the `ioctl`, and only reports an error if the
+    /// `ioctl` returns -1 *and* sets *errno*. A return value of -1 with *errno* still cleared is
+    /// returned as `Ok(-1)`.
+    /// On success, *errno* is restored to the value it had before the call.
+    ///
+    /// Note that the Linux kernel cannot return -1 from a successful `ioctl`, since it uses
+    /// negative return values to report errors (-1 is `EPERM`), so this is only needed on other
+    /// platforms, or with `ioctl(2)` implementations that don't go through the kernel.
+    ///
+    /// # Safety
+    ///
+    /// This method performs an arbitrary `ioctl` on an arbitrary file descriptor.
+    /// The caller has to ensure that any safety requirements of the `ioctl` are met, that `T`
+    /// denotes the correct argument type, and that `fd` is valid (open) and belongs to the driver
+    /// it expects.
+    pub unsafe fn ioctl_errno_checked(self, fd: &impl AsRawFd, arg: T) -> io::Result<c_int> {
+        unsafe {
+            ioctl_with_arg(fd.as_raw_fd(), self.request, arg, |fd, request, arg| {
+                let errno = platform::errno_location();
+                let saved = errno.read();
+                errno.write(0);
+                let res = errno_checked(libc::ioctl(fd, request as _, arg), errno.read());
+                if res.is_ok() {
+                    // Don't leave *errno* cleared after a successful call.
+                    errno.write(saved);
+                }
+                res
+            })
+        }
+    }
+}
+
+/// Performs an `ioctl` that takes an argument, using `call` to invoke `ioctl(2)`.
+///
+/// With the `mock` feature, `ioctl`s on a `MockFd` are handled by its handler instead.
+/// Otherwise, `call` is passed a bitwise copy of `arg`, which it has to hand to `ioctl(2)`.
+/// `arg` is dropped after `call` has returned.
+///
+/// # Safety
+///
+/// `call` must not unwind, and the caller has to uphold the safety requirements of
+/// [`Ioctl::ioctl`].
+#[cfg(feature = "std")]
+unsafe fn ioctl_with_arg<T>(
+    fd: RawFd,
+    request: u32,
+    arg: T,
+    call: impl FnOnce(RawFd, u32, T) -> io::Result<c_int>,
+) -> io::Result<c_int> {
+    #[cfg(feature = "mock")]
+    if let Some(res) = mock::intercept(fd, request, &arg) {
+        drop(arg);
+        return res;
+    }
+    // Values passed to a variadic foreign function are never dropped, so pass a bitwise copy
+    // and drop the original ourselves.
+    let res = call(fd, request, unsafe { ptr::read(&arg) });
+    drop(arg);
+    res
+}
+
+/// Interprets the return value of an `ioctl` that was performed with *errno* cleared.
+#[cfg(feature = "std")]
+fn errno_checked(res: c_int, errno: c_int) -> io::Result<c_int> {
+    if res == -1 && errno != 0 {
+        Err(io::Error::from_raw_os_error(errno))
+    } else {
+        Ok(res)
+    }
+}
+
+/// Indicates that an [`Ioctl`] does not take any arguments.
+///
+/// This is used as the type parameter of [`Ioctl`] by the [`_IO`] and [`_IOC`] functions.
+/// [`Ioctl<NoArgs>`] comes with its own, separate `IOCTL.ioctl(fd)` method that only takes the file
+/// descriptor as an argument.
+///
+/// Since [`NoArgs`] is the default value for [`Ioctl`]'s type parameter, it can typically be
+/// omitted.
+///
+/// # Example
+///
+/// The *uinput* ioctls `UI_DEV_CREATE` and `UI_DEV_DESTROY` do not take any arguments, while
+/// `UI_DEV_SETUP` *does* take an argument.
+///
+/// From `linux/uinput.h`:
+///
+/// ```c
+/// /* ioctl */
+/// #define UINPUT_IOCTL_BASE	'U'
+/// #define UI_DEV_CREATE		_IO(UINPUT_IOCTL_BASE, 1)
+/// #define UI_DEV_DESTROY		_IO(UINPUT_IOCTL_BASE, 2)
+/// ...
+/// #define UI_DEV_SETUP _IOW(UINPUT_IOCTL_BASE, 3, struct uinput_setup)
+/// ```
+///
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use std::{mem, fs::File, ffi::c_char};
+/// use libc::uinput_setup;
+/// use uoctl::*;
+///
+/// const UINPUT_IOCTL_BASE: u8 = b'U';
+/// const UI_DEV_CREATE: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 1);
+/// const UI_DEV_DESTROY: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 2);
+/// const UI_DEV_SETUP: Ioctl<*const uinput_setup> = _IOW(UINPUT_IOCTL_BASE, 3);
+///
+/// let uinput = File::options().write(true).open("/dev/uinput")?;
+///
+/// let mut setup: libc::uinput_setup = unsafe { mem::zeroed() };
+/// setup.name[0] = b'A' as c_char; // (must not be blank)
+/// unsafe {
+///     UI_DEV_SETUP.ioctl(&uinput, &setup)?;
+///     UI_DEV_CREATE.ioctl(&uinput)?;
+///     // ...use the device...
+///     UI_DEV_DESTROY.ioctl(&uinput)?;
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+pub struct NoArgs {
+    // Unsized type so that the `impl<T> Ioctl<T>` does not conflict.
+    _f: [u8],
+}
+
+/// Pointer types that can be used as the argument of an [`Ioctl`] created with [`ioctl_for`].
+///
+/// This trait is sealed, and only implemented for `*mut T` and `*const T`.
+pub trait IoctlPointer: sealed::Sealed {
+    /// The type the pointer points to, whose size is encoded in the `ioctl` request code.
+    type Pointee;
+}
+
+impl<T> IoctlPointer for *mut T {
+    type Pointee = T;
+}
+
+impl<T> IoctlPointer for *const T {
+    type Pointee = T;
+}
+
+/// Types that the successful return value of an `ioctl` can be converted to.
+///
+/// Used by [`Ioctl::ioctl_as`].
+#[cfg(feature = "std")]
+pub trait FromIoctlReturn: Sized {
+    /// Converts the value returned by a successful `ioctl(2)` call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `ret` can't be represented by `Self`.
+    fn from_ioctl_return(ret: c_int) -> io::Result<Self>;
+}
+
+#[cfg(feature = "std")]
+impl FromIoctlReturn for c_int {
+    #[inline]
+    fn from_ioctl_return(ret: c_int) -> io::Result<Self> {
+        Ok(ret)
+    }
+}
+
+/// Discards the return value.
+#[cfg(feature = "std")]
+impl FromIoctlReturn for () {
+    #[inline]
+    fn from_ioctl_return(_: c_int) -> io::Result<Self> {
+        Ok(())
+    }
+}
+
+/// Fails with [`io::ErrorKind::InvalidData`] if the return value is negative.
+#[cfg(feature = "std")]
+impl FromIoctlReturn for usize {
+    fn from_ioctl_return(ret: c_int) -> io::Result<Self> {
+        usize::try_from(ret).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("`ioctl` returned negative value {ret}"),
+            )
+        })
+    }
+}
+
+/// Identifies an `ioctl` by its request code, independent of its argument type.
+///
+/// Obtained via [`Ioctl::id`].
+/// Unlike a bare [`u32`], an [`IoctlId`] can't be confused with other integers, which makes it
+/// suitable as the key of registries and dispatch tables that handle many different `ioctl`s.
+///
+/// The [`Display`][fmt::Display] implementation shows the decoded request code (for example,
+/// `_IOC(_IOC_READ, 'V', 0x00, 104)`), while [`Debug`][fmt::Debug] shows the raw value.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IoctlId(u32);
+
+impl IoctlId {
+    /// Creates an [`IoctlId`] from a raw request code.
+    pub const fn from_raw(request: u32) -> Self {
+        Self(request)
+    }
+
+    /// Returns the `ioctl` request code.
+    pub const fn request(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Debug for IoctlId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IoctlId({:#x})", self.0)
+    }
+}
+
+impl fmt::Display for IoctlId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Decoded { dir, ty, nr, size } = Ioctl::<NoArgs>::from_raw(self.0).decode();
+        write!(f, "_IOC({dir:?}, ")?;
+        if ty.is_ascii_graphic() && ty != b'\\' && ty != b'\'' {
+            write!(f, "'{}'", ty as char)?;
+        } else {
+            write!(f, "{ty:#04x}")?;
+        }
+        write!(f, ", {nr:#04x}, {size})")
+    }
+}
+
+/// The fields of an `ioctl` request code, returned by [`Ioctl::decode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded {
+    /// The direction of the `ioctl`.
+    pub dir: Dir,
+    /// The `ioctl` type (or group).
+    pub ty: u8,
+    /// The `ioctl` number.
+    pub nr: u8,
+    /// The size of the `ioctl` argument.
+    pub size: usize,
+}
+
+/// A coarse classification of the result of an `ioctl`, returned by [`classify`].
+#[derive(Debug)]
+#[cfg(feature = "std")]
+pub enum IoctlOutcome {
+    /// The `ioctl` succeeded and returned the contained value.
+    Ready(c_int),
+    /// The `ioctl` failed with `EAGAIN` (or `EWOULDBLOCK`), because the file descriptor is in
+    /// non-blocking mode and the driver isn't ready yet.
+    WouldBlock,
+    /// The `ioctl` failed with `ENOTTY` or `EINVAL`, which drivers return for `ioctl`s they don't
+    /// support.
+    ///
+    /// Note that `EINVAL` is also commonly returned for supported `ioctl`s with an invalid argument.
+    NotSupported(io::Error),
+    /// The `ioctl` failed with any other error.
+    Error(io::Error),
+}
+
+/// Classifies the result of an `ioctl` into an [`IoctlOutcome`].
+///
+/// Higher-level wrappers often need to distinguish "the driver isn't ready yet" and "the driver
+/// doesn't support this" from other errors, and this function centralizes that logic.
+///
+/// # Example
+///
+/// ```
+/// use std::{ffi::c_int, fs::File};
+/// use uoctl::*;
+///
+/// const RNDGETENTCNT: Ioctl<*mut c_int> = _IOR(b'R', 0x00);
+///
+/// let file = File::open("/dev/null")?;
+/// let mut bytes = 0;
+/// match classify(unsafe { RNDGETENTCNT.ioctl(&file, &mut bytes) }) {
+///     IoctlOutcome::NotSupported(_) => println!("not a random device"),
+///     other => panic!("unexpected result: {other:?}"),
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[cfg(feature = "std")]
+pub fn classify(result: io::Result<c_int>) -> IoctlOutcome {
+    match result {
+        Ok(n) => IoctlOutcome::Ready(n),
+        Err(e) => match e.raw_os_error() {
+            Some(libc::EAGAIN) => IoctlOutcome::WouldBlock,
+            Some(libc::ENOTTY | libc::EINVAL) => IoctlOutcome::NotSupported(e),
+            _ => IoctlOutcome::Error(e),
+        },
+    }
+}
+
+/// The error returned by [`Ioctl::try_cast_arg`] and [`Ioctl::try_from_raw`] when the argument
+/// size doesn't match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeMismatch {
+    /// The argument size encoded in the request code.
+    pub encoded: usize,
+    /// The size of the argument type.
+    pub actual: usize,
+}
+
+impl fmt::Display for SizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "argument size mismatch: request code encodes {} bytes, but argument type has {} bytes",
+            self.encoded, self.actual,
+        )
+    }
+}
+
+impl Error for SizeMismatch {}
+
+/// An error returned by an `ioctl`, classified by its *errno* value.
+///
+/// Returned by [`Ioctl::try_ioctl`].
+/// It converts to an [`io::Error`] with the same OS error code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+#[cfg(feature = "std")]
+pub enum IoctlError {
+    /// `ENOTTY`: the file descriptor doesn't support the `ioctl` ("inappropriate ioctl for
+    /// device").
+    NotSupported,
+    /// `EFAULT`: a pointer in the argument refers to inaccessible memory.
+    BadAddress,
+    /// `EINVAL`: the argument is invalid.
+    ///
+    /// Some drivers also return this for `ioctl`s they don't support.
+    InvalidArgument,
+    /// `EINTR`: the `ioctl` was interrupted by a signal.
+    Interrupted,
+    /// Any other *errno* value.
+    Other(c_int),
+}
+
+#[cfg(feature = "std")]
+impl IoctlError {
+    /// Classifies an *errno* value.
+    pub const fn from_errno(errno: c_int) -> Self {
+        match errno {
+            libc::ENOTTY => Self::NotSupported,
+            libc::EFAULT => Self::BadAddress,
+            libc::EINVAL => Self::InvalidArgument,
+            libc::EINTR => Self::Interrupted,
+            _ => Self::Other(errno),
+        }
+    }
+
+    /// Returns the *errno* value of the error.
+    pub const fn errno(self) -> c_int {
+        match self {
+            Self::NotSupported => libc::ENOTTY,
+            Self::BadAddress => libc::EFAULT,
+            Self::InvalidArgument => libc::EINVAL,
+            Self::Interrupted => libc::EINTR,
+            Self::Other(errno) => errno,
+        }
+    }
+
+    /// Errors without an OS error code can only come from a `MockFd` handler, and are reported as
+    /// `EIO`.
+    fn from_io(e: io::Error) -> Self {
+        Self::from_errno(e.raw_os_error().unwrap_or(libc::EIO))
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for IoctlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        io::Error::from_raw_os_error(self.errno()).fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for IoctlError {}
+
+#[cfg(feature = "std")]
+impl From<IoctlError> for io::Error {
+    fn from(e: IoctlError) -> Self {
+        io::Error::from_raw_os_error(e.errno())
+    }
+}
+
+mod sealed {
+    pub trait Sealed {
+        /// Whether the kernel is allowed to write through the pointer.
+        const MUT: bool;
+    }
+
+    impl<T> Sealed for *mut T {
+        const MUT: bool = true;
+    }
+
+    impl<T> Sealed for *const T {
+        const MUT: bool = false;
+    }
+}
+
+/// Direction of an [`Ioctl`].
+///
+/// Used by [`_IOC`]. Constructed by using the constants [`_IOC_NONE`], [`_IOC_READ`], and
+/// [`_IOC_WRITE`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Dir(u32);
+
+impl Dir {
+    /// Creates a [`Dir`] from the raw bits of a direction field.
+    ///
+    /// The meaning of the bits is platform-specific: they are the value of the direction field
+    /// after it has been shifted into the low bits on Linux, and the unshifted `IOC_VOID`,
+    /// `IOC_OUT`, and `IOC_IN` bits on BSDs (this is what [`Ioctl::as_tuple`] returns).
+    ///
+    /// `bits` is not validated, so the resulting [`Dir`] may not correspond to any valid
+    /// direction. Passing it to [`_IOC`] will then produce a malformed request code.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// let (dir, ..) = _IOR::<u32>(b'V', 0).as_tuple();
+    /// assert_eq!(Dir::from_raw(dir), _IOC_READ);
+    /// ```
+    #[inline]
+    pub const fn from_raw(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw, platform-specific bits of the direction.
+    ///
+    /// This is the inverse of [`Dir::from_raw`].
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether this direction includes [`_IOC_READ`] (the kernel writes to the argument).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// assert!(_IOC_READ.is_read());
+    /// assert!((_IOC_READ | _IOC_WRITE).is_read());
+    /// assert!(!_IOC_NONE.is_read());
+    /// ```
+    #[inline]
+    pub const fn is_read(self) -> bool {
+        self.0 & platform::_IOC_READ != 0
+    }
+
+    /// Returns whether this direction includes [`_IOC_WRITE`] (the kernel reads the argument).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// assert!(_IOC_WRITE.is_write());
+    /// assert!((_IOC_READ | _IOC_WRITE).is_write());
+    /// assert!(!_IOC_NONE.is_write());
+    /// ```
+    #[inline]
+    pub const fn is_write(self) -> bool {
+        self.0 & platform::_IOC_WRITE != 0
+    }
+
+    /// Returns whether this direction includes every data transfer of `other`.
+    ///
+    /// Since [`_IOC_NONE`] doesn't transfer any data, every direction contains it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// assert!((_IOC_READ | _IOC_WRITE).contains(_IOC_READ));
+    /// assert!(!_IOC_READ.contains(_IOC_READ | _IOC_WRITE));
+    /// assert!(_IOC_READ.contains(_IOC_NONE));
+    /// ```
+    #[inline]
+    pub const fn contains(self, other: Dir) -> bool {
+        (self.is_read() || !other.is_read()) && (self.is_write() || !other.is_write())
+    }
+}
+
+impl BitOr for Dir {
+    type Output = Dir;
+
+    #[inline]
+    fn bitor(self, rhs: Self) -> Self::Output {
+        // `_IOC_NONE` is 0 on x86, but non-zero on other architectures. It is invalid and
+        // non-portable to combine it with other usages, so we prevent it here.
+        // The same applies to the BSD encoding, where `IOC_VOID` (aka `_IOC_NONE`) is a separate
+        // bit that must not be combined with `IOC_IN` or `IOC_OUT`.
+        // This check will easily optimize out in almost all cases, since the direction is nearly
+        // always a compile-time constant.
+        if (self == _IOC_NONE && rhs != _IOC_NONE) || (self != _IOC_NONE && rhs == _IOC_NONE) {
+            panic!("`_IOC_NONE` cannot be combined with other values");
+        }
+
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl fmt::Debug for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == _IOC_READ | _IOC_WRITE {
+            f.write_str("_IOC_READ | _IOC_WRITE")
+        } else if *self == _IOC_READ {
+            f.write_str("_IOC_READ")
+        } else if *self == _IOC_WRITE {
+            f.write_str("_IOC_WRITE")
+        } else if *self == _IOC_NONE {
+            f.write_str("_IOC_NONE")
+        } else {
+            write!(f, "{:#x}", self.0)
+        }
+    }
+}
+
+/// Formats the direction compactly, as `none`, `r`, `w`, or `rw`.
+///
+/// Invalid directions are formatted as their raw bits in hexadecimal.
+///
+/// # Example
+///
+/// ```
+/// use uoctl::*;
+///
+/// assert_eq!(_IOC_READ.to_string(), "r");
+/// assert_eq!((_IOC_READ | _IOC_WRITE).to_string(), "rw");
+/// assert_eq!(_IOC_NONE.to_string(), "none");
+/// ```
+impl fmt::Display for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == _IOC_READ | _IOC_WRITE {
+            f.write_str("rw")
+        } else if *self == _IOC_READ {
+            f.write_str("r")
+        } else if *self == _IOC_WRITE {
+            f.write_str("w")
+        } else if *self == _IOC_NONE {
+            f.write_str("none")
+        } else {
+            write!(f, "{:#x}", self.0)
+        }
+    }
+}
+
+/// Indicates that an `ioctl` neither reads nor writes data through its argument.
+///
+/// Identical to [`IOC_VOID`]. [`_IOC_NONE`] is a Linuxism, while [`IOC_VOID`] is used by other
+/// systems.
+pub const _IOC_NONE: Dir = Dir(platform::_IOC_NONE);
+
+/// Indicates that an `ioctl` reads data from the kernel through its pointer argument.
+///
+/// Identical to [`IOC_OUT`]. [`_IOC_READ`] is a Linuxism, while [`IOC_OUT`] is used by other
+/// systems.
+pub const _IOC_READ: Dir = Dir(platform::_IOC_READ);
+
+/// Indicates that an `ioctl` writes data to the kernel through its pointer argument.
+///
+/// Identical to [`IOC_IN`]. [`_IOC_WRITE`] is a Linuxism, while [`IOC_IN`] is used by other
+/// systems.
+pub const _IOC_WRITE: Dir = Dir(platform::_IOC_WRITE);
+
+/// Indicates that an `ioctl` both reads and writes data through its pointer argument.
+///
+/// Equivalent to `_IOC_READ | _IOC_WRITE`, which doesn't work in `const` contexts.
+///
+/// C code always uses `_IOC_READ | _IOC_WRITE` instead of a dedicated constant.
+pub const _IOC_READ_WRITE: Dir = Dir(platform::_IOC_READ | platform::_IOC_WRITE);
+
+/// Indicates that an `ioctl` neither reads nor writes data through its argument.
+///
+/// Identical to [`_IOC_NONE`].
+pub const IOC_VOID: Dir = _IOC_NONE;
+
+/// Indicates that an `ioctl` reads data from the kernel through its pointer argument.
+///
+/// Identical to [`_IOC_READ`].
+pub const IOC_OUT: Dir = _IOC_READ;
+
+/// Indicates that an `ioctl` writes data to the kernel through its pointer argument.
+///
+/// Identical to [`_IOC_WRITE`].
+pub const IOC_IN: Dir = _IOC_WRITE;
+
+/// Indicates that an `ioctl` both reads and writes data through its pointer argument.
+///
+/// Identical to [`_IOC_READ_WRITE`] and `_IOC_READ | _IOC_WRITE`.
+pub const IOC_INOUT: Dir = _IOC_READ_WRITE;
+
+/// The largest argument size that can be encoded in a request code.
+///
+/// [`_IOC`] (and every function built on it) panics when passed a larger size, so this can be
+/// used to validate sizes up front.
+///
+/// The size field has 13 bits in the encoding used by the BSDs and by Linux on Alpha, MIPS,
+/// PowerPC, and SPARC, but 14 bits in the generic Linux encoding used by most other
+/// architectures.
+/// To keep bindings portable, this library limits sizes to 13 bits on all platforms, so this is
+/// always 8191.
+///
+/// # Example
+///
+/// ```
+/// use std::ffi::c_char;
+/// use uoctl::*;
+///
+/// const fn UI_GET_SYSNAME(len: usize) -> Ioctl<*mut c_char> {
+///     _IOC(_IOC_READ, b'U', 44, len)
+/// }
+///
+/// let len = 100_000usize.min(MAX_ARG_SIZE);
+/// assert_eq!(UI_GET_SYSNAME(len).size(), MAX_ARG_SIZE);
+/// ```
+pub const MAX_ARG_SIZE: usize = platform::MAX_ARG_SIZE;
+
+/// Creates an [`Ioctl`] that doesn't read or write any userspace data.
+///
+/// This type of ioctl can return an `int` to userspace via the return value of the `ioctl` syscall.
+/// By default, the returned [`Ioctl`] takes no argument.
+/// [`Ioctl::cast_arg`] can be used to pass a direct argument to the `ioctl`.
+///
+/// # Example
+///
+/// `KVM_GET_API_VERSION` is an `ioctl` that does not take any arguments. The API version is
+/// returned as the return value of the `ioctl(2)` function.
+///
+/// From `linux/kvm.h`:
+///
+/// ```c
+/// #define KVMIO 0xAE
+/// ...
+/// #define KVM_GET_API_VERSION       _IO(KVMIO,   0x00)
+/// ```
+///
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use std::fs::File;
+/// use uoctl::*;
+///
+/// const KVMIO: u8 = 0xAE;
+/// const KVM_GET_API_VERSION: Ioctl<NoArgs> = _IO(KVMIO, 0x00);
+///
+/// let file = File::open("/dev/kvm")?;
+///
+/// let version = unsafe { KVM_GET_API_VERSION.ioctl(&file)? };
+/// println!("KVM API version: {version}");
+/// # std::io::Result::Ok(())
+/// ```
+#[allow(non_snake_case)]
+pub const fn _IO(ty: u8, nr: u8) -> Ioctl<NoArgs> {
+    _IOC(_IOC_NONE, ty, nr, 0)
+}
+
+/// Creates an [`Ioctl`] that reads data of type `T` from the kernel.
+///
+/// By default, a pointer to the data will be passed to `ioctl(2)`, and the kernel will fill the
+/// destination with data.
+///
+/// Since the kernel doesn't read the destination, it does not have to be initialized: a pointer
+/// obtained from [`MaybeUninit::as_mut_ptr`] can be passed (see the [crate-level
+/// example][crate]).
+/// This is the only kind of `ioctl` for which that is the case.
+///
+/// # Errors
+///
+/// This method will cause a compile-time assertion failure if the size of `T` exceeds the `ioctl`
+/// argument size limit.
+/// This typically means that the wrong type `T` was specified.
+///
+/// # Examples
+///
+/// From `linux/random.h`:
+///
+/// ```c
+/// /* ioctl()'s for the random number generator */
+///
+/// /* Get the entropy count. */
+/// #define RNDGETENTCNT	_IOR( 'R', 0x00, int )
+/// ```
+///
+#[cfg_attr(feature = "std", doc = "```")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use std::fs::File;
+/// use std::ffi::c_int;
+/// use uoctl::*;
+///
+/// const RNDGETENTCNT: Ioctl<*mut c_int> = _IOR(b'R', 0x00);
+///
+/// let file = File::open("/dev/urandom")?;
+///
+/// let mut entropy = 0;
+/// unsafe { RNDGETENTCNT.ioctl(&file, &mut entropy)? };
+///
+/// println!("{entropy} bits of entropy in /dev/urandom");
+/// # std::io::Result::Ok(())
+/// ```
+#[allow(non_snake_case)]
+pub const fn _IOR<T>(ty: u8, nr: u8) -> Ioctl<*mut T> {
+    const {
+        assert!(size_of::<T>() <= platform::MAX_ARG_SIZE);
+    }
+    _IOC(_IOC_READ, ty, nr, size_of::<T>())
+}
+
+/// Creates an [`Ioctl`] that writes data of type `T` to the kernel.
+///
+/// By default, a pointer to the data will be passed to `ioctl(2)`, and the kernel will read the
+/// argument from that location.
+/// This is generally correct if the argument is a `struct`, but if the argument is a primitive type
+/// like `int`, or is already a pointer like `char*`, many drivers expect the argument to be passed
+/// to `ioctl(2)` *without* indirection.
+/// To bind to those `ioctl`s, you can call [`Ioctl::with_direct_arg`] on the [`Ioctl`] returned by
+/// [`_IOW`].
+///
+/// **Note**: Some Linux `ioctl`s are **incorrectly** declared with [`_IOW`].
+/// They *will* write to the argument, and if the pointer you pass doesn't have write permission it
+/// *will* cause UB.
+/// Use [`Ioctl::cast_mut`] before invoking the [`Ioctl`] to fix these kinds of misdeclared
+/// `ioctl`s!
+///
+/// Apart from reading comments in the header files, and looking at the Linux source code, there is
+/// no reliable way of finding out which `ioctl` definitions are wrong like that.
+/// Good luck!
+///
+/// # Initialization
+///
+/// The kernel reads the argument, so it has to be fully initialized.
+/// A `&T` coerces to the `*const T` argument of the [`Ioctl`], but a `&MaybeUninit<T>` does not,
+/// so uninitialized data can only be passed by explicitly converting it to a pointer (which is UB
+/// unless the data has actually been initialized):
+///
+/// ```compile_fail
+/// use std::mem::MaybeUninit;
+/// use uoctl::*;
+///
+/// const UI_DEV_SETUP: Ioctl<*const [u8; 92]> = _IOW(b'U', 3);
+///
+/// let setup = MaybeUninit::<[u8; 92]>::uninit();
+/// unsafe { UI_DEV_SETUP.ioctl(&0, &setup)? };
+/// # std::io::Result::Ok(())
+/// ```
+///
+/// # Errors
+///
+/// This method will cause a compile-time assertion failure if the size of `T` exceeds the `ioctl`
+/// argument size limit.
+/// This typically means that the wrong type `T` was specified.
+///
+/// # Example
+///
+/// Let's create a virtual input device with *uinput*.
+///
+/// From `linux/uinput.h`:
+///
+/// ```c
+/// /* ioctl */
+/// #define UINPUT_IOCTL_BASE	'U'
+/// #define UI_DEV_CREATE		_IO(UINPUT_IOCTL_BASE, 1)
+/// #define UI_DEV_DESTROY		_IO(UINPUT_IOCTL_BASE, 2)
+/// ...
+/// #define UI_DEV_SETUP _IOW(UINPUT_IOCTL_BASE, 3, struct uinput_setup)
+/// ...
+/// #define UI_SET_EVBIT		_IOW(UINPUT_IOCTL_BASE, 100, int)
+/// #define UI_SET_KEYBIT		_IOW(UINPUT_IOCTL_BASE, 101, int)
+/// ```
+///
+/// From `linux/input.h`:
+///
+/// ```c
+/// #define EV_KEY			0x01
+/// ...
+/// #define KEY_A			30
+/// ```
+///
+#[cfg_attr(feature = "std", doc = "```rust")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use std::{mem, fs::File, ffi::{c_char, c_int}};
+/// use libc::uinput_setup;
+/// use uoctl::*;
+///
+/// const UINPUT_IOCTL_BASE: u8 = b'U';
+/// const UI_DEV_CREATE: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 1);
+/// const UI_DEV_DESTROY: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 2);
+/// const UI_DEV_SETUP: Ioctl<*const uinput_setup> = _IOW(UINPUT_IOCTL_BASE, 3);
+/// // These two expect their argument to be passed directly instead of behind a pointer:
+/// const UI_SET_EVBIT: Ioctl<c_int> = _IOW(UINPUT_IOCTL_BASE, 100).with_direct_arg();
+/// const UI_SET_KEYBIT: Ioctl<c_int> = _IOW(UINPUT_IOCTL_BASE, 101).with_direct_arg();
+///
+/// const EV_KEY: c_int = 0x01;
+/// const KEY_A: c_int = 30;
+///
+/// let uinput = File::options().write(true).open("/dev/uinput")?;
+///
+/// // Enable the "A" key:
+/// unsafe {
+///     UI_SET_EVBIT.ioctl(&uinput, EV_KEY)?;
+///     UI_SET_KEYBIT.ioctl(&uinput, KEY_A)?;
+/// }
+///
+/// let mut setup: uinput_setup = unsafe { mem::zeroed() };
+/// setup.name[0] = b'A' as c_char; // (must not be blank)
+/// unsafe {
+///     UI_DEV_SETUP.ioctl(&uinput, &setup)?;
+///     UI_DEV_CREATE.ioctl(&uinput)?;
+///     // ...use the device...
+///     UI_DEV_DESTROY.ioctl(&uinput)?;
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[allow(non_snake_case)]
+pub const fn _IOW<T>(ty: u8, nr: u8) -> Ioctl<*const T> {
+    const {
+        assert!(size_of::<T>() <= platform::MAX_ARG_SIZE);
+    }
+    _IOC(_IOC_WRITE, ty, nr, size_of::<T>())
+}
+
+/// Creates an [`Ioctl`] that writes and reads data of type `T`.
+///
+/// By default, a pointer to the data will be passed to `ioctl(2)`, and the kernel will read and
+/// write to the data `T`.
+///
+/// Since the kernel reads the argument before writing to it, it has to be fully initialized, just
+/// like with [`_IOW`]:
+///
+/// ```compile_fail
+/// use std::mem::MaybeUninit;
+/// use uoctl::*;
+///
+/// const VIDIOC_REQBUFS: Ioctl<*mut [u32; 5]> = _IOWR(b'V', 8);
+///
+/// let mut req = MaybeUninit::<[u32; 5]>::uninit();
+/// unsafe { VIDIOC_REQBUFS.ioctl(&0, &mut req)? };
+/// # std::io::Result::Ok(())
+/// ```
+///
+/// # Errors
+///
+/// This method will cause a compile-time assertion failure if the size of `T` exceeds the `ioctl`
+/// argument size limit.
+/// This typically means that the wrong type `T` was specified.
+#[allow(non_snake_case)]
+pub const fn _IOWR<T>(ty: u8, nr: u8) -> Ioctl<*mut T> {
+    const {
+        assert!(size_of::<T>() <= platform::MAX_ARG_SIZE);
+    }
+    _IOC(_IOC_READ_WRITE, ty, nr, size_of::<T>())
+}
+
+/// Creates an [`Ioctl`] that reads data of type `T`, but encodes the size of `S`.
+///
+/// This is like [`_IOR`], but for `ioctl`s whose request code was declared with a different type
+/// than the one userspace actually passes.
+/// This happens with versioned `struct`s, where newer versions of a `struct` are appended to an
+/// older version, but the `ioctl` code (and the size encoded in it) is kept the same.
+///
+/// # Errors
+///
+/// This method will cause a compile-time assertion failure if the size of `S` exceeds the `ioctl`
+/// argument size limit.
+///
+/// # Example
+///
+/// ```
+/// use uoctl::*;
+///
+/// #[repr(C)]
+/// struct InfoV1 {
+///     version: u32,
+///     flags: u32,
+/// }
+///
+/// #[repr(C)]
+/// struct InfoV2 {
+///     v1: InfoV1,
+///     extra: [u32; 4],
+/// }
+///
+/// // The request code remains the one of the original `struct`.
+/// const GET_INFO: Ioctl<*mut InfoV2> = _IOR_sized_as::<InfoV1, _>(b'z', 0);
+/// assert_eq!(GET_INFO.request(), _IOR::<InfoV1>(b'z', 0).request());
+/// ```
+#[allow(non_snake_case)]
+pub const fn _IOR_sized_as<S, T>(ty: u8, nr: u8) -> Ioctl<*mut T> {
+    const {
+        assert!(size_of::<S>() <= platform::MAX_ARG_SIZE);
+    }
+    _IOC(_IOC_READ, ty, nr, size_of::<S>())
+}
+
+/// Creates an [`Ioctl`] that writes data of type `T`, but encodes the size of `S`.
+///
+/// This is like [`_IOW`], but for `ioctl`s whose request code was declared with a different type
+/// than the one userspace actually passes (see [`_IOR_sized_as`]).
+///
+/// # Errors
+///
+/// This method will cause a compile-time assertion failure if the size of `S` exceeds the `ioctl`
+/// argument size limit.
+#[allow(non_snake_case)]
+pub const fn _IOW_sized_as<S, T>(ty: u8, nr: u8) -> Ioctl<*const T> {
+    const {
+        assert!(size_of::<S>() <= platform::MAX_ARG_SIZE);
+    }
+    _IOC(_IOC_WRITE, ty, nr, size_of::<S>())
+}
+
+/// Creates an [`Ioctl`] that writes and reads data of type `T`, but encodes the size of `S`.
+///
+/// This is like [`_IOWR`], but for `ioctl`s whose request code was declared with a different type
+/// than the one userspace actually passes (see [`_IOR_sized_as`]).
+///
+/// # Errors
+///
+/// This method will cause a compile-time assertion failure if the size of `S` exceeds the `ioctl`
+/// argument size limit.
+#[allow(non_snake_case)]
+pub const fn _IOWR_sized_as<S, T>(ty: u8, nr: u8) -> Ioctl<*mut T> {
+    const {
+        assert!(size_of::<S>() <= platform::MAX_ARG_SIZE);
+    }
+    _IOC(_IOC_READ_WRITE, ty, nr, size_of::<S>())
+}
+
+/// Creates an [`Ioctl`] that reads data of type `T`, but encodes an explicit `size`.
+///
+/// This is the equivalent of the kernel's `_IOR_BAD` macro, which is used for legacy `ioctl`s whose
+/// request code was computed with the wrong size (like the size of a pointer, or of a `long`
+/// instead of an `int`).
+/// Unlike with [`_IOR_sized_as`], the size is given as a number, so that codes that don't
+/// correspond to any convenient Rust type can be reproduced exactly.
+///
+/// # Panics
+///
+/// This function may panic when `size` exceeds [`MAX_ARG_SIZE`].
+///
+/// # Example
+///
+/// `FS_IOC_GETFLAGS` is declared with a `long` argument, but the kernel actually writes an `int`.
+///
+/// From `linux/fs.h`:
+///
+/// ```c
+/// #define	FS_IOC_GETFLAGS			_IOR('f', 1, long)
+/// ```
+///
+/// ```
+/// use std::ffi::{c_int, c_long};
+/// use uoctl::*;
+///
+/// const FS_IOC_GETFLAGS: Ioctl<*mut c_int> = _IOR_BAD(b'f', 1, size_of::<c_long>());
+/// assert_eq!(FS_IOC_GETFLAGS.request(), _IOR::<c_long>(b'f', 1).request());
+/// ```
+#[allow(non_snake_case)]
+pub const fn _IOR_BAD<T>(ty: u8, nr: u8, size: usize) -> Ioctl<*mut T> {
+    _IOC(_IOC_READ, ty, nr, size)
+}
+
+/// Creates an [`Ioctl`] that writes data of type `T`, but encodes an explicit `size`.
+///
+/// This is the equivalent of the kernel's `_IOW_BAD` macro (see [`_IOR_BAD`]).
+///
+/// # Panics
+///
+/// This function may panic when `size` exceeds [`MAX_ARG_SIZE`].
+#[allow(non_snake_case)]
+pub const fn _IOW_BAD<T>(ty: u8, nr: u8, size: usize) -> Ioctl<*const T> {
+    _IOC(_IOC_WRITE, ty, nr, size)
+}
+
+/// Creates an [`Ioctl`] that writes and reads data of type `T`, but encodes an explicit `size`.
+///
+/// This is the equivalent of the kernel's `_IOWR_BAD` macro (see [`_IOR_BAD`]).
+///
+/// # Panics
+///
+/// This function may panic when `size` exceeds [`MAX_ARG_SIZE`].
+#[allow(non_snake_case)]
+pub const fn _IOWR_BAD<T>(ty: u8, nr: u8, size: usize) -> Ioctl<*mut T> {
+    _IOC(_IOC_READ_WRITE, ty, nr, size)
+}
+
+/// Creates an [`Ioctl`] taking the pointer type `P`, which transfers data in direction `dir`.
+///
+/// This is a generic version of [`_IOR`], [`_IOW`], and [`_IOWR`], meant for macros and generated
+/// code: the size of the pointee is encoded in the request code, so `ioctl_for::<*mut T>(_IOC_READ,
+/// ty, nr)` is equivalent to `_IOR::<T>(ty, nr)`, `ioctl_for::<*const T>(_IOC_WRITE, ty, nr)` is
+/// equivalent to `_IOW::<T>(ty, nr)`, and `ioctl_for::<*mut T>(_IOC_READ_WRITE, ty, nr)` is
+/// equivalent to `_IOWR::<T>(ty, nr)`.
+///
+/// It is also the typed counterpart of [`_IOC`]: it can be used with any direction, including ones
+/// that don't match the usual [`_IOR`]/[`_IOW`]/[`_IOWR`] patterns, while still deriving the size
+/// from the argument type.
+///
+/// # Errors
+///
+/// This function will cause a compile-time assertion failure if the size of the pointee exceeds
+/// the `ioctl` argument size limit.
+///
+/// # Panics
+///
+/// This function will panic if `dir` includes [`_IOC_READ`] (ie. the kernel writes to the
+/// argument), but `P` is a `*const` pointer.
+/// When used to initialize a `const`, this results in a compile-time error instead.
+///
+/// # Example
+///
+/// ```
+/// use uoctl::*;
+///
+/// const RNDGETENTCNT: Ioctl<*mut i32> = ioctl_for(_IOC_READ, b'R', 0x00);
+/// assert_eq!(RNDGETENTCNT.request(), _IOR::<i32>(b'R', 0x00).request());
+/// ```
+pub const fn ioctl_for<P: IoctlPointer>(dir: Dir, ty: u8, nr: u8) -> Ioctl<P> {
+    const {
+        assert!(size_of::<P::Pointee>() <= platform::MAX_ARG_SIZE);
+    }
+    assert!(
+        <P as sealed::Sealed>::MUT || dir.0 & platform::_IOC_READ == 0,
+        "the kernel writes to the argument of this `ioctl`, so it must be a `*mut` pointer",
+    );
+    _IOC(dir, ty, nr, size_of::<P::Pointee>())
+}
+
+/// Creates an [`Ioctl`] that writes an `int` to the kernel.
+///
+/// This is a BSD-specific function that only exists in the BSD C headers. Using it on other systems
+/// may not result in the correct `ioctl` request code.
+///
+/// Linux does not have a function/macro like this, and it typically uses [`_IOW`] to define
+/// `ioctl`s that pass `int`s (often necessitating a call to [`Ioctl::with_direct_arg`]).
+///
+/// There are no read or read-write equivalents: the BSD kernels only pass the argument of
+/// [`IOC_VOID`] `ioctl`s through as-is, and copy the data of [`IOC_OUT`] and [`IOC_INOUT`]
+/// `ioctl`s through the pointer passed as the argument.
+/// `ioctl`s that return an `int` have to be declared with `_IOR::<c_int>` or `_IOWR::<c_int>`.
+#[allow(non_snake_case)]
+pub const fn _IOWINT(group: u8, nr: u8) -> Ioctl<c_int> {
+    _IOC(IOC_VOID, group, nr, size_of::<c_int>())
+}
+
+/// Creates an [`Ioctl`] that transfers a bitmask of `len` bytes.
+///
+/// This is equivalent to `_IOC::<*mut u8>(dir, ty, nr, len)`, and is meant for polymorphic `ioctl`s
+/// that read (or write) a bitmask whose length in bytes is encoded in the request code, like
+/// evdev's `EVIOCGKEY(len)` (see [`evdev::EVIOCGKEY`]).
+///
+/// # Panics
+///
+/// This function may panic when `len` exceeds [`MAX_ARG_SIZE`].
+#[inline]
+pub const fn bitmask_ioctl(dir: Dir, ty: u8, nr: u8, len: usize) -> Ioctl<*mut u8> {
+    _IOC(dir, ty, nr, len)
+}
+
+/// Computes an `ioctl` number from a base number and an offset, panicking on overflow.
+///
+/// Some drivers define a range of `ioctl`s whose number is computed from a base number and an
+/// index (like a channel number).
+/// Computing `base + offset` with plain arithmetic would silently wrap around in release builds,
+/// resulting in the wrong request code; this function panics instead.
+/// When used in a `const` context, the panic turns into a compile-time error.
+///
+/// # Panics
+///
+/// This function panics if `base + offset` doesn't fit in the 8-bit `ioctl` number.
+///
+/// # Example
+///
+/// ```
+/// use uoctl::*;
+///
+/// const FOO_GET_CHANNEL_BASE: u8 = 0x20;
+///
+/// const fn FOO_GET_CHANNEL(channel: u8) -> Ioctl<*mut u32> {
+///     _IOR(b'X', checked_nr(FOO_GET_CHANNEL_BASE, channel))
+/// }
+///
+/// assert_eq!(FOO_GET_CHANNEL(3).nr(), 0x23);
+/// ```
+pub const fn checked_nr(base: u8, offset: u8) -> u8 {
+    match base.checked_add(offset) {
+        Some(nr) => nr,
+        None => panic!("`ioctl` number overflows its 8-bit field"),
+    }
+}
+
+/// A scheme for encoding the components of an `ioctl` in its request code.
+///
+/// The encoding used by this library is selected at compile time, based on the target operating
+/// system and architecture, and can be queried with [`encoding`].
+///
+/// [`Encoding::encode`] and [`Encoding::decode`] work with request codes in any encoding,
+/// regardless of the target, which is useful for tools that handle request codes captured on
+/// another platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// The generic Linux encoding (from `asm-generic/ioctl.h`), used by most architectures.
+    ///
+    /// It has 2 direction bits, and 14 size bits.
+    LinuxGeneric,
+    /// The Linux encoding used on Alpha, MIPS, PowerPC, and SPARC.
+    ///
+    /// It has 3 direction bits (with a dedicated bit for `_IOC_NONE`), and 13 size bits.
+    LinuxAlternate,
+    /// The encoding used by the BSDs (including macOS).
+    ///
+    /// It has 3 direction bits (`IOC_VOID`, `IOC_OUT`, and `IOC_IN`), and 13 size bits.
+    Bsd,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::LinuxGeneric => "Linux (generic)",
+            Encoding::LinuxAlternate => "Linux (Alpha/MIPS/PowerPC/SPARC)",
+            Encoding::Bsd => "BSD",
+        })
+    }
+}
+
+impl Encoding {
+    /// Returns the number of size bits, and the direction bits for "none", "read", and "write".
+    const fn layout(self) -> (u32, u32, u32, u32) {
+        match self {
+            Encoding::LinuxGeneric => (14, 0, 2, 1),
+            // The BSD `IOC_VOID`, `IOC_OUT`, and `IOC_IN` bits are in the same place.
+            Encoding::LinuxAlternate | Encoding::Bsd => (13, 1, 2, 4),
+        }
+    }
+
+    /// Encodes a request code in this encoding.
+    ///
+    /// This works like [`_IOC`], but uses the bit layout of `self` instead of the one of the
+    /// target.
+    /// Only the meaning of `dir` ([`Dir::is_read`] and [`Dir::is_write`]) is used, so the
+    /// [`Dir`] constants of the target can be used with any encoding.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `size` doesn't fit in the size field of the encoding.
+    ///
+    /// # Example
+    ///
+    /// Encoding `VIDIOC_QUERYCAP` (`_IOR('V', 0, struct v4l2_capability)`) for x86-64 and
+    /// PowerPC:
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// assert_eq!(Encoding::LinuxGeneric.encode(_IOC_READ, b'V', 0, 104), 0x80685600);
+    /// assert_eq!(Encoding::LinuxAlternate.encode(_IOC_READ, b'V', 0, 104), 0x40685600);
+    /// ```
+    pub const fn encode(self, dir: Dir, ty: u8, nr: u8, size: usize) -> u32 {
+        let (size_bits, none, read, write) = self.layout();
+        assert!(
+            size < 1 << size_bits,
+            "`ioctl` argument size overflows the size field of the encoding",
+        );
+
+        let mut bits = 0;
+        if dir.is_read() {
+            bits |= read;
+        }
+        if dir.is_write() {
+            bits |= write;
+        }
+        if bits == 0 {
+            bits = none;
+        }
+        bits << (16 + size_bits) | (size as u32) << 16 | (ty as u32) << 8 | nr as u32
+    }
+
+    /// Decodes a request code that uses this encoding.
+    ///
+    /// This works like [`Ioctl::decode`], but uses the bit layout of `self` instead of the one
+    /// of the target.
+    ///
+    /// The returned [`Dir`] is *not* the raw direction field of `request`: it is the target's
+    /// [`_IOC_NONE`], [`_IOC_READ`], [`_IOC_WRITE`], or [`_IOC_READ_WRITE`] constant with the same
+    /// meaning, so it can be compared against those constants and inspected with
+    /// [`Dir::is_read`] and [`Dir::is_write`].
+    /// Direction fields without a meaning of their own are mapped onto those constants too: a
+    /// field of 0 in an encoding with a dedicated "none" bit becomes [`_IOC_NONE`], and the
+    /// "none" bit is ignored when it is combined with the read or write bits.
+    ///
+    /// # Example
+    ///
+    /// Decoding a PowerPC request code:
+    ///
+    /// ```
+    /// use uoctl::*;
+    ///
+    /// let decoded = Encoding::LinuxAlternate.decode(0x40685600);
+    /// assert_eq!(decoded, Decoded { dir: _IOC_READ, ty: b'V', nr: 0, size: 104 });
+    /// ```
+    pub const fn decode(self, request: u32) -> Decoded {
+        let (size_bits, _, read, write) = self.layout();
+        let bits = request >> (16 + size_bits);
+        let dir = match (bits & read != 0, bits & write != 0) {
+            (false, false) => _IOC_NONE,
+            (true, false) => _IOC_READ,
+            (false, true) => _IOC_WRITE,
+            (true, true) => _IOC_READ_WRITE,
+        };
+        Decoded {
+            dir,
+            ty: (request >> 8) as u8,
+            nr: request as u8,
+            size: (request >> 16 & ((1 << size_bits) - 1)) as usize,
+        }
+    }
+}
+
+/// Returns the `ioctl` [`Encoding`] this library was compiled for.
+///
+/// # Example
+///
+/// ```
+/// use uoctl::*;
+///
+/// const _: () = assert!(matches!(
+///     encoding(),
+///     Encoding::LinuxGeneric | Encoding::LinuxAlternate | Encoding::Bsd,
+/// ));
+/// ```
+///
+/// The [`Encoding`] can be included in diagnostics, since it implements [`Display`]:
+///
+/// ```
+/// println!("using the {} `ioctl` encoding", uoctl::encoding());
+/// ```
+///
+/// [`Display`]: fmt::Display
+pub const fn encoding() -> Encoding {
+    platform::ENCODING
+}
+
+/// Combines an `ioctl` type and number into a 16-bit key (`ty << 8 | nr`).
+///
+/// Also see [`Ioctl::group_number_key`].
+pub const fn group_number_key(ty: u8, nr: u8) -> u16 {
+    (ty as u16) << 8 | nr as u16
+}
+
+/// Manually constructs an [`Ioctl`] from its components.
+///
+/// Also see [`Ioctl::from_raw`] for a way to interface with "legacy" ioctls that don't yet follow
+/// this scheme.
+///
+/// Prefer to use [`_IO`], [`_IOR`], [`_IOW`], or [`_IOWR`] where possible.
+/// When the direction has to be chosen explicitly, but the size is the size of a type, use
+/// [`_IOC_typed`] (or [`ioctl_for`], for other pointer types), which derives the size from the
+/// argument type and checks it at compile time.
+/// [`_IOC`] itself is meant for `ioctl`s whose size isn't known at compile time, like
+/// `UI_GET_SYSNAME(len)` below.
+///
+/// # Arguments
+///
+/// - **`dir`**: Direction of the ioctl. One of [`_IOC_NONE`], [`_IOC_READ`], [`_IOC_WRITE`], or
+///   `_IOC_READ | _IOC_WRITE` (aka [`_IOC_READ_WRITE`]).
+/// - **`ty`**: the `ioctl` group or type to identify the driver or subsystem. You can find a list
+///   [here]. The encoding only has room for 8 bits; use [`Ioctl::from_raw`] for schemes with a
+///   wider group.
+/// - **`nr`**: the `ioctl` number within its group.
+/// - **`size`**: the size of the `ioctl`'s (direct or indirect) argument.
+///
+/// [here]: https://www.kernel.org/doc/html/latest/userspace-api/ioctl/ioctl-number.html
+///
+/// # Panics
+///
+/// This function may panic when `size` exceeds [`MAX_ARG_SIZE`].
+///
+/// # Example
+///
+/// `UI_GET_SYSNAME` is a polymorphic `ioctl` that can be invoked with a variety of buffer lengths.
+/// This function can be used to bind to it.
+///
+/// From `linux/uinput.h`:
+///
+/// ```c
+/// /* ioctl */
+/// #define UINPUT_IOCTL_BASE	'U'
+/// ...
+/// #define UI_GET_SYSNAME(len)	_IOC(_IOC_READ, UINPUT_IOCTL_BASE, 44, len)
+/// ```
+///
+#[cfg_attr(feature = "std", doc = "```no_run")]
+#[cfg_attr(not(feature = "std"), doc = "```ignore")]
+/// use std::ffi::c_char;
+/// use uoctl::*;
+///
+/// const UINPUT_IOCTL_BASE: u8 = b'U';
+/// const fn UI_GET_SYSNAME(len: usize) -> Ioctl<*mut c_char> {
+///     _IOC(_IOC_READ, UINPUT_IOCTL_BASE, 44, len)
+/// }
+///
+/// // Use it like this:
+/// unsafe {
+/// #   let fd = &123;
+///     let mut buffer = [0 as c_char; 16];
+///     UI_GET_SYSNAME(16).ioctl(fd, buffer.as_mut_ptr())?;
+/// }
+/// # std::io::Result::Ok(())
+/// ```
+#[allow(non_snake_case)]
+#[inline]
+pub const fn _IOC<T: ?Sized>(dir: Dir, ty: u8, nr: u8, size: usize) -> Ioctl<T> {
+    assert!(size <= platform::MAX_ARG_SIZE);
+
+    let request = platform::_IOC(dir.0, ty as u32, nr as u32, size as u32);
+    Ioctl::from_raw(request)
+}
+
+/// Manually constructs an [`Ioctl`] like [`_IOC`], but derives the argument size from `T`.
+///
+/// The request code encodes `size_of::<T>()` as the argument size, just like with [`_IOR`],
+/// [`_IOW`], and [`_IOWR`], but the direction can be chosen freely.
+/// Like those functions, this fails to compile if `T` is larger than [`MAX_ARG_SIZE`].
+///
+/// [`ioctl_for`] is the equivalent for other pointer types.
+///
+/// # Example
+///
+/// ```
+/// use uoctl::*;
+///
+/// // Declared as taking no data, but still encodes the size of its argument.
+/// const FOO_RESET: Ioctl<*mut u64> = _IOC_typed(_IOC_NONE, b'x', 3);
+///
+/// assert_eq!(FOO_RESET, _IOC(_IOC_NONE, b'x', 3, 8));
+/// assert_eq!(FOO_RESET.size(), size_of::<u64>());
+/// ```
+///
+/// Arguments larger than [`MAX_ARG_SIZE`] are rejected at compile time:
+///
+/// ```compile_fail
+/// use uoctl::*;
+///
+/// const TOO_LARGE: Ioctl<*mut [u8; MAX_ARG_SIZE + 1]> = _IOC_typed(_IOC_READ, b'x', 4);
+/// ```
+#[allow(non_snake_case)]
+#[inline]
+pub const fn _IOC_typed<T>(dir: Dir, ty: u8, nr: u8) -> Ioctl<*mut T> {
+    const {
+        assert!(size_of::<T>() <= platform::MAX_ARG_SIZE);
+    }
+    _IOC(dir, ty, nr, size_of::<T>())
+}
+
+/// Manually constructs an [`Ioctl`] like [`_IOC`], but returns [`None`] instead of panicking
+/// when `size` exceeds [`MAX_ARG_SIZE`].
 ///
-/// Since [`NoArgs`] is the default value for [`Ioctl`]'s type parameter, it can typically be
-/// omitted.
+/// This is meant for `ioctl`s whose argument size is only known at runtime, for example because
+/// it comes from user input.
 ///
 /// # Example
 ///
-/// The *uinput* ioctls `UI_DEV_CREATE` and `UI_DEV_DESTROY` do not take any arguments, while
-/// `UI_DEV_SETUP` *does* take an argument.
-///
-/// From `linux/uinput.h`:
+/// Binding to `UI_GET_SYSNAME` (see [`_IOC`]) with a buffer length that may be too large:
 ///
-/// ```c
-/// /* ioctl */
-/// #define UINPUT_IOCTL_BASE	'U'
-/// #define UI_DEV_CREATE		_IO(UINPUT_IOCTL_BASE, 1)
-/// #define UI_DEV_DESTROY		_IO(UINPUT_IOCTL_BASE, 2)
-/// ...
-/// #define UI_DEV_SETUP _IOW(UINPUT_IOCTL_BASE, 3, struct uinput_setup)
 /// ```
-///
-/// ```rust
-/// use std::{mem, fs::File, ffi::c_char};
-/// use libc::uinput_setup;
+/// use std::ffi::c_char;
 /// use uoctl::*;
 ///
 /// const UINPUT_IOCTL_BASE: u8 = b'U';
-/// const UI_DEV_CREATE: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 1);
-/// const UI_DEV_DESTROY: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 2);
-/// const UI_DEV_SETUP: Ioctl<*const uinput_setup> = _IOW(UINPUT_IOCTL_BASE, 3);
-///
-/// let uinput = File::options().write(true).open("/dev/uinput")?;
-///
-/// let mut setup: libc::uinput_setup = unsafe { mem::zeroed() };
-/// setup.name[0] = b'A' as c_char; // (must not be blank)
-/// unsafe {
-///     UI_DEV_SETUP.ioctl(&uinput, &setup)?;
-///     UI_DEV_CREATE.ioctl(&uinput)?;
-///     // ...use the device...
-///     UI_DEV_DESTROY.ioctl(&uinput)?;
+/// const fn UI_GET_SYSNAME(len: usize) -> Option<Ioctl<*mut c_char>> {
+///     _IOC_checked(_IOC_READ, UINPUT_IOCTL_BASE, 44, len)
 /// }
-/// # std::io::Result::Ok(())
+///
+/// assert_eq!(UI_GET_SYSNAME(16).unwrap().size(), 16);
+/// assert_eq!(UI_GET_SYSNAME(MAX_ARG_SIZE + 1), None);
 /// ```
-pub struct NoArgs {
-    // Unsized type so that the `impl<T> Ioctl<T>` does not conflict.
-    _f: [u8],
+#[allow(non_snake_case)]
+#[inline]
+pub const fn _IOC_checked<T: ?Sized>(dir: Dir, ty: u8, nr: u8, size: usize) -> Option<Ioctl<T>> {
+    if size > platform::MAX_ARG_SIZE {
+        None
+    } else {
+        Some(_IOC(dir, ty, nr, size))
+    }
 }
 
-/// Direction of an [`Ioctl`].
-///
-/// Used by [`_IOC`]. Constructed by using the constants [`_IOC_NONE`], [`_IOC_READ`], and
-/// [`_IOC_WRITE`].
-#[derive(Clone, Copy, PartialEq, Eq)]
-pub struct Dir(u32);
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "std")]
+    use std::{
+        fs::File,
+        os::fd::{FromRawFd, OwnedFd},
+        thread,
+    };
+
+    use super::*;
+
+    /// `FIONREAD` for the target, for tests in all modules.
+    pub(crate) const FIONREAD: Ioctl<*mut c_int> = Ioctl::from_raw(libc::FIONREAD as _);
+
+    /// Returns the read and write end of a new pipe.
+    #[cfg(feature = "std")]
+    fn pipe() -> (OwnedFd, OwnedFd) {
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) }
+    }
+
+    #[test]
+    fn dir_or() {
+        assert_ne!(_IOC_NONE, _IOC_READ);
+        assert_ne!(_IOC_NONE, _IOC_WRITE);
+
+        assert_eq!(_IOC_READ | _IOC_WRITE, _IOC_READ_WRITE);
+        assert_eq!(_IOC_READ | _IOC_READ, _IOC_READ);
+        assert_eq!(_IOC_WRITE | _IOC_WRITE, _IOC_WRITE);
+        assert_eq!(_IOC_NONE | _IOC_NONE, _IOC_NONE);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn by_value_struct() {
+        #[repr(C)]
+        struct TwoWords {
+            a: usize,
+            b: usize,
+        }
+
+        const IOCTL: Ioctl<TwoWords> = _IOW(b'x', 1).with_direct_arg();
+        assert_eq!(
+            IOCTL.request(),
+            _IOC::<NoArgs>(_IOC_WRITE, b'x', 1, 2 * size_of::<usize>()).request(),
+        );
+
+        // `/dev/null` doesn't implement any `ioctl`s. This checks that the by-value struct makes it
+        // through the variadic call.
+        let file = File::open("/dev/null").unwrap();
+        let err = unsafe { IOCTL.ioctl(&file, TwoWords { a: 1, b: 2 }).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+    }
+
+    #[test]
+    fn group_number_key() {
+        #[expect(dead_code)]
+        struct Capability {
+            driver: [u8; 16],
+            card: [u8; 32],
+            bus_info: [u8; 32],
+            version: u32,
+            capabilities: u32,
+            device_caps: u32,
+            reserved: [u32; 3],
+        }
+
+        const VIDIOC_QUERYCAP: Ioctl<*mut Capability> = _IOR(b'V', 0);
+
+        assert_eq!(VIDIOC_QUERYCAP.ty(), b'V');
+        assert_eq!(VIDIOC_QUERYCAP.nr(), 0);
+        assert_eq!(VIDIOC_QUERYCAP.group_number_key(), 0x5600);
+        assert_eq!(super::group_number_key(b'V', 0), 0x5600);
+        assert_eq!(super::group_number_key(0xAE, 0x41), 0xAE41);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn borrow_request() {
+        use std::collections::HashMap;
+
+        fn name_of(names: &HashMap<u32, &'static str>, key: impl Borrow<u32>) -> &'static str {
+            names.get(key.borrow()).copied().unwrap_or("unknown")
+        }
+
+        let names = HashMap::from([(FIONREAD.request(), "FIONREAD")]);
+        assert_eq!(name_of(&names, FIONREAD), "FIONREAD");
+        assert_eq!(name_of(&names, FIONREAD.request()), "FIONREAD");
+        assert_eq!(name_of(&names, _IO(b'V', 0)), "unknown");
+        assert_eq!(*FIONREAD.as_ref(), FIONREAD.request());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_nonzero() {
+        let (read, _write) = pipe();
+        let mut bytes = 0;
+        let res = unsafe { FIONREAD.ioctl_nonzero(&read, &mut bytes).unwrap() };
+        assert_eq!(res, None);
+
+        #[cfg(feature = "mock")]
+        {
+            const CHECK: Ioctl<c_int> = _IO(b'x', 0x03).cast_arg();
+
+            let fd = MockFd::new(|_, arg| Ok(arg as c_int * 2)).unwrap();
+            let res = unsafe { CHECK.ioctl_nonzero(&fd, 9).unwrap() };
+            assert_eq!(res, NonZero::new(18));
+            let res = unsafe { CHECK.ioctl_nonzero(&fd, 0).unwrap() };
+            assert_eq!(res, None);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_poll() {
+        let (read, write) = pipe();
+
+        let mut bytes = c_int::MAX;
+        let res = unsafe {
+            FIONREAD
+                .ioctl_poll(
+                    &read,
+                    &mut bytes,
+                    libc::POLLIN,
+                    Some(Duration::from_millis(10)),
+                )
+                .unwrap()
+        };
+        assert_eq!(res, None);
+        assert_eq!(bytes, c_int::MAX);
+
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            let res = unsafe { libc::write(write.as_raw_fd(), b"abc".as_ptr().cast(), 3) };
+            assert_eq!(res, 3);
+            write
+        });
+
+        let res = unsafe {
+            FIONREAD
+                .ioctl_poll(&read, &mut bytes, libc::POLLIN, None)
+                .unwrap()
+        };
+        assert_eq!(res, Some(0));
+        assert_eq!(bytes, 3);
+
+        // Once the write end is closed, `poll` reports `POLLHUP` and the `ioctl` is still
+        // performed.
+        drop(writer.join().unwrap());
+        let mut bytes = c_int::MAX;
+        let res = unsafe {
+            FIONREAD
+                .ioctl_poll(&read, &mut bytes, libc::POLLIN, Some(Duration::ZERO))
+                .unwrap()
+        };
+        assert_eq!(res, Some(0));
+        assert_eq!(bytes, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "`_IOC_NONE` cannot be combined with other values")]
+    fn dir_none_or_read() {
+        let _ = _IOC_NONE | _IOC_READ;
+    }
+
+    #[test]
+    #[should_panic(expected = "`_IOC_NONE` cannot be combined with other values")]
+    fn dir_none_or_write() {
+        let _ = _IOC_NONE | _IOC_WRITE;
+    }
+
+    #[test]
+    #[should_panic(expected = "`_IOC_NONE` cannot be combined with other values")]
+    fn dir_read_or_none() {
+        let _ = _IOC_READ | _IOC_NONE;
+    }
+
+    #[test]
+    #[should_panic(expected = "`_IOC_NONE` cannot be combined with other values")]
+    fn dir_write_or_none() {
+        let _ = _IOC_WRITE | _IOC_NONE;
+    }
+
+    // The BSD-style aliases are available on every platform, but these tests are primarily meant
+    // to exercise the BSD encoding.
+
+    #[test]
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn bsd_dir_bits() {
+        // The direction bits from `sys/ioccom.h`, which aren't shifted like on Linux.
+        assert_eq!(IOC_VOID.bits(), 1 << 29);
+        assert_eq!(IOC_OUT.bits(), 1 << 30);
+        assert_eq!(IOC_IN.bits(), 1 << 31);
+        assert_eq!(IOC_INOUT.bits(), IOC_IN.bits() | IOC_OUT.bits());
+
+        // The size field is 13 bits wide (`IOCPARM_MASK`), and sits right below the direction bits.
+        assert_eq!(MAX_ARG_SIZE, 0x1fff);
+        assert_eq!(
+            _IOC::<NoArgs>(IOC_INOUT, b'x', 1, MAX_ARG_SIZE).request(),
+            0xdfff7801,
+        );
+        assert_eq!(_IOR::<[u8; 0x100]>(b'x', 1).request(), 0x41007801);
+    }
+
+    #[cfg(feature = "std")]
+    thread_local! {
+        static DROPS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+    }
+
+    /// An `ioctl` argument that counts how often it is dropped, and clobbers *errno* when it is.
+    #[cfg(feature = "std")]
+    #[repr(transparent)]
+    struct DropArg(*mut c_int);
+
+    #[cfg(feature = "std")]
+    impl Drop for DropArg {
+        fn drop(&mut self) {
+            DROPS.set(DROPS.get() + 1);
+            unsafe { libc::close(-1) };
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn drop_arg() {
+        let (read, _write) = pipe();
+        let file = File::open("/dev/null").unwrap();
+        let ioctl = FIONREAD.cast_arg::<DropArg>();
+
+        DROPS.set(0);
+        let mut bytes = c_int::MAX;
+        unsafe { ioctl.ioctl(&read, DropArg(&mut bytes)).unwrap() };
+        assert_eq!(bytes, 0);
+        assert_eq!(DROPS.get(), 1);
+
+        // The error is captured before the argument is dropped.
+        let err = unsafe { ioctl.ioctl(&file, DropArg(&mut bytes)).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+        assert_eq!(DROPS.get(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn panicking_drop_arg() {
+        struct PanicArg(#[expect(dead_code)] *mut c_int);
+
+        impl Drop for PanicArg {
+            fn drop(&mut self) {
+                DROPS.set(DROPS.get() + 1);
+                panic!("dropped");
+            }
+        }
+
+        let (read, _write) = pipe();
+        let ioctl = FIONREAD.cast_arg::<PanicArg>();
+
+        DROPS.set(0);
+        let mut bytes = c_int::MAX;
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+            ioctl.ioctl(&read, PanicArg(&mut bytes))
+        }));
+        assert!(res.is_err());
+        // The `ioctl` completed before the panic, and the argument was dropped exactly once.
+        assert_eq!(bytes, 0);
+        assert_eq!(DROPS.get(), 1);
+    }
+
+    #[test]
+    fn sized_as() {
+        #[expect(dead_code)]
+        struct Old([u8; 8]);
+        #[expect(dead_code)]
+        struct New([u8; 24]);
+
+        let ioctl: Ioctl<*mut New> = _IOWR_sized_as::<Old, New>(b'z', 1);
+        assert_eq!(ioctl.size(), 8);
+        assert_eq!(ioctl.request(), _IOWR::<Old>(b'z', 1).request());
+
+        let ioctl: Ioctl<*mut New> = _IOR_sized_as::<Old, New>(b'z', 2);
+        assert_eq!(ioctl.request(), _IOR::<Old>(b'z', 2).request());
+
+        let ioctl: Ioctl<*const New> = _IOW_sized_as::<Old, New>(b'z', 3);
+        assert_eq!(ioctl.request(), _IOW::<Old>(b'z', 3).request());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn check_struct_version() {
+        // `FIONREAD` stands in for a kernel filling in a version field: it stores the number of
+        // bytes buffered in the pipe.
+        let (read, write) = pipe();
+        assert_eq!(
+            unsafe { libc::write(write.as_raw_fd(), [0u8; 12].as_ptr().cast(), 12) },
+            12,
+        );
+
+        unsafe {
+            assert!(
+                FIONREAD
+                    .check_struct_version(&read, 12, |&n| n as u32)
+                    .unwrap()
+            );
+            assert!(
+                !FIONREAD
+                    .check_struct_version(&read, 13, |&n| n as u32)
+                    .unwrap()
+            );
+        }
+
+        let file = File::open("/dev/null").unwrap();
+        let err = unsafe {
+            FIONREAD
+                .check_struct_version(&file, 0, |&n| n as u32)
+                .unwrap_err()
+        };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_blocking() {
+        fn nonblocking(fd: &impl AsRawFd) -> bool {
+            let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFL) };
+            assert_ne!(flags, -1);
+            flags & libc::O_NONBLOCK != 0
+        }
+        fn set_nonblocking(fd: &impl AsRawFd) {
+            let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFL) };
+            assert_ne!(
+                unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) },
+                -1
+            );
+        }
+
+        let (read, _write) = pipe();
+        assert!(!nonblocking(&read));
+
+        let mut bytes = c_int::MAX;
+        unsafe { FIONREAD.ioctl_blocking(&read, &mut bytes, false).unwrap() };
+        assert_eq!(bytes, 0);
+        assert!(!nonblocking(&read));
+
+        set_nonblocking(&read);
+        bytes = c_int::MAX;
+        unsafe { FIONREAD.ioctl_blocking(&read, &mut bytes, true).unwrap() };
+        assert_eq!(bytes, 0);
+        assert!(nonblocking(&read));
+
+        // The flags are also restored when the `ioctl` fails.
+        let file = File::open("/dev/null").unwrap();
+        set_nonblocking(&file);
+        let err = unsafe {
+            FIONREAD
+                .ioctl_blocking(&file, &mut bytes, true)
+                .unwrap_err()
+        };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+        assert!(nonblocking(&file));
+
+        let err = unsafe { FIONREAD.ioctl_blocking(&-1, &mut bytes, true).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn hex() {
+        let ioctl = _IOR::<u32>(b'V', 0);
+        assert_eq!(format!("{ioctl:#x}"), format!("{:#x}", ioctl.request()));
+        assert_eq!(format!("{ioctl:08X}"), format!("{:08X}", ioctl.request()));
+        assert_eq!(format!("{:#06x}", _IO(0, 1)), "0x0001");
+
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        {
+            assert_eq!(format!("{ioctl:#x}"), "0x80045600");
+            assert_eq!(format!("{ioctl:#X}"), "0x80045600");
+            assert_eq!(format!("{:X}", _IOR::<u32>(b'j', 0x1a)), "80046A1A");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn duration_conversion() {
+        let ts = duration_to_timespec(Duration::new(3, 456_789_012)).unwrap();
+        assert_eq!((ts.tv_sec, ts.tv_nsec), (3, 456_789_012));
+
+        let tv = duration_to_timeval(Duration::new(3, 456_789_012)).unwrap();
+        assert_eq!((tv.tv_sec, tv.tv_usec), (3, 456_790));
+        let tv = duration_to_timeval(Duration::new(3, 999_999_001)).unwrap();
+        assert_eq!((tv.tv_sec, tv.tv_usec), (4, 0));
+        let tv = duration_to_timeval(Duration::ZERO).unwrap();
+        assert_eq!((tv.tv_sec, tv.tv_usec), (0, 0));
+
+        let err = duration_to_timespec(Duration::MAX).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = duration_to_timeval(Duration::MAX).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_duration() {
+        let file = File::open("/dev/null").unwrap();
+
+        let ioctl: Ioctl<*const libc::timespec> = _IOW(b'z', 0x20);
+        let err = unsafe { ioctl.ioctl_duration(&file, Duration::from_millis(5)) }.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+        let err = unsafe { ioctl.ioctl_duration(&file, Duration::MAX) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let ioctl: Ioctl<*const libc::timeval> = _IOW(b'z', 0x21);
+        let err = unsafe { ioctl.ioctl_duration(&file, Duration::from_millis(5)) }.unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+    }
+
+    #[test]
+    fn ioctl_for() {
+        #[expect(dead_code)]
+        struct Arg([u8; 24]);
+
+        assert_eq!(
+            super::ioctl_for::<*mut Arg>(_IOC_READ, b'z', 1).request(),
+            _IOR::<Arg>(b'z', 1).request(),
+        );
+        assert_eq!(
+            super::ioctl_for::<*const Arg>(_IOC_WRITE, b'z', 2).request(),
+            _IOW::<Arg>(b'z', 2).request(),
+        );
+        assert_eq!(
+            super::ioctl_for::<*mut Arg>(_IOC_READ_WRITE, b'z', 3).request(),
+            _IOWR::<Arg>(b'z', 3).request(),
+        );
+        // Declaring a write-only `ioctl` with a `*mut` pointer is fine (see `Ioctl::cast_mut`).
+        assert_eq!(
+            super::ioctl_for::<*mut Arg>(_IOC_WRITE, b'z', 4).request(),
+            _IOW::<Arg>(b'z', 4).request(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "it must be a `*mut` pointer")]
+    fn ioctl_for_const_read() {
+        super::ioctl_for::<*const u32>(_IOC_READ, b'z', 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_or() {
+        const FIONREAD2: Ioctl<*mut c_int> = _IOR(b'z', 0x30);
+
+        let (read, write) = pipe();
+        assert_eq!(
+            unsafe { libc::write(write.as_raw_fd(), [0u8; 3].as_ptr().cast(), 3) },
+            3
+        );
+
+        let mut bytes = c_int::MAX;
+        let err = unsafe { FIONREAD2.ioctl(&read, &mut bytes).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+
+        unsafe { FIONREAD2.ioctl_or(FIONREAD, &read, &mut bytes).unwrap() };
+        assert_eq!(bytes, 3);
+
+        // Other errors are returned without trying the fallback.
+        let err = unsafe { FIONREAD2.ioctl_or(FIONREAD, &-1, &mut bytes).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_id() {
+        use std::collections::HashMap;
+
+        const KVM_RUN: Ioctl = _IO(0xAE, 0x80);
+        let rndgetentcnt = _IOR::<c_int>(b'R', 0x00);
+
+        let mut handlers: HashMap<IoctlId, &str> = HashMap::new();
+        handlers.insert(rndgetentcnt.id(), "RNDGETENTCNT");
+        handlers.insert(KVM_RUN.id(), "KVM_RUN");
+        handlers.insert(FIONREAD.id(), "FIONREAD");
+
+        assert_eq!(handlers[&KVM_RUN.id()], "KVM_RUN");
+        // The argument type doesn't matter.
+        assert_eq!(
+            handlers[&rndgetentcnt.cast_arg::<u8>().id()],
+            "RNDGETENTCNT"
+        );
+        assert_eq!(handlers[&IoctlId::from_raw(FIONREAD.request())], "FIONREAD");
+        assert_eq!(handlers.get(&_IO(0xAE, 0x81).id()), None);
+
+        assert_eq!(KVM_RUN.id().request(), KVM_RUN.request());
+        assert_eq!(
+            format!("{:?}", KVM_RUN.id()),
+            format!("IoctlId({:#x})", KVM_RUN.request()),
+        );
+        assert_eq!(KVM_RUN.id().to_string(), "_IOC(_IOC_NONE, 0xae, 0x80, 0)");
+        assert_eq!(
+            rndgetentcnt.id().to_string(),
+            "_IOC(_IOC_READ, 'R', 0x00, 4)"
+        );
+        assert_eq!(
+            _IOWR::<[u8; 104]>(b'\\', 1).id().to_string(),
+            "_IOC(_IOC_READ | _IOC_WRITE, 0x5c, 0x01, 104)"
+        );
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "raw-syscall",
+        any(target_os = "linux", target_os = "android")
+    ))]
+    fn ioctl_syscall() {
+        let (read, write) = pipe();
+        assert_eq!(
+            unsafe { libc::write(write.as_raw_fd(), [0u8; 5].as_ptr().cast(), 5) },
+            5
+        );
+
+        let mut libc_bytes = c_int::MAX;
+        let mut raw_bytes = c_int::MAX;
+        let libc_res = unsafe { FIONREAD.ioctl(&read, &mut libc_bytes).unwrap() };
+        let raw_res = unsafe {
+            FIONREAD
+                .ioctl_syscall(read.as_raw_fd(), &raw mut raw_bytes as usize)
+                .unwrap()
+        };
+        assert_eq!(libc_res, raw_res);
+        assert_eq!(libc_bytes, 5);
+        assert_eq!(raw_bytes, 5);
+
+        let file = File::open("/dev/null").unwrap();
+        let libc_err = unsafe { FIONREAD.ioctl(&file, &mut libc_bytes).unwrap_err() };
+        let raw_err = unsafe {
+            FIONREAD
+                .ioctl_syscall(file.as_raw_fd(), &raw mut raw_bytes as usize)
+                .unwrap_err()
+        };
+        assert_eq!(libc_err.raw_os_error(), Some(raw_err));
+        assert_eq!(raw_err, libc::ENOTTY);
+
+        let raw_err = unsafe { FIONREAD.ioctl_syscall(-1, 0).unwrap_err() };
+        assert_eq!(raw_err, libc::EBADF);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read_into_vec() {
+        let ioctl = _IOC::<*mut u16>(_IOC_READ, b'z', 0x40, 0);
+        assert_eq!(
+            ioctl.with_size(6 * size_of::<u16>()).request(),
+            _IOR::<[u16; 6]>(b'z', 0x40).request(),
+        );
+
+        let file = File::open("/dev/null").unwrap();
+        let err = unsafe { ioctl.read_into_vec(&file, vec![0u16; 6]).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic]
+    fn read_into_vec_too_large() {
+        let ioctl = _IOC::<*mut u8>(_IOC_READ, b'z', 0x40, 0);
+        let file = File::open("/dev/null").unwrap();
+        let _ = unsafe { ioctl.read_into_vec(&file, vec![0; platform::MAX_ARG_SIZE + 1]) };
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn errno_checked_ioctl() {
+        let (read, write) = pipe();
+        unsafe {
+            libc::write(write.as_raw_fd(), [0u8; 3].as_ptr().cast(), 3);
+            // A stale *errno* must not turn a successful call into an error.
+            platform::errno_location().write(libc::EAGAIN);
+        }
+        let mut count = 0;
+        let res = unsafe { FIONREAD.ioctl_errno_checked(&read, &mut count) };
+        assert_eq!(res.unwrap(), 0);
+        assert_eq!(count, 3);
+        // The caller's *errno* is restored after a successful call.
+        assert_eq!(unsafe { platform::errno_location().read() }, libc::EAGAIN);
+
+        let res = unsafe { FIONREAD.ioctl_errno_checked(&-1, &mut count) };
+        assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::EBADF));
+
+        // -1 with *errno* left untouched is a valid result.
+        assert_eq!(errno_checked(-1, 0).unwrap(), -1);
+        assert_eq!(
+            errno_checked(-1, libc::ENOTTY).unwrap_err().raw_os_error(),
+            Some(libc::ENOTTY),
+        );
+        assert_eq!(errno_checked(5, libc::ENOTTY).unwrap(), 5);
+    }
+
+    #[test]
+    fn well_formed() {
+        assert!(_IO(b'x', 1).is_well_formed());
+        assert!(_IOR::<u32>(b'x', 1).is_well_formed());
+        assert!(_IOW::<[u8; 64]>(b'x', 1).is_well_formed());
+        assert!(_IOWR::<u64>(b'x', 1).is_well_formed());
+        assert!(_IOC::<NoArgs>(_IOC_READ, b'x', 1, platform::MAX_ARG_SIZE).is_well_formed());
+
+        // Zero-sized arguments and `_IOC_NONE` with a size contradict each other.
+        assert!(!_IOR::<()>(b'x', 1).is_well_formed());
+        assert!(!_IOC::<NoArgs>(_IOC_READ_WRITE, b'x', 1, 0).is_well_formed());
+        assert!(!_IOC::<NoArgs>(_IOC_NONE, b'x', 1, 8).is_well_formed());
+        assert_eq!(
+            _IOWINT(b'x', 1).is_well_formed(),
+            cfg!(not(any(target_os = "linux", target_os = "android"))),
+        );
+
+        // Sizes above `MAX_ARG_SIZE` are representable on most Linux architectures, but not
+        // portable.
+        #[cfg(all(
+            any(target_os = "linux", target_os = "android"),
+            any(target_arch = "x86_64", target_arch = "aarch64"),
+        ))]
+        {
+            let size = platform::MAX_ARG_SIZE as u32 + 1;
+            let oversized = platform::_IOC(platform::_IOC_READ, b'x' as u32, 1, size);
+            assert!(!Ioctl::<NoArgs>::from_raw(oversized).is_well_formed());
+        }
+
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            // The BSD encoding requires a direction bit to be set, and doesn't allow combining
+            // `IOC_VOID` with the others.
+            assert!(!Ioctl::<NoArgs>::from_raw(0x7801).is_well_formed());
+            let void_in = platform::IOC_VOID | platform::IOC_IN | 4 << 16 | 0x7801;
+            assert!(!Ioctl::<NoArgs>::from_raw(void_in).is_well_formed());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read_into_slots() {
+        let (read, write) = pipe();
+        let mut arena = vec![MaybeUninit::<c_int>::uninit(); 4];
+        let mut values = Vec::new();
+        for slot in &mut arena {
+            unsafe { libc::write(write.as_raw_fd(), b"x".as_ptr().cast(), 1) };
+            let value = unsafe { FIONREAD.read_into_slot(&read, slot).unwrap() };
+            values.push(*value);
+        }
+        assert_eq!(values, [1, 2, 3, 4]);
+        assert_eq!(unsafe { arena[2].assume_init() }, 3);
+
+        let mut slot = MaybeUninit::uninit();
+        let err = unsafe { FIONREAD.read_into_slot(&-1, &mut slot).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic = "cannot be used with `ioctl`s that read their argument"]
+    fn read_into_slot_write() {
+        let ioctl = _IOWR::<c_int>(b'x', 1);
+        let mut slot = MaybeUninit::uninit();
+        let _ = unsafe { ioctl.read_into_slot(&-1, &mut slot) };
+    }
+
+    #[test]
+    fn try_cast_arg() {
+        let raw: Ioctl<*mut u8> = _IOC(_IOC_READ_WRITE, b'x', 1, 8);
+        assert_eq!(
+            raw.try_cast_arg::<*mut u64>().unwrap().request(),
+            raw.request(),
+        );
+        assert!(raw.try_cast_arg::<*const [u32; 2]>().is_ok());
+        assert_eq!(
+            raw.try_cast_arg::<*mut u32>().err().unwrap(),
+            SizeMismatch {
+                encoded: 8,
+                actual: 4,
+            },
+        );
+        assert_eq!(
+            _IO(b'x', 1).try_cast_arg::<*mut u8>().err().unwrap(),
+            SizeMismatch {
+                encoded: 0,
+                actual: 1,
+            },
+        );
+    }
+
+    #[test]
+    fn as_tuple() {
+        let (dir, ty, nr, size) = _IOWR::<[u8; 24]>(b'V', 0x11).as_tuple();
+        assert_eq!(dir, platform::_IOC_READ | platform::_IOC_WRITE);
+        assert_eq!(ty, b'V');
+        assert_eq!(nr, 0x11);
+        assert_eq!(size, 24);
+
+        assert_eq!(
+            _IO(0xAE, 0x80).as_tuple(),
+            (platform::_IOC_NONE, 0xAE, 0x80, 0)
+        );
+
+        let group = |ioctl: Ioctl<*mut i32>| match ioctl.as_tuple() {
+            (_, b'R', _, _) => "random",
+            (_, b'V', _, _) => "v4l2",
+            _ => "other",
+        };
+        assert_eq!(group(_IOR(b'R', 0x00)), "random");
+        assert_eq!(group(_IOR(b'V', 0x00)), "v4l2");
+        assert_eq!(group(_IOR(b'x', 0x00)), "other");
+    }
+
+    #[test]
+    fn checked_nr() {
+        assert_eq!(super::checked_nr(0x20, 3), 0x23);
+        assert_eq!(super::checked_nr(0xF0, 0x0F), 0xFF);
+
+        const CHANNEL_5: Ioctl<*mut u32> = _IOR(b'X', super::checked_nr(0x10, 5));
+        assert_eq!(CHANNEL_5.nr(), 0x15);
+    }
+
+    #[test]
+    #[should_panic = "overflows its 8-bit field"]
+    fn checked_nr_overflow() {
+        let offset = std::hint::black_box(0x10);
+        super::checked_nr(0xF0, offset);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn classify_results() {
+        let err = io::Error::from_raw_os_error;
+        assert!(matches!(classify(Ok(3)), IoctlOutcome::Ready(3)));
+        assert!(matches!(
+            classify(Err(err(libc::EAGAIN))),
+            IoctlOutcome::WouldBlock,
+        ));
+        assert!(matches!(
+            classify(Err(err(libc::EWOULDBLOCK))),
+            IoctlOutcome::WouldBlock,
+        ));
+        for errno in [libc::ENOTTY, libc::EINVAL] {
+            match classify(Err(err(errno))) {
+                IoctlOutcome::NotSupported(e) => assert_eq!(e.raw_os_error(), Some(errno)),
+                other => panic!("{other:?}"),
+            }
+        }
+        match classify(Err(err(libc::EBADF))) {
+            IoctlOutcome::Error(e) => assert_eq!(e.raw_os_error(), Some(libc::EBADF)),
+            other => panic!("{other:?}"),
+        }
+
+        let (read, _write) = pipe();
+        let mut bytes = 0;
+        let res = unsafe { FIONREAD.ioctl(&read, &mut bytes) };
+        assert!(matches!(classify(res), IoctlOutcome::Ready(0)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn encoding() {
+        #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+        assert_eq!(super::encoding(), Encoding::LinuxGeneric);
+        #[cfg(all(target_os = "linux", target_arch = "powerpc64"))]
+        assert_eq!(super::encoding(), Encoding::LinuxAlternate);
+        #[cfg(all(target_os = "linux", target_arch = "alpha"))]
+        assert_eq!(super::encoding(), Encoding::LinuxAlternate);
+        #[cfg(target_os = "freebsd")]
+        assert_eq!(super::encoding(), Encoding::Bsd);
 
-impl BitOr for Dir {
-    type Output = Dir;
+        assert_eq!(Encoding::LinuxGeneric.to_string(), "Linux (generic)");
+        assert_eq!(
+            Encoding::LinuxAlternate.to_string(),
+            "Linux (Alpha/MIPS/PowerPC/SPARC)"
+        );
+        const _: Encoding = super::encoding();
+    }
 
-    #[inline]
-    fn bitor(self, rhs: Self) -> Self::Output {
-        // `_IOC_NONE` is 0 on x86, but non-zero on other architectures. It is invalid and
-        // non-portable to combine it with other usages, so we prevent it here.
-        // This check will easily optimize out in almost all cases, since the direction is nearly
-        // always a compile-time constant.
-        if (self == _IOC_NONE && rhs != _IOC_NONE) || (self != _IOC_NONE && rhs == _IOC_NONE) {
-            panic!("`_IOC_NONE` cannot be combined with other values");
+    #[test]
+    fn dir() {
+        assert_eq!(_IOR::<u32>(b'V', 0).dir(), _IOC_READ);
+        assert_eq!(_IOW::<u32>(b'V', 0).dir(), _IOC_WRITE);
+        assert_eq!(_IOWR::<u32>(b'V', 0).dir(), _IOC_READ_WRITE);
+        assert_eq!(_IO(b'V', 0).dir(), _IOC_NONE);
+
+        for dir in [_IOC_NONE, _IOC_READ, _IOC_WRITE, _IOC_READ_WRITE] {
+            assert_eq!(_IOC::<NoArgs>(dir, 0xff, 0xff, 0).dir(), dir);
+            assert_eq!(_IOC::<NoArgs>(dir, 0, 0, platform::MAX_ARG_SIZE).dir(), dir);
         }
+    }
 
-        Self(self.0 | rhs.0)
+    #[test]
+    fn size() {
+        assert_eq!(_IOR::<u32>(b'V', 0).size(), 4);
+        assert_eq!(_IOW::<[u8; 104]>(b'V', 0).size(), 104);
+        assert_eq!(_IOWR::<u64>(b'V', 0).size(), 8);
+        assert_eq!(_IO(b'V', 0).size(), 0);
+        assert_eq!(
+            _IOC::<NoArgs>(_IOC_READ, 0xff, 0xff, platform::MAX_ARG_SIZE).size(),
+            platform::MAX_ARG_SIZE,
+        );
+
+        let ioctl: Ioctl<*mut u32> = _IOR(b'V', 0);
+        assert_eq!(ioctl.size(), size_of::<u32>());
     }
-}
 
-impl fmt::Debug for Dir {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if *self == _IOC_READ | _IOC_WRITE {
-            f.write_str("_IOC_READ | _IOC_WRITE")
-        } else if *self == _IOC_READ {
-            f.write_str("_IOC_READ")
-        } else if *self == _IOC_WRITE {
-            f.write_str("_IOC_WRITE")
-        } else if *self == _IOC_NONE {
-            f.write_str("_IOC_NONE")
-        } else {
-            write!(f, "{:#x}", self.0)
+    #[test]
+    fn decode() {
+        assert_eq!(
+            _IOR::<u32>(b'V', 0x10).decode(),
+            Decoded {
+                dir: _IOC_READ,
+                ty: b'V',
+                nr: 0x10,
+                size: 4,
+            },
+        );
+        assert_eq!(
+            _IOW::<[u8; 24]>(b'x', 0xff).decode(),
+            Decoded {
+                dir: _IOC_WRITE,
+                ty: b'x',
+                nr: 0xff,
+                size: 24,
+            },
+        );
+        assert_eq!(
+            _IOWR::<u64>(0xAE, 0x00).decode(),
+            Decoded {
+                dir: _IOC_READ_WRITE,
+                ty: 0xAE,
+                nr: 0x00,
+                size: 8,
+            },
+        );
+        assert_eq!(
+            _IO(0xAE, 0x01).decode(),
+            Decoded {
+                dir: _IOC_NONE,
+                ty: 0xAE,
+                nr: 0x01,
+                size: 0,
+            },
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn debug() {
+        let ioctl = _IOWR::<u64>(b'x', 0x20);
+        assert_eq!(
+            format!("{ioctl:?}"),
+            format!(
+                "Ioctl {{ dir: _IOC_READ | _IOC_WRITE, ty: 'x' (0x78), nr: 32, size: 8, request: {:#x} }}",
+                ioctl.request(),
+            ),
+        );
+
+        let ioctl = _IO(0xAE, 0x01);
+        assert_eq!(
+            format!("{ioctl:?}"),
+            format!(
+                "Ioctl {{ dir: _IOC_NONE, ty: 0xae, nr: 1, size: 0, request: {:#x} }}",
+                ioctl.request(),
+            ),
+        );
+        assert!(format!("{:?}", _IO(b'\'', 0)).contains(r"ty: '\'' (0x27)"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn eq_hash() {
+        use std::collections::{HashMap, HashSet};
+
+        const A: Ioctl<*mut u32> = _IOR(b'x', 1);
+        const B: Ioctl<*mut u32> = _IOR(b'x', 2);
+
+        assert_eq!(A, _IOR(b'x', 1));
+        assert_ne!(A, B);
+
+        let set = HashSet::from([A, B, A]);
+        assert_eq!(set.len(), 2);
+
+        // Hashing agrees with `u32`, so `Ioctl` keys can be looked up by request code.
+        let names = HashMap::from([(A, "A"), (B, "B")]);
+        assert_eq!(names[&B], "B");
+        assert_eq!(names.get(&A.request()), Some(&"A"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ord() {
+        let mut ioctls: Vec<Ioctl<*mut u32>> = vec![
+            _IOR(b'x', 2),
+            _IOW::<u32>(b'x', 1).cast_mut(),
+            _IOR(b'a', 9),
+            _IOW::<u32>(b'z', 0).cast_mut(),
+            _IOR(b'x', 1),
+        ];
+        let mut requests = ioctls.iter().map(|i| i.request()).collect::<Vec<_>>();
+        ioctls.sort();
+        requests.sort();
+        assert_eq!(
+            ioctls.iter().map(|i| i.request()).collect::<Vec<_>>(),
+            requests
+        );
+
+        ioctls.dedup();
+        assert_eq!(ioctls.len(), 5);
+        assert!(ioctls.binary_search(&_IOR(b'a', 9)).is_ok());
+        assert!(ioctls.binary_search(&_IOR(b'a', 8)).is_err());
+    }
+
+    #[test]
+    fn dir_raw() {
+        for dir in [_IOC_NONE, _IOC_READ, _IOC_WRITE, _IOC_READ_WRITE] {
+            assert_eq!(Dir::from_raw(dir.bits()), dir);
+            let (bits, ..) = _IOC::<NoArgs>(dir, b'x', 1, 0).as_tuple();
+            assert_eq!(Dir::from_raw(bits), dir);
         }
+        assert_eq!(_IOC_READ.bits(), platform::_IOC_READ);
+        assert_eq!(Dir::from_raw(0xdead).bits(), 0xdead);
     }
-}
 
-/// Indicates that an `ioctl` neither reads nor writes data through its argument.
-///
-/// Identical to [`IOC_VOID`]. [`_IOC_NONE`] is a Linuxism, while [`IOC_VOID`] is used by other
-/// systems.
-pub const _IOC_NONE: Dir = Dir(platform::_IOC_NONE);
+    #[test]
+    #[cfg(feature = "std")]
+    fn dir_display() {
+        assert_eq!(_IOC_NONE.to_string(), "none");
+        assert_eq!(_IOC_READ.to_string(), "r");
+        assert_eq!(_IOC_WRITE.to_string(), "w");
+        assert_eq!(_IOC_READ_WRITE.to_string(), "rw");
+    }
 
-/// Indicates that an `ioctl` reads data from the kernel through its pointer argument.
-///
-/// Identical to [`IOC_OUT`]. [`_IOC_READ`] is a Linuxism, while [`IOC_OUT`] is used by other
-/// systems.
-pub const _IOC_READ: Dir = Dir(platform::_IOC_READ);
+    #[test]
+    fn dir_queries() {
+        let all = [_IOC_NONE, _IOC_READ, _IOC_WRITE, _IOC_READ_WRITE];
+        let read = [false, true, false, true];
+        let write = [false, false, true, true];
+        for (i, dir) in all.into_iter().enumerate() {
+            assert_eq!(dir.is_read(), read[i], "{dir:?}");
+            assert_eq!(dir.is_write(), write[i], "{dir:?}");
+            assert!(dir.contains(dir));
+            assert!(dir.contains(_IOC_NONE));
+            assert!(_IOC_READ_WRITE.contains(dir));
+        }
+        assert!(!_IOC_NONE.contains(_IOC_READ));
+        assert!(!_IOC_READ.contains(_IOC_WRITE));
+        assert!(!_IOC_WRITE.contains(_IOC_READ_WRITE));
 
-/// Indicates that an `ioctl` writes data to the kernel through its pointer argument.
-///
-/// Identical to [`IOC_IN`]. [`_IOC_WRITE`] is a Linuxism, while [`IOC_IN`] is used by other
-/// systems.
-pub const _IOC_WRITE: Dir = Dir(platform::_IOC_WRITE);
+        // The raw bits of `_IOC_NONE` are non-zero on some platforms, but it never reads or writes.
+        let (none, read, write): (u32, u32, u32) = match crate::encoding() {
+            Encoding::LinuxGeneric => (0, 2, 1),
+            Encoding::LinuxAlternate => (1, 2, 4),
+            Encoding::Bsd => (0x20000000, 0x40000000, 0x80000000),
+        };
+        assert_eq!(_IOC_NONE.bits(), none);
+        assert_eq!(_IOC_READ.bits(), read);
+        assert_eq!(_IOC_WRITE.bits(), write);
+        assert!(!Dir::from_raw(none).is_read());
+        assert!(!Dir::from_raw(none).is_write());
+    }
 
-/// Indicates that an `ioctl` both reads and writes data through its pointer argument.
-///
-/// Equivalent to `_IOC_READ | _IOC_WRITE`, which doesn't work in `const` contexts.
-///
-/// C code always uses `_IOC_READ | _IOC_WRITE` instead of a dedicated constant.
-pub const _IOC_READ_WRITE: Dir = Dir(platform::_IOC_READ | platform::_IOC_WRITE);
+    #[test]
+    fn bad() {
+        let ioctl: Ioctl<*mut u32> = _IOR_BAD(b'z', 1, 8);
+        assert_eq!(ioctl.request(), _IOR::<u64>(b'z', 1).request());
+        let ioctl: Ioctl<*const u32> = _IOW_BAD(b'z', 2, size_of::<*const u32>());
+        assert_eq!(ioctl.request(), _IOW::<*const u32>(b'z', 2).request());
+        let ioctl: Ioctl<*mut u8> = _IOWR_BAD(b'z', 3, 0);
+        assert_eq!(ioctl.decode().dir, _IOC_READ_WRITE);
+        assert_eq!(ioctl.size(), 0);
+    }
 
-/// Indicates that an `ioctl` neither reads nor writes data through its argument.
-///
-/// Identical to [`_IOC_NONE`].
-pub const IOC_VOID: Dir = _IOC_NONE;
+    #[test]
+    fn ioc_typed() {
+        let ioctl: Ioctl<*mut [u8; 12]> = _IOC_typed(_IOC_WRITE, b'z', 4);
+        assert_eq!(ioctl, _IOC(_IOC_WRITE, b'z', 4, 12));
+        assert_eq!(ioctl, crate::ioctl_for(_IOC_WRITE, b'z', 4));
+        assert_eq!(_IOC_typed::<u32>(_IOC_READ, b'z', 5), _IOR::<u32>(b'z', 5));
+        assert_eq!(_IOC_typed::<()>(_IOC_NONE, b'z', 6).size(), 0);
+    }
 
-/// Indicates that an `ioctl` reads data from the kernel through its pointer argument.
-///
-/// Identical to [`_IOC_READ`].
-pub const IOC_OUT: Dir = _IOC_READ;
+    #[test]
+    fn ioctl_for_any_dir() {
+        let ioctl: Ioctl<*mut [u8; 12]> = crate::ioctl_for(_IOC_WRITE, b'z', 4);
+        assert_eq!(ioctl.decode().dir, _IOC_WRITE);
+        assert_eq!(ioctl.size(), 12);
+        assert_eq!(
+            ioctl.request(),
+            _IOC::<NoArgs>(_IOC_WRITE, b'z', 4, 12).request()
+        );
+    }
 
-/// Indicates that an `ioctl` writes data to the kernel through its pointer argument.
-///
-/// Identical to [`_IOC_WRITE`].
-pub const IOC_IN: Dir = _IOC_WRITE;
+    #[test]
+    #[cfg(feature = "mock")]
+    fn ioctl_retry() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
 
-/// Indicates that an `ioctl` both reads and writes data through its pointer argument.
-///
-/// Identical to [`_IOC_READ_WRITE`] and `_IOC_READ | _IOC_WRITE`.
-pub const IOC_INOUT: Dir = _IOC_READ_WRITE;
+        const GET: Ioctl<*mut u32> = _IOR(b'x', 1);
+        const RESET: Ioctl = _IO(b'x', 2);
 
-/// Creates an [`Ioctl`] that doesn't read or write any userspace data.
-///
-/// This type of ioctl can return an `int` to userspace via the return value of the `ioctl` syscall.
-/// By default, the returned [`Ioctl`] takes no argument.
-/// [`Ioctl::cast_arg`] can be used to pass a direct argument to the `ioctl`.
-///
-/// # Example
-///
-/// `KVM_GET_API_VERSION` is an `ioctl` that does not take any arguments. The API version is
-/// returned as the return value of the `ioctl(2)` function.
-///
-/// From `linux/kvm.h`:
-///
-/// ```c
-/// #define KVMIO 0xAE
-/// ...
-/// #define KVM_GET_API_VERSION       _IO(KVMIO,   0x00)
-/// ```
-///
-/// ```rust
-/// use std::fs::File;
-/// use uoctl::*;
-///
-/// const KVMIO: u8 = 0xAE;
-/// const KVM_GET_API_VERSION: Ioctl<NoArgs> = _IO(KVMIO, 0x00);
-///
-/// let file = File::open("/dev/kvm")?;
-///
-/// let version = unsafe { KVM_GET_API_VERSION.ioctl(&file)? };
-/// println!("KVM API version: {version}");
-/// # std::io::Result::Ok(())
-/// ```
-#[allow(non_snake_case)]
-pub const fn _IO(ty: u8, nr: u8) -> Ioctl<NoArgs> {
-    _IOC(_IOC_NONE, ty, nr, 0)
-}
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fd = MockFd::new({
+            let calls = calls.clone();
+            move |request, arg| {
+                // Fail with `EINTR` on the first 3 attempts.
+                if calls.fetch_add(1, Ordering::SeqCst) % 4 < 3 {
+                    return Err(io::Error::from_raw_os_error(libc::EINTR));
+                }
+                if request == GET.request() {
+                    unsafe { *(arg as *mut u32) = 7 };
+                }
+                Ok(0)
+            }
+        })
+        .unwrap();
 
-/// Creates an [`Ioctl`] that reads data of type `T` from the kernel.
-///
-/// By default, a pointer to the data will be passed to `ioctl(2)`, and the kernel will fill the
-/// destination with data.
-///
-/// # Errors
-///
-/// This method will cause a compile-time assertion failure if the size of `T` exceeds the `ioctl`
-/// argument size limit.
-/// This typically means that the wrong type `T` was specified.
-///
-/// # Examples
-///
-/// From `linux/random.h`:
-///
-/// ```c
-/// /* ioctl()'s for the random number generator */
-///
-/// /* Get the entropy count. */
-/// #define RNDGETENTCNT	_IOR( 'R', 0x00, int )
-/// ```
-///
-/// ```
-/// use std::fs::File;
-/// use std::ffi::c_int;
-/// use uoctl::*;
-///
-/// const RNDGETENTCNT: Ioctl<*mut c_int> = _IOR(b'R', 0x00);
-///
-/// let file = File::open("/dev/urandom")?;
-///
-/// let mut entropy = 0;
-/// unsafe { RNDGETENTCNT.ioctl(&file, &mut entropy)? };
-///
-/// println!("{entropy} bits of entropy in /dev/urandom");
-/// # std::io::Result::Ok(())
-/// ```
-#[allow(non_snake_case)]
-pub const fn _IOR<T>(ty: u8, nr: u8) -> Ioctl<*mut T> {
-    const {
-        assert!(size_of::<T>() <= platform::MAX_ARG_SIZE);
+        let mut value = 0;
+        unsafe { GET.ioctl_retry(&fd, &mut value).unwrap() };
+        assert_eq!(value, 7);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        unsafe { RESET.ioctl_retry(&fd).unwrap() };
+        assert_eq!(calls.load(Ordering::SeqCst), 8);
+
+        let err = unsafe { RESET.ioctl(&fd).unwrap_err() };
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn ioctl_as() {
+        const COUNT: Ioctl<c_int> = _IO(b'x', 1).cast_arg();
+        const RESET: Ioctl = _IO(b'x', 2);
+
+        let fd = MockFd::new(|request, arg| {
+            if request == COUNT.request() {
+                Ok(arg as c_int)
+            } else {
+                Ok(1)
+            }
+        })
+        .unwrap();
+
+        let n: usize = unsafe { COUNT.ioctl_as(&fd, 5).unwrap() };
+        assert_eq!(n, 5);
+        let n: c_int = unsafe { COUNT.ioctl_as(&fd, -5).unwrap() };
+        assert_eq!(n, -5);
+        let err = unsafe { COUNT.ioctl_as::<usize>(&fd, -5).unwrap_err() };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let () = unsafe { RESET.ioctl_as(&fd).unwrap() };
+        let n: usize = unsafe { RESET.ioctl_as(&fd).unwrap() };
+        assert_eq!(n, 1);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_unit() {
+        let (read, _write) = pipe();
+        let mut bytes = -1;
+        unsafe { FIONREAD.ioctl_unit(&read, &mut bytes).unwrap() };
+        assert_eq!(bytes, 0);
+
+        let err = unsafe { _IO(b'x', 0xff).ioctl_unit(&read).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_raw() {
+        use std::io::Write;
+
+        let (read, write) = pipe();
+        File::from(write).write_all(b"abc").unwrap();
+        let mut bytes = 0;
+        unsafe { FIONREAD.ioctl_raw(read.as_raw_fd(), &mut bytes).unwrap() };
+        assert_eq!(bytes, 3);
+
+        let err = unsafe { _IO(b'x', 0xff).ioctl_raw(read.as_raw_fd()).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_fd() {
+        use std::io::Write;
+
+        let (read, write) = pipe();
+        File::from(write).write_all(b"abc").unwrap();
+        let mut bytes = 0;
+        unsafe { FIONREAD.ioctl_fd(read.as_fd(), &mut bytes).unwrap() };
+        assert_eq!(bytes, 3);
+        bytes = 0;
+        unsafe { FIONREAD.ioctl_fd(&read, &mut bytes).unwrap() };
+        assert_eq!(bytes, 3);
+
+        let err = unsafe { _IO(b'x', 0xff).ioctl_fd(&read).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn ioctl_owned_fd() {
+        use std::os::fd::IntoRawFd;
+
+        const CREATE: Ioctl<c_int> = _IO(b'x', 1).cast_arg();
+        const CREATE_NOARGS: Ioctl = _IO(b'x', 2);
+
+        let (read, _write) = pipe();
+        let read_fd = read.as_raw_fd();
+        let fd = MockFd::new(move |request, arg| {
+            if request == CREATE.request() {
+                Ok(arg as c_int)
+            } else {
+                Ok(unsafe { libc::dup(read_fd) })
+            }
+        })
+        .unwrap();
+
+        let new = unsafe { CREATE_NOARGS.ioctl_owned_fd(&fd).unwrap() };
+        let mut bytes = -1;
+        unsafe { FIONREAD.ioctl(&new, &mut bytes).unwrap() };
+        assert_eq!(bytes, 0);
+
+        let new = unsafe { CREATE.ioctl_owned_fd(&fd, new.into_raw_fd()).unwrap() };
+        unsafe { FIONREAD.ioctl(&new, &mut bytes).unwrap() };
+
+        let err = unsafe { CREATE.ioctl_owned_fd(&fd, -5).unwrap_err() };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    #[cfg(feature = "mock")]
+    fn ioctl_owned_fd_cloexec() {
+        const CREATE: Ioctl<c_int> = _IO(b'x', 1).cast_arg();
+        const CREATE_NOARGS: Ioctl = _IO(b'x', 2);
+
+        let (read, _write) = pipe();
+        let read_fd = read.as_raw_fd();
+        // `dup` doesn't copy `FD_CLOEXEC`.
+        let fd = MockFd::new(move |_, _| Ok(unsafe { libc::dup(read_fd) })).unwrap();
+
+        let cloexec = |fd: &OwnedFd| {
+            let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFD) };
+            assert_ne!(flags, -1);
+            flags & libc::FD_CLOEXEC != 0
+        };
+
+        let new = unsafe { CREATE.ioctl_owned_fd(&fd, 0).unwrap() };
+        assert!(!cloexec(&new));
+        let new = unsafe { CREATE.ioctl_owned_fd_cloexec(&fd, 0).unwrap() };
+        assert!(cloexec(&new));
+        let new = unsafe { CREATE_NOARGS.ioctl_owned_fd_cloexec(&fd).unwrap() };
+        assert!(cloexec(&new));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn try_ioctl() {
+        let (read, _write) = pipe();
+        let mut bytes = -1;
+        assert_eq!(unsafe { FIONREAD.try_ioctl(&read, &mut bytes) }, Ok(0));
+        assert_eq!(bytes, 0);
+        assert_eq!(
+            unsafe { FIONREAD.try_ioctl(&read, ptr::null_mut()) },
+            Err(IoctlError::BadAddress),
+        );
+        assert_eq!(
+            unsafe { _IO(b'x', 0xff).try_ioctl(&read) },
+            Err(IoctlError::NotSupported),
+        );
+
+        for errno in [
+            libc::ENOTTY,
+            libc::EFAULT,
+            libc::EINVAL,
+            libc::EINTR,
+            libc::EBUSY,
+        ] {
+            let e = IoctlError::from_errno(errno);
+            assert_eq!(e.errno(), errno);
+            assert_eq!(io::Error::from(e).raw_os_error(), Some(errno));
+            assert_eq!(
+                e.to_string(),
+                io::Error::from_raw_os_error(errno).to_string()
+            );
+        }
+        assert_eq!(IoctlError::from_errno(libc::EINTR), IoctlError::Interrupted);
+        assert_eq!(
+            IoctlError::from_errno(libc::EBUSY),
+            IoctlError::Other(libc::EBUSY)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read() {
+        use std::io::Write;
+
+        let (read, write) = pipe();
+        File::from(write).write_all(b"abcd").unwrap();
+        assert_eq!(unsafe { FIONREAD.read(&read).unwrap() }, 4);
+
+        let err = unsafe { _IOR::<c_int>(b'R', 0x00).read(&read).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[should_panic = "`read` cannot be used"]
+    fn read_iowr() {
+        const IOWR: Ioctl<*mut c_int> = _IOWR(b'x', 1);
+        let (read, _write) = pipe();
+        unsafe { IOWR.read(&read).ok() };
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write() {
+        const FIONBIO: Ioctl<*const c_int> = Ioctl::from_raw(libc::FIONBIO as _);
+
+        let nonblocking = |fd: &OwnedFd| {
+            let flags = unsafe { libc::fcntl(fd.as_raw_fd(), libc::F_GETFL) };
+            assert_ne!(flags, -1);
+            flags & libc::O_NONBLOCK != 0
+        };
+
+        let (read, _write) = pipe();
+        assert!(!nonblocking(&read));
+        unsafe { FIONBIO.write(&read, &1).unwrap() };
+        assert!(nonblocking(&read));
+        unsafe { FIONBIO.write(&read, &0).unwrap() };
+        assert!(!nonblocking(&read));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn modify() {
+        use std::io::Write;
+
+        let (read, write) = pipe();
+        File::from(write).write_all(b"abc").unwrap();
+        let mut bytes = -1;
+        unsafe { FIONREAD.modify(&read, &mut bytes).unwrap() };
+        assert_eq!(bytes, 3);
+    }
+
+    #[test]
+    fn check_size() {
+        const IOR: Ioctl<*mut u32> = _IOR(b'x', 1);
+        assert!(IOR.check_size::<u32>());
+        assert!(IOR.check_size::<[u8; 4]>());
+        assert!(!IOR.check_size::<u64>());
+        assert!(!IOR.check_size::<()>());
+
+        assert!(_IO(b'x', 1).check_size::<()>());
+        assert!(!_IO(b'x', 1).check_size::<u32>());
+    }
+
+    #[test]
+    fn max_arg_size() {
+        assert_eq!(MAX_ARG_SIZE, 8191);
+        assert_eq!(
+            _IOC::<NoArgs>(_IOC_READ, b'x', 1, MAX_ARG_SIZE).size(),
+            MAX_ARG_SIZE
+        );
+        let res = std::panic::catch_unwind(|| _IOC::<NoArgs>(_IOC_READ, b'x', 1, MAX_ARG_SIZE + 1));
+        assert!(res.is_err());
+    }
+
+    #[test]
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    fn bsd_decode() {
+        let ioctl = _IOWINT(b'x', 1);
+        assert_eq!(ioctl.request(), 0x20047801);
+        assert_eq!(
+            ioctl.decode(),
+            Decoded {
+                dir: IOC_VOID,
+                ty: b'x',
+                nr: 1,
+                size: size_of::<c_int>(),
+            },
+        );
+        let ioctl = _IOR::<u32>(b'x', 1);
+        assert_eq!(ioctl.request(), 0x40047801);
+        assert_eq!(
+            ioctl.decode(),
+            Decoded {
+                dir: IOC_OUT,
+                ty: b'x',
+                nr: 1,
+                size: 4,
+            },
+        );
+
+        // FreeBSD's `FIONREAD`, `_IOR('f', 127, int)`.
+        let fionread = Ioctl::<NoArgs>::from_raw(0x4004667f);
+        assert_eq!(fionread.dir(), IOC_OUT);
+        assert_eq!(fionread.ty(), b'f');
+        assert_eq!(fionread.nr(), 127);
+        assert_eq!(fionread.size(), 4);
+
+        let ioctl = _IOWR::<[u8; MAX_ARG_SIZE]>(0xff, 0xff);
+        assert_eq!(ioctl.dir(), IOC_INOUT);
+        assert_eq!(ioctl.size(), MAX_ARG_SIZE);
+        assert_eq!((ioctl.ty(), ioctl.nr()), (0xff, 0xff));
+    }
+
+    #[test]
+    fn group_char() {
+        assert_eq!(_IO(b'E', 1).group_char(), 'E');
+        assert_eq!(_IOR::<u32>(b'U', 1).group_char(), 'U');
+        assert_eq!(_IO(0xAE, 1).group_char(), '\u{AE}');
     }
-    _IOC(_IOC_READ, ty, nr, size_of::<T>())
-}
 
-/// Creates an [`Ioctl`] that writes data of type `T` to the kernel.
-///
-/// By default, a pointer to the data will be passed to `ioctl(2)`, and the kernel will read the
-/// argument from that location.
-/// This is generally correct if the argument is a `struct`, but if the argument is a primitive type
-/// like `int`, or is already a pointer like `char*`, many drivers expect the argument to be passed
-/// to `ioctl(2)` *without* indirection.
-/// To bind to those `ioctl`s, you can call [`Ioctl::with_direct_arg`] on the [`Ioctl`] returned by
-/// [`_IOW`].
-///
-/// **Note**: Some Linux `ioctl`s are **incorrectly** declared with [`_IOW`].
-/// They *will* write to the argument, and if the pointer you pass doesn't have write permission it
-/// *will* cause UB.
-/// Use [`Ioctl::cast_mut`] before invoking the [`Ioctl`] to fix these kinds of misdeclared
-/// `ioctl`s!
-///
-/// Apart from reading comments in the header files, and looking at the Linux source code, there is
-/// no reliable way of finding out which `ioctl` definitions are wrong like that.
-/// Good luck!
-///
-/// # Errors
-///
-/// This method will cause a compile-time assertion failure if the size of `T` exceeds the `ioctl`
-/// argument size limit.
-/// This typically means that the wrong type `T` was specified.
-///
-/// # Example
-///
-/// Let's create a virtual input device with *uinput*.
-///
-/// From `linux/uinput.h`:
-///
-/// ```c
-/// /* ioctl */
-/// #define UINPUT_IOCTL_BASE	'U'
-/// #define UI_DEV_CREATE		_IO(UINPUT_IOCTL_BASE, 1)
-/// #define UI_DEV_DESTROY		_IO(UINPUT_IOCTL_BASE, 2)
-/// ...
-/// #define UI_DEV_SETUP _IOW(UINPUT_IOCTL_BASE, 3, struct uinput_setup)
-/// ...
-/// #define UI_SET_EVBIT		_IOW(UINPUT_IOCTL_BASE, 100, int)
-/// #define UI_SET_KEYBIT		_IOW(UINPUT_IOCTL_BASE, 101, int)
-/// ```
-///
-/// From `linux/input.h`:
-///
-/// ```c
-/// #define EV_KEY			0x01
-/// ...
-/// #define KEY_A			30
-/// ```
-///
-/// ```rust
-/// use std::{mem, fs::File, ffi::{c_char, c_int}};
-/// use libc::uinput_setup;
-/// use uoctl::*;
-///
-/// const UINPUT_IOCTL_BASE: u8 = b'U';
-/// const UI_DEV_CREATE: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 1);
-/// const UI_DEV_DESTROY: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 2);
-/// const UI_DEV_SETUP: Ioctl<*const uinput_setup> = _IOW(UINPUT_IOCTL_BASE, 3);
-/// // These two expect their argument to be passed directly instead of behind a pointer:
-/// const UI_SET_EVBIT: Ioctl<c_int> = _IOW(UINPUT_IOCTL_BASE, 100).with_direct_arg();
-/// const UI_SET_KEYBIT: Ioctl<c_int> = _IOW(UINPUT_IOCTL_BASE, 101).with_direct_arg();
-///
-/// const EV_KEY: c_int = 0x01;
-/// const KEY_A: c_int = 30;
-///
-/// let uinput = File::options().write(true).open("/dev/uinput")?;
-///
-/// // Enable the "A" key:
-/// unsafe {
-///     UI_SET_EVBIT.ioctl(&uinput, EV_KEY)?;
-///     UI_SET_KEYBIT.ioctl(&uinput, KEY_A)?;
-/// }
-///
-/// let mut setup: uinput_setup = unsafe { mem::zeroed() };
-/// setup.name[0] = b'A' as c_char; // (must not be blank)
-/// unsafe {
-///     UI_DEV_SETUP.ioctl(&uinput, &setup)?;
-///     UI_DEV_CREATE.ioctl(&uinput)?;
-///     // ...use the device...
-///     UI_DEV_DESTROY.ioctl(&uinput)?;
-/// }
-/// # std::io::Result::Ok(())
-/// ```
-#[allow(non_snake_case)]
-pub const fn _IOW<T>(ty: u8, nr: u8) -> Ioctl<*const T> {
-    const {
-        assert!(size_of::<T>() <= platform::MAX_ARG_SIZE);
+    #[test]
+    fn with_size() {
+        let ioctl = _IOWR::<u32>(b'x', 7).with_size(100);
+        assert_eq!(
+            ioctl.decode(),
+            Decoded {
+                dir: _IOC_READ_WRITE,
+                ty: b'x',
+                nr: 7,
+                size: 100,
+            },
+        );
+        assert_eq!(ioctl.with_size(4), _IOWR::<u32>(b'x', 7));
+        assert_eq!(_IO(b'x', 1).with_size(0), _IO(b'x', 1));
     }
-    _IOC(_IOC_WRITE, ty, nr, size_of::<T>())
-}
 
-/// Creates an [`Ioctl`] that writes and reads data of type `T`.
-///
-/// By default, a pointer to the data will be passed to `ioctl(2)`, and the kernel will read and
-/// write to the data `T`.
-///
-/// # Errors
-///
-/// This method will cause a compile-time assertion failure if the size of `T` exceeds the `ioctl`
-/// argument size limit.
-/// This typically means that the wrong type `T` was specified.
-#[allow(non_snake_case)]
-pub const fn _IOWR<T>(ty: u8, nr: u8) -> Ioctl<*mut T> {
-    const {
-        assert!(size_of::<T>() <= platform::MAX_ARG_SIZE);
+    #[test]
+    #[should_panic]
+    fn with_size_too_large() {
+        _IOR::<u8>(b'x', 1).with_size(MAX_ARG_SIZE + 1);
     }
-    _IOC(_IOC_READ_WRITE, ty, nr, size_of::<T>())
-}
 
-/// Creates an [`Ioctl`] that writes an `int` to the kernel.
-///
-/// This is a BSD-specific function that only exists in the BSD C headers. Using it on other systems
-/// may not result in the correct `ioctl` request code.
-///
-/// Linux does not have a function/macro like this, and it typically uses [`_IOW`] to define
-/// `ioctl`s that pass `int`s (often necessitating a call to [`Ioctl::with_direct_arg`]).
-#[allow(non_snake_case)]
-pub const fn _IOWINT(group: u8, nr: u8) -> Ioctl<c_int> {
-    _IOC(IOC_VOID, group, nr, size_of::<c_int>())
-}
+    #[test]
+    fn from_parts() {
+        for (dir, ty, nr, size) in [
+            (_IOC_NONE, b'x', 1, 0),
+            (_IOC_READ, b'V', 0, 104),
+            (_IOC_WRITE, 0xff, 0xff, MAX_ARG_SIZE),
+            (_IOC_READ_WRITE, 0, 0, 1),
+        ] {
+            let ioctl = Ioctl::<NoArgs>::from_parts(dir, ty, nr, size);
+            assert_eq!(ioctl, _IOC(dir, ty, nr, size));
+            assert_eq!(ioctl.decode(), Decoded { dir, ty, nr, size });
+        }
+    }
 
-/// Manually constructs an [`Ioctl`] from its components.
-///
-/// Also see [`Ioctl::from_raw`] for a way to interface with "legacy" ioctls that don't yet follow
-/// this scheme.
-///
-/// Prefer to use [`_IO`], [`_IOR`], [`_IOW`], or [`_IOWR`] where possible.
-///
-/// # Arguments
-///
-/// - **`dir`**: Direction of the ioctl. One of [`_IOC_NONE`], [`_IOC_READ`], [`_IOC_WRITE`], or
-///   `_IOC_READ | _IOC_WRITE` (aka [`_IOC_READ_WRITE`]).
-/// - **`ty`**: the `ioctl` group or type to identify the driver or subsystem. You can find a list
-///   [here].
-/// - **`nr`**: the `ioctl` number within its group.
-/// - **`size`**: the size of the `ioctl`'s (direct or indirect) argument.
-///
-/// [here]: https://www.kernel.org/doc/html/latest/userspace-api/ioctl/ioctl-number.html
-///
-/// # Panics
-///
-/// This function may panic when `size` exceeds the (platform-specific) maximum parameter size.
-///
-/// # Example
-///
-/// `UI_GET_SYSNAME` is a polymorphic `ioctl` that can be invoked with a variety of buffer lengths.
-/// This function can be used to bind to it.
-///
-/// From `linux/uinput.h`:
-///
-/// ```c
-/// /* ioctl */
-/// #define UINPUT_IOCTL_BASE	'U'
-/// ...
-/// #define UI_GET_SYSNAME(len)	_IOC(_IOC_READ, UINPUT_IOCTL_BASE, 44, len)
-/// ```
-///
-/// ```no_run
-/// use std::ffi::c_char;
-/// use uoctl::*;
-///
-/// const UINPUT_IOCTL_BASE: u8 = b'U';
-/// const fn UI_GET_SYSNAME(len: usize) -> Ioctl<*mut c_char> {
-///     _IOC(_IOC_READ, UINPUT_IOCTL_BASE, 44, len)
-/// }
-///
-/// // Use it like this:
-/// unsafe {
-/// #   let fd = &123;
-///     let mut buffer = [0 as c_char; 16];
-///     UI_GET_SYSNAME(16).ioctl(fd, buffer.as_mut_ptr())?;
-/// }
-/// # std::io::Result::Ok(())
-/// ```
-#[allow(non_snake_case)]
-#[inline]
-pub const fn _IOC<T: ?Sized>(dir: Dir, ty: u8, nr: u8, size: usize) -> Ioctl<T> {
-    assert!(size <= platform::MAX_ARG_SIZE);
+    #[test]
+    fn ioc_rejects_oversized() {
+        let res = std::panic::catch_unwind(|| _IOC::<NoArgs>(_IOC_READ, b'x', 1, 8192));
+        assert!(res.is_err());
 
-    let request = platform::_IOC(dir.0, ty as u32, nr as u32, size as u32);
-    Ioctl::from_raw(request)
-}
+        // The BSD encoding only has 13 bits for the size, so an oversized length must not bleed
+        // into the direction bits.
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        {
+            let res = std::panic::catch_unwind(|| {
+                platform::_IOC(platform::IOC_OUT, b'x' as u32, 1, 8192)
+            });
+            assert!(res.is_err());
+            assert_eq!(
+                platform::_IOC(platform::IOC_OUT, b'x' as u32, 1, 8191),
+                0x5fff7801,
+            );
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn into_u32() {
+        fn request(ioctl: impl Into<u32>) -> u32 {
+            ioctl.into()
+        }
+
+        let ioctl = _IOR::<u32>(b'x', 1);
+        assert_eq!(u32::from(ioctl), ioctl.request());
+        assert_eq!(request(ioctl), ioctl.request());
+        assert_eq!(request(_IO(b'x', 1)), _IO(b'x', 1).request());
+    }
 
     #[test]
-    fn dir_or() {
-        assert_ne!(_IOC_NONE, _IOC_READ);
-        assert_ne!(_IOC_NONE, _IOC_WRITE);
+    fn cast_pointee() {
+        let ioctl: Ioctl<*mut [u8; 4]> = _IOR::<u32>(b'x', 1).cast_pointee();
+        assert_eq!(ioctl.request(), _IOR::<u32>(b'x', 1).request());
+        let ioctl: Ioctl<*const [u8; 4]> = _IOW::<u32>(b'x', 1).cast_pointee();
+        assert_eq!(ioctl.request(), _IOW::<u32>(b'x', 1).request());
+    }
 
-        assert_eq!(_IOC_READ | _IOC_WRITE, _IOC_READ_WRITE);
-        assert_eq!(_IOC_READ | _IOC_READ, _IOC_READ);
-        assert_eq!(_IOC_WRITE | _IOC_WRITE, _IOC_WRITE);
-        assert_eq!(_IOC_NONE | _IOC_NONE, _IOC_NONE);
+    #[test]
+    #[cfg(feature = "mock")]
+    fn ioctl_slice() {
+        const FILL: Ioctl<*mut u16> = _IOC(_IOC_READ, b'x', 1, 0);
+
+        let fd = MockFd::new(|request, arg| {
+            let len = Ioctl::<NoArgs>::from_raw(request).size() / size_of::<u16>();
+            let slice = unsafe { std::slice::from_raw_parts_mut(arg as *mut u16, len) };
+            for (i, elem) in slice.iter_mut().enumerate() {
+                *elem = i as u16;
+            }
+            Ok(len as c_int)
+        })
+        .unwrap();
+
+        let mut buf = [0xffff; 4];
+        let ioctl = FILL.with_size(size_of_val(&buf));
+        assert_eq!(unsafe { ioctl.ioctl_slice(&fd, &mut buf).unwrap() }, 4);
+        assert_eq!(buf, [0, 1, 2, 3]);
+
+        let mut buf = [0xffff; 3];
+        let err = unsafe { ioctl.ioctl_slice(&fd, &mut buf).unwrap_err() };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            err.get_ref().unwrap().downcast_ref(),
+            Some(&SizeMismatch {
+                encoded: 8,
+                actual: 6
+            }),
+        );
+        assert_eq!(buf, [0xffff; 3]);
     }
 
     #[test]
-    #[should_panic(expected = "`_IOC_NONE` cannot be combined with other values")]
-    fn dir_none_or_read() {
-        let _ = _IOC_NONE | _IOC_READ;
+    #[cfg(feature = "mock")]
+    fn ioctl_retry_n() {
+        use std::sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        };
+
+        const SET: Ioctl<c_int> = _IO(b'x', 1).cast_arg();
+        const RESET: Ioctl = _IO(b'x', 2);
+
+        // Fails with `EINTR` until it has been called `arg` times (or 3 times, without argument).
+        let calls = Arc::new(AtomicUsize::new(0));
+        let fd = MockFd::new({
+            let calls = calls.clone();
+            move |request, arg| {
+                let limit = if request == SET.request() { arg } else { 3 };
+                if calls.fetch_add(1, Ordering::SeqCst) + 1 < limit {
+                    Err(io::Error::from_raw_os_error(libc::EINTR))
+                } else {
+                    Ok(0)
+                }
+            }
+        })
+        .unwrap();
+
+        let err = unsafe { SET.ioctl_retry_n(&fd, 5, 3).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::EINTR));
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 4);
+
+        unsafe { SET.ioctl_retry_n(&fd, 5, 4).unwrap() };
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 5);
+
+        let err = unsafe { RESET.ioctl_retry_n(&fd, 0).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::EINTR));
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+        unsafe { RESET.ioctl_retry_n(&fd, 2).unwrap() };
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 3);
     }
 
     #[test]
-    #[should_panic(expected = "`_IOC_NONE` cannot be combined with other values")]
-    fn dir_none_or_write() {
-        let _ = _IOC_NONE | _IOC_WRITE;
+    fn ioc_checked() {
+        assert_eq!(
+            _IOC_checked::<*mut u8>(_IOC_READ, b'U', 44, 16),
+            Some(_IOC(_IOC_READ, b'U', 44, 16)),
+        );
+        assert_eq!(
+            _IOC_checked::<*mut u8>(_IOC_READ, b'U', 44, MAX_ARG_SIZE),
+            Some(_IOC(_IOC_READ, b'U', 44, MAX_ARG_SIZE)),
+        );
+        assert_eq!(
+            _IOC_checked::<*mut u8>(_IOC_READ, b'U', 44, MAX_ARG_SIZE + 1),
+            None,
+        );
+        assert_eq!(
+            _IOC_checked::<*mut u8>(_IOC_READ, b'U', 44, usize::MAX),
+            None
+        );
     }
 
     #[test]
-    #[should_panic(expected = "`_IOC_NONE` cannot be combined with other values")]
-    fn dir_read_or_none() {
-        let _ = _IOC_READ | _IOC_NONE;
+    fn try_from_raw() {
+        const EVIOCGVERSION: Ioctl<*mut c_int> = _IOR(b'E', 0x01);
+
+        assert_eq!(
+            Ioctl::<*mut c_int>::try_from_raw(EVIOCGVERSION.request()),
+            Ok(EVIOCGVERSION),
+        );
+        assert_eq!(
+            Ioctl::<*mut u64>::try_from_raw(EVIOCGVERSION.request()),
+            Err(SizeMismatch {
+                encoded: 4,
+                actual: 8,
+            }),
+        );
+        assert_eq!(
+            Ioctl::<*mut u64>::try_from_raw(FIONREAD.request()),
+            Ok(Ioctl::from_raw(FIONREAD.request())),
+        );
     }
 
     #[test]
-    #[should_panic(expected = "`_IOC_NONE` cannot be combined with other values")]
-    fn dir_write_or_none() {
-        let _ = _IOC_WRITE | _IOC_NONE;
+    #[cfg(feature = "std")]
+    fn to_c_macro() {
+        assert_eq!(_IO(b'U', 1).to_c_macro(), "_IO('U', 1)");
+        assert_eq!(
+            _IOR::<u32>(b'V', 0x10).to_c_macro(),
+            "_IOR('V', 16, /* 4 bytes */)"
+        );
+        assert_eq!(
+            _IOW::<u8>(b'V', 0x10).to_c_macro(),
+            "_IOW('V', 16, /* 1 byte */)"
+        );
+        assert_eq!(
+            _IOWR::<[u8; 104]>(0xFF, 11).to_c_macro(),
+            "_IOWR(0xff, 11, /* 104 bytes */)"
+        );
+        assert_eq!(
+            _IOC::<*mut u8>(_IOC_NONE, b'\\', 2, 8).to_c_macro(),
+            "_IOC(_IOC_NONE, '\\\\', 2, /* 8 bytes */)"
+        );
+        assert_eq!(_IO(b'\'', 3).to_c_macro(), "_IO('\\'', 3)");
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_with() {
+        use std::io::Write;
+
+        const SET: Ioctl<c_int> = _IO(b'x', 2).cast_arg();
+        const RESET: Ioctl = _IO(b'x', 3);
+
+        let (read, write) = pipe();
+        File::from(write).write_all(b"hello").unwrap();
+
+        let mut bytes = 0;
+        let res = unsafe {
+            FIONREAD.ioctl_with(&mut bytes, |request, arg| {
+                libc::ioctl(read.as_raw_fd(), request as _, arg)
+            })
+        };
+        assert_eq!(res.unwrap(), 0);
+        assert_eq!(bytes, 5);
+
+        let res = unsafe {
+            SET.ioctl_with(7, |request, arg| {
+                assert_eq!(request, SET.request());
+                arg as c_int + 1
+            })
+        };
+        assert_eq!(res.unwrap(), 8);
+
+        let err = unsafe {
+            RESET
+                .ioctl_with(|request, arg| {
+                    assert_eq!(request, RESET.request());
+                    assert!(arg.is_null());
+                    libc::ioctl(-1, request as _, arg)
+                })
+                .unwrap_err()
+        };
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn ioctl_non_null() {
+        use std::io::Write;
+
+        let (read, write) = pipe();
+        File::from(write).write_all(b"hello").unwrap();
+
+        let mut bytes = 0;
+        unsafe {
+            FIONREAD
+                .ioctl_non_null(&read, NonNull::from(&mut bytes))
+                .unwrap();
+        }
+        assert_eq!(bytes, 5);
+
+        const FIONBIO: Ioctl<*const c_int> = Ioctl::from_raw(libc::FIONBIO as _);
+        let nonblocking: c_int = 1;
+        unsafe {
+            FIONBIO
+                .ioctl_non_null(&read, NonNull::from(&nonblocking))
+                .unwrap();
+        }
+        let flags = unsafe { libc::fcntl(read.as_raw_fd(), libc::F_GETFL) };
+        assert_ne!(flags & libc::O_NONBLOCK, 0);
+    }
+
+    #[test]
+    fn is_compat() {
+        const COMPAT: Ioctl<*mut u8> = _IOC(_IOC_READ, b't', 63, 8);
+        const NATIVE: Ioctl<*mut u8> = _IOC(_IOC_READ, b't', 63, 16);
+
+        assert!(COMPAT.is_compat(8, 16));
+        assert!(!NATIVE.is_compat(8, 16));
+        assert!(!COMPAT.is_compat(8, 8));
+        assert!(!COMPAT.is_compat(4, 16));
+    }
+
+    #[test]
+    fn encoding_encode() {
+        const ENCODINGS: [Encoding; 3] = [
+            Encoding::LinuxGeneric,
+            Encoding::LinuxAlternate,
+            Encoding::Bsd,
+        ];
+
+        // `_IO('V', 1)`, `_IOR('V', 0, struct v4l2_capability)`, `_IOW('U', 3, struct
+        // uinput_setup)`, and `_IOWR('V', 4, struct v4l2_format)`, in the encodings above.
+        let codes = [
+            (
+                (_IOC_NONE, b'V', 1, 0),
+                [0x00005601, 0x20005601, 0x20005601],
+            ),
+            (
+                (_IOC_READ, b'V', 0, 104),
+                [0x80685600, 0x40685600, 0x40685600],
+            ),
+            (
+                (_IOC_WRITE, b'U', 3, 92),
+                [0x405c5503, 0x805c5503, 0x805c5503],
+            ),
+            (
+                (_IOC_READ_WRITE, b'V', 4, 208),
+                [0xc0d05604, 0xc0d05604, 0xc0d05604],
+            ),
+        ];
+        for ((dir, ty, nr, size), requests) in codes {
+            for (encoding, request) in ENCODINGS.into_iter().zip(requests) {
+                assert_eq!(encoding.encode(dir, ty, nr, size), request, "{encoding}");
+                assert_eq!(
+                    encoding.decode(request),
+                    Decoded { dir, ty, nr, size },
+                    "{encoding}",
+                );
+            }
+        }
+
+        // Matches the encoding of the target.
+        let ioctl = _IOWR::<[u8; 208]>(b'V', 4);
+        assert_eq!(
+            crate::encoding().encode(_IOC_READ_WRITE, b'V', 4, 208),
+            ioctl.request(),
+        );
+        assert_eq!(crate::encoding().decode(ioctl.request()), ioctl.decode());
+        let ioctl = _IO(b'V', 1);
+        assert_eq!(
+            crate::encoding().encode(_IOC_NONE, b'V', 1, 0),
+            ioctl.request()
+        );
+        assert_eq!(crate::encoding().decode(ioctl.request()), ioctl.decode());
+
+        // The size field of the generic encoding is 1 bit larger.
+        assert_eq!(Encoding::LinuxGeneric.decode(0x3fff0000).size, 0x3fff);
+        assert_eq!(Encoding::LinuxAlternate.decode(0x3fff0000).size, 0x1fff);
+    }
+
+    #[test]
+    #[should_panic = "overflows the size field"]
+    fn encoding_encode_too_large() {
+        Encoding::LinuxAlternate.encode(_IOC_READ, b'V', 0, 0x2000);
+    }
+
+    #[test]
+    fn encoding_decode_dir() {
+        // Neither the "none" bit nor any other direction bit is set.
+        assert_eq!(Encoding::LinuxAlternate.decode(0x00005601).dir, _IOC_NONE);
+        assert_eq!(Encoding::Bsd.decode(0x00005601).dir, _IOC_NONE);
+        // The "none" bit is set together with the read bit.
+        assert_eq!(Encoding::LinuxAlternate.decode(0x60045601).dir, _IOC_READ);
     }
 }
@@ -0,0 +1,204 @@
+/// Verifies at compile time that an [`Ioctl`][crate::Ioctl] binding matches its argument type.
+///
+/// `verify_binding!(IOCTL, Type)` expands to a `const` assertion that checks that:
+///
+/// - `IOCTL` takes a `*mut Type` or `*const Type` argument (otherwise, a type error is raised),
+/// - the argument size encoded in the request code is equal to `size_of::<Type>()`,
+/// - and that `IOCTL` takes a `*mut` pointer if the kernel writes data to userspace (ie. if the
+///   direction includes [`_IOC_READ`][crate::_IOC_READ]).
+///
+/// [`_IOR`][crate::_IOR], [`_IOW`][crate::_IOW], and [`_IOWR`][crate::_IOWR] always produce
+/// bindings that pass these checks.
+/// The macro is most useful for bindings that are put together by other means (for example, via
+/// [`_IOC`][crate::_IOC], [`Ioctl::from_raw`][crate::Ioctl::from_raw], or one of the casting
+/// methods), and to lock a binding to its `struct`, so that changing either of them will be caught
+/// by the compiler.
+///
+/// Note that this cannot check that `Type` has the correct *layout*, only that it has the expected
+/// size. Types used as `ioctl` arguments should generally be `#[repr(C)]`.
+///
+/// # Examples
+///
+/// ```
+/// use uoctl::*;
+///
+/// #[repr(C)]
+/// struct Capability {
+///     driver: [u8; 16],
+///     card: [u8; 32],
+///     bus_info: [u8; 32],
+///     version: u32,
+///     capabilities: u32,
+///     device_caps: u32,
+///     reserved: [u32; 3],
+/// }
+///
+/// const VIDIOC_QUERYCAP: Ioctl<*mut Capability> = _IOC(_IOC_READ, b'V', 0, 104);
+///
+/// verify_binding!(VIDIOC_QUERYCAP, Capability);
+/// ```
+///
+/// Passing an argument type of the wrong size fails to compile:
+///
+/// ```compile_fail
+/// use uoctl::*;
+///
+/// const VIDIOC_QUERYCAP: Ioctl<*mut [u8; 100]> = _IOC(_IOC_READ, b'V', 0, 104);
+///
+/// verify_binding!(VIDIOC_QUERYCAP, [u8; 100]);
+/// ```
+///
+/// So does an `ioctl` that the kernel writes to, but is declared with a `*const` argument:
+///
+/// ```compile_fail
+/// use uoctl::*;
+///
+/// const RNDGETENTCNT: Ioctl<*const i32> = _IOR::<i32>(b'R', 0x00).cast_const();
+///
+/// verify_binding!(RNDGETENTCNT, i32);
+/// ```
+///
+/// And a binding that uses a different argument type altogether:
+///
+/// ```compile_fail
+/// use uoctl::*;
+///
+/// const RNDGETENTCNT: Ioctl<*mut i32> = _IOR(b'R', 0x00);
+///
+/// verify_binding!(RNDGETENTCNT, u32);
+/// ```
+#[macro_export]
+macro_rules! verify_binding {
+    ($ioctl:expr, $ty:ty $(,)?) => {
+        const _: () = $crate::__private::verify_binding::<$ty, _>($ioctl);
+    };
+}
+
+/// Declares several [`Ioctl`][crate::Ioctl] constants at once.
+///
+/// Each entry has the form `VISIBILITY NAME = MACRO(GROUP, NUMBER[, TYPE]);`, where `MACRO` is one
+/// of [`_IO`][crate::_IO], [`_IOR`][crate::_IOR], [`_IOW`][crate::_IOW], or
+/// [`_IOWR`][crate::_IOWR], and expands to a `const` of the matching [`Ioctl`][crate::Ioctl] type:
+///
+/// | Entry                            | Type of the constant |
+/// |----------------------------------|----------------------|
+/// | `_IO(GROUP, NUMBER)`             | `Ioctl<NoArgs>`      |
+/// | `_IOR(GROUP, NUMBER, T)`         | `Ioctl<*mut T>`      |
+/// | `_IOW(GROUP, NUMBER, T)`         | `Ioctl<*const T>`    |
+/// | `_IOWR(GROUP, NUMBER, T)`        | `Ioctl<*mut T>`      |
+///
+/// An `_IOW` entry can be followed by `.with_direct_arg()`, which declares an `Ioctl<T>` (see
+/// [`Ioctl::with_direct_arg`][crate::Ioctl::with_direct_arg]).
+///
+/// `GROUP` may be a `char` or a [`u8`] (it is converted with `as u8`), so the `ioctl` type can be
+/// copied from the C header verbatim.
+/// Attributes (including doc comments) on an entry are applied to the constant.
+///
+/// # Example
+///
+/// From `linux/uinput.h`:
+///
+/// ```c
+/// #define UINPUT_IOCTL_BASE	'U'
+/// #define UI_DEV_CREATE		_IO(UINPUT_IOCTL_BASE, 1)
+/// #define UI_DEV_DESTROY		_IO(UINPUT_IOCTL_BASE, 2)
+/// ...
+/// #define UI_DEV_SETUP _IOW(UINPUT_IOCTL_BASE, 3, struct uinput_setup)
+/// ...
+/// #define UI_SET_EVBIT		_IOW(UINPUT_IOCTL_BASE, 100, int)
+/// ...
+/// #define UI_GET_VERSION		_IOR(UINPUT_IOCTL_BASE, 45, unsigned int)
+/// ```
+///
+/// ```
+/// use std::ffi::{c_int, c_uint};
+/// use libc::uinput_setup;
+/// use uoctl::*;
+///
+/// const UINPUT_IOCTL_BASE: char = 'U';
+///
+/// ioctl! {
+///     /// Creates the device.
+///     pub UI_DEV_CREATE = _IO(UINPUT_IOCTL_BASE, 1);
+///     pub UI_DEV_DESTROY = _IO(UINPUT_IOCTL_BASE, 2);
+///     pub UI_DEV_SETUP = _IOW(UINPUT_IOCTL_BASE, 3, uinput_setup);
+///     pub UI_SET_EVBIT = _IOW(UINPUT_IOCTL_BASE, 100, c_int).with_direct_arg();
+///     UI_GET_VERSION = _IOR(UINPUT_IOCTL_BASE, 45, c_uint);
+/// }
+///
+/// let _: Ioctl<NoArgs> = UI_DEV_CREATE;
+/// let _: Ioctl<*const uinput_setup> = UI_DEV_SETUP;
+/// let _: Ioctl<c_int> = UI_SET_EVBIT;
+/// let _: Ioctl<*mut c_uint> = UI_GET_VERSION;
+///
+/// assert_eq!(UI_DEV_SETUP, _IOW(b'U', 3));
+/// assert_eq!(UI_SET_EVBIT, _IOW(b'U', 100).with_direct_arg());
+/// ```
+#[macro_export]
+macro_rules! ioctl {
+    () => {};
+    (
+        $(#[$attr:meta])*
+        $vis:vis $name:ident = _IO($group:expr, $nr:expr $(,)?);
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        $vis const $name: $crate::Ioctl<$crate::NoArgs> = $crate::_IO(($group) as u8, $nr);
+        $crate::ioctl! { $($rest)* }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis $name:ident = _IOR($group:expr, $nr:expr, $ty:ty $(,)?);
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        $vis const $name: $crate::Ioctl<*mut $ty> = $crate::_IOR(($group) as u8, $nr);
+        $crate::ioctl! { $($rest)* }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis $name:ident = _IOW($group:expr, $nr:expr, $ty:ty $(,)?).with_direct_arg();
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        $vis const $name: $crate::Ioctl<$ty> =
+            $crate::_IOW::<$ty>(($group) as u8, $nr).with_direct_arg();
+        $crate::ioctl! { $($rest)* }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis $name:ident = _IOW($group:expr, $nr:expr, $ty:ty $(,)?);
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        $vis const $name: $crate::Ioctl<*const $ty> = $crate::_IOW(($group) as u8, $nr);
+        $crate::ioctl! { $($rest)* }
+    };
+    (
+        $(#[$attr:meta])*
+        $vis:vis $name:ident = _IOWR($group:expr, $nr:expr, $ty:ty $(,)?);
+        $($rest:tt)*
+    ) => {
+        $(#[$attr])*
+        $vis const $name: $crate::Ioctl<*mut $ty> = $crate::_IOWR(($group) as u8, $nr);
+        $crate::ioctl! { $($rest)* }
+    };
+}
+
+#[doc(hidden)]
+pub mod __private {
+    use crate::{Ioctl, IoctlPointer, sealed::Sealed};
+
+    pub const fn verify_binding<T, P: IoctlPointer<Pointee = T>>(ioctl: Ioctl<P>) {
+        let size = ioctl.size();
+        assert!(
+            size == size_of::<T>(),
+            "the size encoded in the `ioctl` request code does not match the argument type",
+        );
+
+        assert!(
+            <P as Sealed>::MUT || !ioctl.dir().is_read(),
+            "the kernel writes to the argument of this `ioctl`, so it must be a `*mut` pointer",
+        );
+    }
+}
@@ -0,0 +1,279 @@
+//! Memory-mapped driver buffers.
+
+use std::{
+    io,
+    ops::{Deref, DerefMut},
+    os::fd::AsRawFd,
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// A region of a device file that has been mapped into memory with `mmap(2)`.
+///
+/// Buffer-based driver APIs (like V4L2's `V4L2_MEMORY_MMAP` streaming I/O, or DRM dumb buffers)
+/// typically use an `ioctl` to allocate device buffers and obtain an offset and length for each of
+/// them.
+/// The buffers are then mapped into memory by passing that offset and length to `mmap(2)`, before
+/// more `ioctl`s are used to hand them back and forth between userspace and the driver.
+///
+/// [`MappedBuffer`] performs the `mmap(2)` step, exposes the mapped region as a `[u8]`, and unmaps
+/// it again when dropped.
+///
+/// The region is mapped with `PROT_READ | PROT_WRITE` and `MAP_SHARED`.
+///
+/// # Example
+///
+/// Mapping the first V4L2 capture buffer.
+///
+/// From `linux/videodev2.h`:
+///
+/// ```c
+/// struct v4l2_requestbuffers {
+/// 	__u32			count;
+/// 	__u32			type;		/* enum v4l2_buf_type */
+/// 	__u32			memory;		/* enum v4l2_memory */
+/// 	__u32			capabilities;
+/// 	__u8			flags;
+/// 	__u8			reserved[3];
+/// };
+/// // ...
+/// struct v4l2_buffer {
+/// 	__u32			index;
+/// 	__u32			type;
+/// 	__u32			bytesused;
+/// 	__u32			flags;
+/// 	__u32			field;
+/// 	struct timeval		timestamp;
+/// 	struct v4l2_timecode	timecode;
+/// 	__u32			sequence;
+///
+/// 	/* memory location */
+/// 	__u32			memory;
+/// 	union {
+/// 		__u32           offset;
+/// 		unsigned long   userptr;
+/// 		struct v4l2_plane *planes;
+/// 		__s32		fd;
+/// 	} m;
+/// 	__u32			length;
+/// 	__u32			reserved2;
+/// 	union {
+/// 		__s32		request_fd;
+/// 		__u32		reserved;
+/// 	};
+/// };
+/// // ...
+/// #define VIDIOC_REQBUFS		_IOWR('V',  8, struct v4l2_requestbuffers)
+/// #define VIDIOC_QUERYBUF		_IOWR('V',  9, struct v4l2_buffer)
+/// ```
+///
+/// ```no_run
+/// use std::{ffi::{c_ulong, c_void}, fs::File, mem};
+/// use uoctl::*;
+///
+/// #[repr(C)]
+/// struct RequestBuffers {
+///     count: u32,
+///     ty: u32,
+///     memory: u32,
+///     capabilities: u32,
+///     flags: u8,
+///     reserved: [u8; 3],
+/// }
+///
+/// #[repr(C)]
+/// union BufferLocation {
+///     offset: u32,
+///     userptr: c_ulong,
+///     planes: *mut c_void,
+///     fd: i32,
+/// }
+///
+/// #[repr(C)]
+/// struct Buffer {
+///     index: u32,
+///     ty: u32,
+///     bytesused: u32,
+///     flags: u32,
+///     field: u32,
+///     timestamp: libc::timeval,
+///     timecode: [u32; 4],
+///     sequence: u32,
+///     memory: u32,
+///     m: BufferLocation,
+///     length: u32,
+///     reserved2: u32,
+///     request_fd: i32,
+/// }
+///
+/// const VIDIOC_REQBUFS: Ioctl<*mut RequestBuffers> = _IOWR(b'V', 8);
+/// const VIDIOC_QUERYBUF: Ioctl<*mut Buffer> = _IOWR(b'V', 9);
+///
+/// const V4L2_BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+/// const V4L2_MEMORY_MMAP: u32 = 1;
+///
+/// let file = File::options().read(true).write(true).open("/dev/video0")?;
+///
+/// let mut req: RequestBuffers = unsafe { mem::zeroed() };
+/// req.count = 1;
+/// req.ty = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+/// req.memory = V4L2_MEMORY_MMAP;
+/// unsafe { VIDIOC_REQBUFS.ioctl(&file, &mut req)? };
+///
+/// let mut buf: Buffer = unsafe { mem::zeroed() };
+/// buf.index = 0;
+/// buf.ty = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+/// buf.memory = V4L2_MEMORY_MMAP;
+/// unsafe { VIDIOC_QUERYBUF.ioctl(&file, &mut buf)? };
+///
+/// let mapped = unsafe {
+///     MappedBuffer::new(&file, buf.m.offset.into(), buf.length as usize)?
+/// };
+/// assert_eq!(mapped.len(), buf.length as usize);
+/// # std::io::Result::Ok(())
+/// ```
+pub struct MappedBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: `MappedBuffer` owns its mapping exclusively, just like a `Box<[u8]>` owns its allocation.
+unsafe impl Send for MappedBuffer {}
+unsafe impl Sync for MappedBuffer {}
+
+impl MappedBuffer {
+    /// Maps `len` bytes of `fd`, starting at `offset`, into memory.
+    ///
+    /// `offset` and `len` are typically obtained from a prior `ioctl` that allocated or queried
+    /// the buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `offset` cannot be represented as an `off_t`, or if `mmap(2)` fails.
+    /// In particular, `mmap(2)` will fail with `EINVAL` if `len` is 0, and many drivers will
+    /// also reject `offset`s and `len`gths that don't match a buffer they've allocated.
+    ///
+    /// # Safety
+    ///
+    /// [`MappedBuffer`] hands out `&mut [u8]` references to the mapped memory, so the caller has
+    /// to ensure that the memory is not modified through any other means while such a reference
+    /// exists.
+    /// This includes other mappings of the same region, as well as the device or driver writing
+    /// to the buffer while it is owned by userspace.
+    pub unsafe fn new(fd: &impl AsRawFd, offset: u64, len: usize) -> io::Result<Self> {
+        let offset = libc::off_t::try_from(offset).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("mapping offset {offset:#x} is out of range"),
+            )
+        })?;
+
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                offset,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            // `mmap` never returns a null pointer when it succeeds (since we didn't pass
+            // `MAP_FIXED`).
+            ptr: NonNull::new(ptr.cast()).unwrap(),
+            len,
+        })
+    }
+
+    /// Returns a raw pointer to the start of the mapped region.
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
+    }
+
+    /// Returns a raw mutable pointer to the start of the mapped region.
+    #[inline]
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+}
+
+impl Deref for MappedBuffer {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for MappedBuffer {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for MappedBuffer {
+    fn drop(&mut self) {
+        // `munmap` can only fail if the arguments are invalid, which would be a bug in this type.
+        let res = unsafe { libc::munmap(self.ptr.as_ptr().cast(), self.len) };
+        debug_assert_eq!(res, 0, "munmap failed: {}", io::Error::last_os_error());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    #[test]
+    fn map_dev_zero() {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .open("/dev/zero")
+            .unwrap();
+
+        let mut mapped = unsafe { MappedBuffer::new(&file, 0, 4096).unwrap() };
+        assert_eq!(mapped.len(), 4096);
+        assert!(mapped.iter().all(|&b| b == 0));
+
+        mapped[0] = 0xAA;
+        mapped[4095] = 0x55;
+        assert_eq!(mapped[0], 0xAA);
+        assert_eq!(mapped[4095], 0x55);
+    }
+
+    #[test]
+    fn zero_length() {
+        let file = File::options()
+            .read(true)
+            .write(true)
+            .open("/dev/zero")
+            .unwrap();
+
+        let err = unsafe { MappedBuffer::new(&file, 0, 0).err().unwrap() };
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+    }
+
+    #[test]
+    fn offset_out_of_range() {
+        let file = File::open("/dev/zero").unwrap();
+
+        let err = unsafe { MappedBuffer::new(&file, u64::MAX, 4096).err().unwrap() };
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn invalid_fd() {
+        let err = unsafe { MappedBuffer::new(&-1, 0, 4096).err().unwrap() };
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+    }
+}
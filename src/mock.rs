@@ -0,0 +1,278 @@
+//! Mock file descriptors for testing `ioctl` wrappers without a device.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::c_int,
+    fmt, io,
+    marker::PhantomData,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
+    rc::Rc,
+};
+
+type Handler = Rc<RefCell<dyn FnMut(u32, usize) -> io::Result<c_int>>>;
+
+thread_local! {
+    /// The handlers of the [`MockFd`]s created on this thread.
+    ///
+    /// This is thread-local, so that `ioctl`s on real file descriptors never contend on a lock,
+    /// and only pay for checking whether this thread has created any [`MockFd`]s.
+    static REGISTRY: RefCell<Option<HashMap<RawFd, Handler>>> = const { RefCell::new(None) };
+}
+
+/// A file descriptor whose `ioctl`s are handled by a closure instead of a driver.
+///
+/// Every `ioctl` performed on a [`MockFd`] via [`Ioctl::ioctl`] (or any of the other methods that
+/// perform `ioctl`s through libc) calls the handler with the request code and the argument, and
+/// returns the handler's result instead of calling `ioctl(2)`.
+/// This allows testing the logic of `ioctl` wrappers (like how they populate argument `struct`s,
+/// and how they handle errors) without access to the real device.
+///
+/// The argument is passed to the handler as a [`usize`]: pointer arguments are passed as their
+/// address, and integer arguments are zero-extended.
+/// Small `struct`s passed by value (see [`Ioctl::with_direct_arg`]) end up in the low-order bytes
+/// of the [`usize`].
+/// Larger arguments can't be passed to the handler, so `ioctl`s on a [`MockFd`] that take an
+/// argument larger than a [`usize`] by value fail with `EINVAL` without calling it (the kernel
+/// wouldn't receive them intact either).
+/// A handler for an `ioctl` that takes a pointer can cast it back to the pointer type and access
+/// the argument through it, just like the driver would.
+///
+/// [`MockFd`] is backed by an open file descriptor referring to `/dev/null`, so it can also be
+/// passed to other system calls like `poll(2)` or `fcntl(2)`.
+/// `Ioctl::ioctl_syscall` bypasses the mock and always performs the real system call.
+///
+/// `ioctl`s are only intercepted on the thread that created the [`MockFd`], which is why it
+/// can't be sent to other threads.
+/// The handler must not perform `ioctl`s on the same [`MockFd`], since that would panic.
+///
+/// This type requires the `mock` feature.
+///
+/// [`Ioctl::ioctl`]: crate::Ioctl::ioctl
+/// [`Ioctl::with_direct_arg`]: crate::Ioctl::with_direct_arg
+///
+/// # Example
+///
+/// Mocking V4L2's `VIDIOC_QUERYCAP`:
+///
+/// ```
+/// use std::io;
+/// use uoctl::*;
+///
+/// #[repr(C)]
+/// #[derive(Default)]
+/// struct Capability {
+///     driver: [u8; 16],
+///     card: [u8; 32],
+///     bus_info: [u8; 32],
+///     version: u32,
+///     capabilities: u32,
+///     device_caps: u32,
+///     reserved: [u32; 3],
+/// }
+///
+/// const VIDIOC_QUERYCAP: Ioctl<*mut Capability> = _IOR(b'V', 0);
+///
+/// let fd = MockFd::new(|request, arg| {
+///     if request != VIDIOC_QUERYCAP.request() {
+///         return Err(io::Error::from_raw_os_error(libc::ENOTTY));
+///     }
+///     let cap = unsafe { &mut *(arg as *mut Capability) };
+///     cap.driver[..4].copy_from_slice(b"mock");
+///     cap.capabilities = 0x1;
+///     Ok(0)
+/// })?;
+///
+/// let mut cap = Capability::default();
+/// unsafe { VIDIOC_QUERYCAP.ioctl(&fd, &mut cap)? };
+/// assert_eq!(&cap.driver[..4], b"mock");
+/// assert_eq!(cap.capabilities, 0x1);
+/// # std::io::Result::Ok(())
+/// ```
+pub struct MockFd {
+    fd: OwnedFd,
+    /// The handler is registered in a thread-local, so this must not be `Send` or `Sync`.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl MockFd {
+    /// Creates a [`MockFd`] whose `ioctl`s are handled by `handler`.
+    ///
+    /// `handler` is called with the request code and the argument of each `ioctl`, and returns the
+    /// result of the `ioctl`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/dev/null` can't be opened.
+    pub fn new(handler: impl FnMut(u32, usize) -> io::Result<c_int> + 'static) -> io::Result<Self> {
+        let fd = unsafe { libc::open(c"/dev/null".as_ptr(), libc::O_RDWR | libc::O_CLOEXEC) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        REGISTRY.with_borrow_mut(|registry| {
+            registry
+                .get_or_insert_with(HashMap::new)
+                .insert(fd.as_raw_fd(), Rc::new(RefCell::new(handler)));
+        });
+        Ok(Self {
+            fd,
+            _not_send: PhantomData,
+        })
+    }
+}
+
+impl Drop for MockFd {
+    fn drop(&mut self) {
+        // Unregister before the file descriptor is closed, so that a file descriptor that reuses the
+        // number is never intercepted.
+        REGISTRY.with_borrow_mut(|registry| {
+            if let Some(map) = registry {
+                map.remove(&self.fd.as_raw_fd());
+            }
+        });
+    }
+}
+
+impl AsRawFd for MockFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for MockFd {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+impl fmt::Debug for MockFd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockFd").field("fd", &self.fd).finish()
+    }
+}
+
+/// Calls the handler of the [`MockFd`] `fd`, if this thread has created one.
+pub(crate) fn intercept<T>(fd: RawFd, request: u32, arg: &T) -> Option<io::Result<c_int>> {
+    // Clone the handler, so that the registry isn't borrowed while it runs.
+    let handler = REGISTRY.with_borrow(|registry| registry.as_ref()?.get(&fd).cloned())?;
+    let Some(arg) = crate::backend::try_arg_bits(arg) else {
+        return Some(Err(io::Error::from_raw_os_error(libc::EINVAL)));
+    };
+    let mut handler = handler.borrow_mut();
+    Some(handler(request, arg))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::{_IO, _IOR, _IOW, Ioctl};
+
+    use super::*;
+
+    #[test]
+    fn intercepts_ioctls() {
+        const GET: Ioctl<*mut u32> = _IOR(b'x', 1);
+        const SET: Ioctl<c_int> = _IOW::<c_int>(b'x', 2).with_direct_arg();
+        const RESET: Ioctl = _IO(b'x', 3);
+
+        let (tx, rx) = mpsc::channel();
+        let fd = MockFd::new(move |request, arg| {
+            tx.send(request).unwrap();
+            match request {
+                _ if request == GET.request() => {
+                    unsafe { *(arg as *mut u32) = 42 };
+                    Ok(0)
+                }
+                _ if request == SET.request() => Ok(arg as c_int + 1),
+                _ => Err(io::Error::from_raw_os_error(libc::ENOTTY)),
+            }
+        })
+        .unwrap();
+
+        let mut value = 0;
+        unsafe {
+            assert_eq!(GET.ioctl(&fd, &mut value).unwrap(), 0);
+            assert_eq!(SET.ioctl(&fd, 7).unwrap(), 8);
+            let err = RESET.ioctl(&fd).unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::ENOTTY));
+        }
+        assert_eq!(value, 42);
+        assert_eq!(
+            rx.try_iter().collect::<Vec<_>>(),
+            [GET.request(), SET.request(), RESET.request()],
+        );
+    }
+
+    #[test]
+    fn by_value_struct() {
+        #[derive(Clone, Copy)]
+        #[repr(C)]
+        struct Pair {
+            a: u16,
+            b: u8,
+        }
+
+        const SET_PAIR: Ioctl<Pair> = _IOW::<Pair>(b'x', 1).with_direct_arg();
+
+        let fd = MockFd::new(|_, arg| {
+            // The `struct` is stored in the low-order bytes of `arg`.
+            let bytes = arg.to_ne_bytes();
+            let offset = if cfg!(target_endian = "big") {
+                bytes.len() - size_of::<Pair>()
+            } else {
+                0
+            };
+            let pair = unsafe { bytes[offset..].as_ptr().cast::<Pair>().read_unaligned() };
+            Ok(c_int::from(pair.a) + c_int::from(pair.b))
+        })
+        .unwrap();
+
+        let res = unsafe { SET_PAIR.ioctl(&fd, Pair { a: 1000, b: 24 }).unwrap() };
+        assert_eq!(res, 1024);
+    }
+
+    #[test]
+    fn oversized_arg() {
+        #[derive(Clone, Copy)]
+        #[repr(C)]
+        struct Words([usize; 2]);
+
+        const SET_WORDS: Ioctl<Words> = _IOW::<Words>(b'x', 1).with_direct_arg();
+
+        let fd = MockFd::new(|_, _| unreachable!()).unwrap();
+        let err = unsafe { SET_WORDS.ioctl(&fd, Words([1, 2])).unwrap_err() };
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+    }
+
+    #[test]
+    fn other_threads_unaffected() {
+        let fd = MockFd::new(|_, _| Ok(1234)).unwrap();
+        let raw = fd.as_raw_fd();
+        assert!(intercept(raw, 0, &0u32).is_some());
+        std::thread::spawn(move || assert!(intercept(raw, 0, &0u32).is_none()))
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn real_fds_unaffected() {
+        use crate::tests::FIONREAD;
+
+        let _mock = MockFd::new(|_, _| Ok(1234)).unwrap();
+        let null = std::fs::File::open("/dev/null").unwrap();
+        let mut bytes = 0;
+        let res = unsafe { FIONREAD.ioctl(&null, &mut bytes) };
+        assert_ne!(res.ok(), Some(1234));
+    }
+
+    #[test]
+    fn unregistered_on_drop() {
+        let fd = MockFd::new(|_, _| Ok(0)).unwrap();
+        let raw = fd.as_raw_fd();
+        drop(fd);
+        assert!(intercept(raw, 0, &0u32).is_none());
+    }
+}
@@ -0,0 +1,146 @@
+//! `ioctl`s for configuring network interfaces.
+//!
+//! These `ioctl`s are performed on a socket, but act on the network interface named in the
+//! `ifreq` argument rather than on the socket itself.
+//! Any socket works, so programs conventionally open a throwaway `AF_INET`/`SOCK_DGRAM` socket
+//! just for this purpose, which [`with_dgram_socket`] takes care of.
+//! Changing the configuration of an interface requires `CAP_NET_ADMIN`.
+//!
+//! From `linux/sockios.h`:
+//!
+//! ```c
+//! #define SIOCGIFADDR	0x8915		/* get PA address		*/
+//! ...
+//! #define SIOCGIFFLAGS	0x8913		/* get flags			*/
+//! #define SIOCSIFFLAGS	0x8914		/* set flags			*/
+//! ...
+//! #define SIOCGIFMTU	0x8921		/* get MTU size			*/
+//! #define SIOCSIFMTU	0x8922		/* set MTU size			*/
+//! ...
+//! #define SIOCGIFHWADDR	0x8927		/* Get hardware address		*/
+//! ...
+//! #define SIOCGIFINDEX	0x8933		/* name -> if_index mapping	*/
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! use uoctl::net::*;
+//!
+//! let mut req = ifreq_with_name("lo")?;
+//! with_dgram_socket(|sock| unsafe { SIOCGIFFLAGS.ioctl(&sock, &mut req) })?;
+//!
+//! let flags = unsafe { req.ifr_ifru.ifru_flags } as i32;
+//! assert_ne!(flags & libc::IFF_LOOPBACK, 0);
+//! # std::io::Result::Ok(())
+//! ```
+//!
+//! Sockets from the standard library can be used directly, too:
+//!
+//! ```no_run
+//! use std::net::UdpSocket;
+//! use uoctl::net::*;
+//!
+//! let sock = UdpSocket::bind("0.0.0.0:0")?;
+//! let mut req = ifreq_with_name("eth0")?;
+//! unsafe { SIOCGIFMTU.ioctl(&sock, &mut req)? };
+//! println!("MTU: {}", unsafe { req.ifr_ifru.ifru_mtu });
+//! # std::io::Result::Ok(())
+//! ```
+
+use std::{
+    ffi::c_char,
+    io, mem,
+    os::fd::{AsFd, BorrowedFd, FromRawFd, OwnedFd},
+};
+
+use crate::Ioctl;
+
+/// Reads the address of the interface into `ifr_ifru.ifru_addr`.
+pub const SIOCGIFADDR: Ioctl<*mut libc::ifreq> = Ioctl::from_raw(0x8915);
+/// Reads the flags of the interface (`IFF_*`) into `ifr_ifru.ifru_flags`.
+pub const SIOCGIFFLAGS: Ioctl<*mut libc::ifreq> = Ioctl::from_raw(0x8913);
+/// Sets the flags of the interface (`IFF_*`) to `ifr_ifru.ifru_flags`.
+pub const SIOCSIFFLAGS: Ioctl<*const libc::ifreq> = Ioctl::from_raw(0x8914);
+/// Reads the MTU of the interface into `ifr_ifru.ifru_mtu`.
+pub const SIOCGIFMTU: Ioctl<*mut libc::ifreq> = Ioctl::from_raw(0x8921);
+/// Sets the MTU of the interface to `ifr_ifru.ifru_mtu`.
+pub const SIOCSIFMTU: Ioctl<*const libc::ifreq> = Ioctl::from_raw(0x8922);
+/// Reads the hardware address of the interface into `ifr_ifru.ifru_hwaddr`.
+pub const SIOCGIFHWADDR: Ioctl<*mut libc::ifreq> = Ioctl::from_raw(0x8927);
+/// Reads the index of the interface into `ifr_ifru.ifru_ifindex`.
+pub const SIOCGIFINDEX: Ioctl<*mut libc::ifreq> = Ioctl::from_raw(0x8933);
+
+/// Creates a zeroed `ifreq` that refers to the interface `name`.
+///
+/// # Errors
+///
+/// Returns an [`io::ErrorKind::InvalidInput`] error if `name` contains a NUL byte, or doesn't fit
+/// in `ifr_name` (including the terminating NUL byte).
+pub fn ifreq_with_name(name: &str) -> io::Result<libc::ifreq> {
+    let mut req: libc::ifreq = unsafe { mem::zeroed() };
+    if name.len() >= req.ifr_name.len() || name.contains('\0') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid network interface name `{name}`"),
+        ));
+    }
+    for (dest, &b) in req.ifr_name.iter_mut().zip(name.as_bytes()) {
+        *dest = b as c_char;
+    }
+    Ok(req)
+}
+
+/// Opens a temporary `AF_INET`/`SOCK_DGRAM` socket, and passes it to `f`.
+///
+/// The socket is closed once `f` returns.
+///
+/// # Errors
+///
+/// Returns an error if the socket can't be created, or the error returned by `f`.
+pub fn with_dgram_socket<R>(f: impl FnOnce(BorrowedFd<'_>) -> io::Result<R>) -> io::Result<R> {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let sock = unsafe { OwnedFd::from_raw_fd(fd) };
+    f(sock.as_fd())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use super::*;
+
+    #[test]
+    fn interface_name() {
+        let req = ifreq_with_name("lo").unwrap();
+        assert_eq!(&req.ifr_name[..3], &[b'l' as c_char, b'o' as c_char, 0]);
+
+        assert!(ifreq_with_name("0123456789abcdef").is_err());
+        assert!(ifreq_with_name("lo\0").is_err());
+    }
+
+    #[test]
+    fn loopback_flags() {
+        let mut req = ifreq_with_name("lo").unwrap();
+        with_dgram_socket(|sock| unsafe { SIOCGIFFLAGS.ioctl(&sock, &mut req) }).unwrap();
+        let flags = unsafe { req.ifr_ifru.ifru_flags } as i32;
+        assert_ne!(flags & libc::IFF_LOOPBACK, 0);
+
+        let sock = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut req = ifreq_with_name("lo").unwrap();
+        unsafe { SIOCGIFINDEX.ioctl(&sock, &mut req).unwrap() };
+        assert_ne!(unsafe { req.ifr_ifru.ifru_ifindex }, 0);
+    }
+
+    #[test]
+    fn closure_error() {
+        let err = with_dgram_socket(|_| -> io::Result<()> {
+            Err(io::Error::from_raw_os_error(libc::ENODEV))
+        })
+        .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENODEV));
+    }
+}
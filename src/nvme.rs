@@ -0,0 +1,227 @@
+//! `ioctl`s for NVMe controllers (`/dev/nvme*`) and namespaces (`/dev/nvme*n*`).
+//!
+//! [`NVME_IOCTL_ADMIN_CMD`] and [`NVME_IOCTL_IO_CMD`] pass arbitrary commands through to the
+//! device, which generally requires `CAP_SYS_ADMIN`.
+//! Commands that transfer data reference a userspace buffer via the `addr` and `data_len` fields
+//! of [`nvme_passthru_cmd`], which can be set with [`nvme_passthru_cmd::set_data`].
+//!
+//! From `linux/nvme_ioctl.h`:
+//!
+//! ```c
+//! #define NVME_IOCTL_ID		_IO('N', 0x40)
+//! #define NVME_IOCTL_ADMIN_CMD	_IOWR('N', 0x41, struct nvme_admin_cmd)
+//! #define NVME_IOCTL_SUBMIT_IO	_IOW('N', 0x42, struct nvme_user_io)
+//! #define NVME_IOCTL_IO_CMD	_IOWR('N', 0x43, struct nvme_passthru_cmd)
+//! #define NVME_IOCTL_RESET	_IO('N', 0x44)
+//! #define NVME_IOCTL_SUBSYS_RESET	_IO('N', 0x45)
+//! #define NVME_IOCTL_RESCAN	_IO('N', 0x46)
+//! ```
+//!
+//! # Example
+//!
+//! Reading the model number from the *Identify Controller* data structure:
+//!
+#![cfg_attr(feature = "std", doc = "```no_run")]
+#![cfg_attr(not(feature = "std"), doc = "```ignore")]
+//! use std::fs::File;
+//! use uoctl::nvme::*;
+//!
+//! let ctrl = File::open("/dev/nvme0")?;
+//!
+//! let mut data = vec![0u8; 4096];
+//! let mut cmd = nvme_passthru_cmd {
+//!     opcode: 0x06, // Identify
+//!     cdw10: 1,     // CNS 01h: Identify Controller
+//!     ..Default::default()
+//! };
+//! cmd.set_data(&mut data);
+//! unsafe { NVME_IOCTL_ADMIN_CMD.ioctl(&ctrl, &mut cmd)? };
+//!
+//! println!("model: {}", String::from_utf8_lossy(&data[24..64]).trim_end());
+//! # std::io::Result::Ok(())
+//! ```
+
+#![allow(non_camel_case_types)]
+
+use crate::{_IO, _IOW, _IOWR, Ioctl, NoArgs};
+
+/// Returns the namespace ID of a namespace device.
+pub const NVME_IOCTL_ID: Ioctl<NoArgs> = _IO(b'N', 0x40);
+/// Submits an admin command to the controller.
+///
+/// On success, returns the status field of the completion (0 if the command succeeded), and the
+/// kernel writes the command-specific result to [`nvme_passthru_cmd::result`].
+pub const NVME_IOCTL_ADMIN_CMD: Ioctl<*mut nvme_passthru_cmd> = _IOWR(b'N', 0x41);
+/// Submits a read, write, or compare command to a namespace.
+pub const NVME_IOCTL_SUBMIT_IO: Ioctl<*const nvme_user_io> = _IOW(b'N', 0x42);
+/// Submits an arbitrary I/O command to a namespace.
+///
+/// Returns the status just like [`NVME_IOCTL_ADMIN_CMD`].
+pub const NVME_IOCTL_IO_CMD: Ioctl<*mut nvme_passthru_cmd> = _IOWR(b'N', 0x43);
+/// Resets the controller.
+pub const NVME_IOCTL_RESET: Ioctl<NoArgs> = _IO(b'N', 0x44);
+/// Resets the NVM subsystem.
+pub const NVME_IOCTL_SUBSYS_RESET: Ioctl<NoArgs> = _IO(b'N', 0x45);
+/// Rescans the namespaces of the controller.
+pub const NVME_IOCTL_RESCAN: Ioctl<NoArgs> = _IO(b'N', 0x46);
+
+/// A read, write, or compare command, submitted with [`NVME_IOCTL_SUBMIT_IO`].
+///
+/// ```c
+/// struct nvme_user_io {
+/// 	__u8	opcode;
+/// 	__u8	flags;
+/// 	__u16	control;
+/// 	__u16	nblocks;
+/// 	__u16	rsvd;
+/// 	__u64	metadata;
+/// 	__u64	addr;
+/// 	__u64	slba;
+/// 	__u32	dsmgmt;
+/// 	__u32	reftag;
+/// 	__u16	apptag;
+/// 	__u16	appmask;
+/// };
+/// ```
+///
+/// `metadata` and `addr` are userspace pointers, stored as [`u64`]s.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct nvme_user_io {
+    pub opcode: u8,
+    pub flags: u8,
+    pub control: u16,
+    pub nblocks: u16,
+    pub rsvd: u16,
+    pub metadata: u64,
+    pub addr: u64,
+    pub slba: u64,
+    pub dsmgmt: u32,
+    pub reftag: u32,
+    pub apptag: u16,
+    pub appmask: u16,
+}
+
+/// A command passed through to the device with [`NVME_IOCTL_ADMIN_CMD`] or [`NVME_IOCTL_IO_CMD`].
+///
+/// ```c
+/// struct nvme_passthru_cmd {
+/// 	__u8	opcode;
+/// 	__u8	flags;
+/// 	__u16	rsvd1;
+/// 	__u32	nsid;
+/// 	__u32	cdw2;
+/// 	__u32	cdw3;
+/// 	__u64	metadata;
+/// 	__u64	addr;
+/// 	__u32	metadata_len;
+/// 	__u32	data_len;
+/// 	__u32	cdw10;
+/// 	__u32	cdw11;
+/// 	__u32	cdw12;
+/// 	__u32	cdw13;
+/// 	__u32	cdw14;
+/// 	__u32	cdw15;
+/// 	__u32	timeout_ms;
+/// 	__u32	result;
+/// };
+/// ```
+///
+/// `metadata` and `addr` are userspace pointers, stored as [`u64`]s.
+/// The C header also defines `struct nvme_admin_cmd` as an alias of this `struct`.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct nvme_passthru_cmd {
+    pub opcode: u8,
+    pub flags: u8,
+    pub rsvd1: u16,
+    pub nsid: u32,
+    pub cdw2: u32,
+    pub cdw3: u32,
+    pub metadata: u64,
+    pub addr: u64,
+    pub metadata_len: u32,
+    pub data_len: u32,
+    pub cdw10: u32,
+    pub cdw11: u32,
+    pub cdw12: u32,
+    pub cdw13: u32,
+    pub cdw14: u32,
+    pub cdw15: u32,
+    pub timeout_ms: u32,
+    pub result: u32,
+}
+
+impl nvme_passthru_cmd {
+    /// Sets `addr` and `data_len` to refer to `buf`.
+    ///
+    /// The command only stores the address of `buf`, so `buf` has to stay alive (and must not be
+    /// accessed) until the `ioctl` using this command has completed.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the length of `buf` doesn't fit in `data_len`.
+    pub fn set_data(&mut self, buf: &mut [u8]) {
+        self.data_len = buf
+            .len()
+            .try_into()
+            .expect("buffer too large for `data_len`");
+        self.addr = buf.as_mut_ptr() as usize as u64;
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+
+    #[test]
+    fn layout() {
+        assert_eq!(size_of::<nvme_user_io>(), 48);
+        assert_eq!(size_of::<nvme_passthru_cmd>(), 72);
+    }
+
+    #[test]
+    fn codes() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(NVME_IOCTL_ID.request(), 0x4e40);
+            assert_eq!(NVME_IOCTL_ADMIN_CMD.request(), 0xc0484e41);
+            assert_eq!(NVME_IOCTL_SUBMIT_IO.request(), 0x40304e42);
+            assert_eq!(NVME_IOCTL_IO_CMD.request(), 0xc0484e43);
+        }
+    }
+
+    #[test]
+    fn set_data() {
+        let mut buf = [0u8; 512];
+        let mut cmd = nvme_passthru_cmd::default();
+        cmd.set_data(&mut buf);
+        assert_eq!(cmd.data_len, 512);
+        assert_eq!(cmd.addr, buf.as_ptr() as usize as u64);
+    }
+
+    #[test]
+    fn identify_controller() {
+        // Requires an NVMe drive and `CAP_SYS_ADMIN`.
+        let Ok(ctrl) = File::open("/dev/nvme0") else {
+            return;
+        };
+
+        let mut data = vec![0u8; 4096];
+        let mut cmd = nvme_passthru_cmd {
+            opcode: 0x06,
+            cdw10: 1,
+            ..Default::default()
+        };
+        cmd.set_data(&mut data);
+        match unsafe { NVME_IOCTL_ADMIN_CMD.ioctl(&ctrl, &mut cmd) } {
+            Ok(status) => assert_eq!(status, 0),
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EACCES | libc::EPERM)) => return,
+            Err(e) => panic!("NVME_IOCTL_ADMIN_CMD failed: {e}"),
+        }
+        // The PCI vendor ID is never 0.
+        assert_ne!(u16::from_le_bytes([data[0], data[1]]), 0);
+    }
+}
@@ -0,0 +1,203 @@
+//! `ioctl`s for process file descriptors ("pidfds").
+//!
+//! Most operations on pidfds are performed with dedicated system calls, which are *not* `ioctl`s
+//! and are therefore out of scope for this library:
+//!
+//! - `pidfd_open(2)` creates a pidfd for a process.
+//! - `pidfd_send_signal(2)` sends a signal to the process.
+//! - `pidfd_getfd(2)` duplicates a file descriptor of the process.
+//! - `waitid(2)` with `P_PIDFD` waits for the process to exit.
+//! - `process_madvise(2)` and `process_mrelease(2)` act on the memory of the process.
+//! - `setns(2)` moves the calling thread into the namespaces of the process.
+//!
+//! Since Linux 6.11, pidfds also support a few `ioctl`s that return file descriptors for the
+//! namespaces of the process, and Linux 6.13 added [`PIDFD_GET_INFO`], which reads information
+//! about the process.
+//!
+//! From `linux/pidfd.h`:
+//!
+//! ```c
+//! #define PIDFS_IOCTL_MAGIC 0xFF
+//!
+//! #define PIDFD_GET_CGROUP_NAMESPACE            _IO(PIDFS_IOCTL_MAGIC, 1)
+//! #define PIDFD_GET_IPC_NAMESPACE               _IO(PIDFS_IOCTL_MAGIC, 2)
+//! #define PIDFD_GET_MNT_NAMESPACE               _IO(PIDFS_IOCTL_MAGIC, 3)
+//! #define PIDFD_GET_NET_NAMESPACE               _IO(PIDFS_IOCTL_MAGIC, 4)
+//! #define PIDFD_GET_PID_NAMESPACE               _IO(PIDFS_IOCTL_MAGIC, 5)
+//! #define PIDFD_GET_PID_FOR_CHILDREN_NAMESPACE  _IO(PIDFS_IOCTL_MAGIC, 6)
+//! #define PIDFD_GET_TIME_NAMESPACE              _IO(PIDFS_IOCTL_MAGIC, 7)
+//! #define PIDFD_GET_TIME_FOR_CHILDREN_NAMESPACE _IO(PIDFS_IOCTL_MAGIC, 8)
+//! #define PIDFD_GET_USER_NAMESPACE              _IO(PIDFS_IOCTL_MAGIC, 9)
+//! #define PIDFD_GET_UTS_NAMESPACE               _IO(PIDFS_IOCTL_MAGIC, 10)
+//! #define PIDFD_GET_INFO                        _IOWR(PIDFS_IOCTL_MAGIC, 11, struct pidfd_info)
+//! ```
+//!
+//! # Example
+//!
+#![cfg_attr(feature = "std", doc = "```no_run")]
+#![cfg_attr(not(feature = "std"), doc = "```ignore")]
+//! use std::os::fd::{FromRawFd, OwnedFd};
+//! use uoctl::pidfd::*;
+//!
+//! let pidfd = unsafe {
+//!     let fd = libc::syscall(libc::SYS_pidfd_open, libc::getpid(), 0);
+//!     if fd == -1 {
+//!         return Err(std::io::Error::last_os_error());
+//!     }
+//!     OwnedFd::from_raw_fd(fd as _)
+//! };
+//!
+//! let mut info = pidfd_info {
+//!     mask: PIDFD_INFO_CREDS,
+//!     ..Default::default()
+//! };
+//! unsafe { PIDFD_GET_INFO.ioctl(&pidfd, &mut info)? };
+//! println!("pid {} (parent {}), uid {}", info.pid, info.ppid, info.ruid);
+//!
+//! let netns = unsafe { PIDFD_GET_NET_NAMESPACE.ioctl_owned_fd(&pidfd)? };
+//! # std::io::Result::Ok(())
+//! ```
+
+#![allow(non_camel_case_types)]
+
+use crate::{_IO, _IOWR, Ioctl, NoArgs};
+
+/// The `ioctl` group used by pidfds.
+pub const PIDFS_IOCTL_MAGIC: u8 = 0xFF;
+
+/// Returns a new file descriptor referring to the cgroup namespace of the process.
+pub const PIDFD_GET_CGROUP_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 1);
+/// Returns a new file descriptor referring to the IPC namespace of the process.
+pub const PIDFD_GET_IPC_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 2);
+/// Returns a new file descriptor referring to the mount namespace of the process.
+pub const PIDFD_GET_MNT_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 3);
+/// Returns a new file descriptor referring to the network namespace of the process.
+pub const PIDFD_GET_NET_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 4);
+/// Returns a new file descriptor referring to the PID namespace of the process.
+pub const PIDFD_GET_PID_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 5);
+/// Returns a new file descriptor referring to the PID namespace of the children of the process.
+pub const PIDFD_GET_PID_FOR_CHILDREN_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 6);
+/// Returns a new file descriptor referring to the time namespace of the process.
+pub const PIDFD_GET_TIME_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 7);
+/// Returns a new file descriptor referring to the time namespace of the children of the process.
+pub const PIDFD_GET_TIME_FOR_CHILDREN_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 8);
+/// Returns a new file descriptor referring to the user namespace of the process.
+pub const PIDFD_GET_USER_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 9);
+/// Returns a new file descriptor referring to the UTS namespace of the process.
+pub const PIDFD_GET_UTS_NAMESPACE: Ioctl<NoArgs> = _IO(PIDFS_IOCTL_MAGIC, 10);
+
+/// Reads information about the process.
+///
+/// [`pidfd_info::mask`] selects the information to return, and is overwritten by the kernel
+/// with the information it actually returned.
+pub const PIDFD_GET_INFO: Ioctl<*mut pidfd_info> = _IOWR(PIDFS_IOCTL_MAGIC, 11);
+
+/// [`pidfd_info::mask`]: the PID fields (`pid`, `tgid`, `ppid`). Always returned.
+pub const PIDFD_INFO_PID: u64 = 1 << 0;
+/// [`pidfd_info::mask`]: the credential fields (`ruid` through `fsgid`). Always returned.
+pub const PIDFD_INFO_CREDS: u64 = 1 << 1;
+/// [`pidfd_info::mask`]: [`pidfd_info::cgroupid`].
+pub const PIDFD_INFO_CGROUPID: u64 = 1 << 2;
+
+/// Information about a process, read with [`PIDFD_GET_INFO`].
+///
+/// This is the original version of the `struct` (`PIDFD_INFO_SIZE_VER0`). Newer kernels accept
+/// it, and will only fill in the fields that it contains.
+///
+/// ```c
+/// struct pidfd_info {
+/// 	__u64 mask;
+/// 	__u64 cgroupid;
+/// 	__u32 pid;
+/// 	__u32 tgid;
+/// 	__u32 ppid;
+/// 	__u32 ruid;
+/// 	__u32 rgid;
+/// 	__u32 euid;
+/// 	__u32 egid;
+/// 	__u32 suid;
+/// 	__u32 sgid;
+/// 	__u32 fsuid;
+/// 	__u32 fsgid;
+/// 	__u32 spare0[1];
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct pidfd_info {
+    pub mask: u64,
+    pub cgroupid: u64,
+    pub pid: u32,
+    pub tgid: u32,
+    pub ppid: u32,
+    pub ruid: u32,
+    pub rgid: u32,
+    pub euid: u32,
+    pub egid: u32,
+    pub suid: u32,
+    pub sgid: u32,
+    pub fsuid: u32,
+    pub fsgid: u32,
+    pub spare0: [u32; 1],
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::{
+        fs, io,
+        os::{
+            fd::{FromRawFd, OwnedFd},
+            unix::fs::MetadataExt,
+        },
+    };
+
+    use super::*;
+
+    fn pidfd_self() -> OwnedFd {
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, libc::getpid(), 0) };
+        assert_ne!(fd, -1, "pidfd_open failed: {}", io::Error::last_os_error());
+        unsafe { OwnedFd::from_raw_fd(fd as _) }
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(size_of::<pidfd_info>(), 64);
+    }
+
+    #[test]
+    fn get_info() {
+        let pidfd = pidfd_self();
+
+        let mut info = pidfd_info {
+            mask: PIDFD_INFO_CREDS,
+            ..Default::default()
+        };
+        match unsafe { PIDFD_GET_INFO.ioctl(&pidfd, &mut info) } {
+            Ok(_) => {}
+            // Requires Linux 6.13.
+            Err(e) if e.raw_os_error() == Some(libc::ENOTTY) => return,
+            Err(e) => panic!("PIDFD_GET_INFO failed: {e}"),
+        }
+        assert_ne!(info.mask & PIDFD_INFO_PID, 0);
+        assert_ne!(info.mask & PIDFD_INFO_CREDS, 0);
+        assert_eq!(info.pid, std::process::id());
+        assert_eq!(info.ppid, unsafe { libc::getppid() } as u32);
+        assert_eq!(info.euid, unsafe { libc::geteuid() });
+    }
+
+    #[test]
+    fn get_namespace() {
+        let pidfd = pidfd_self();
+
+        let netns = match unsafe { PIDFD_GET_NET_NAMESPACE.ioctl_owned_fd(&pidfd) } {
+            Ok(fd) => fd,
+            // Requires Linux 6.11.
+            Err(e) if e.raw_os_error() == Some(libc::ENOTTY) => return,
+            Err(e) => panic!("PIDFD_GET_NET_NAMESPACE failed: {e}"),
+        };
+        let netns = fs::File::from(netns).metadata().unwrap();
+        let expected = fs::metadata("/proc/self/ns/net").unwrap();
+        assert_eq!(netns.ino(), expected.ino());
+        assert_eq!(netns.dev(), expected.dev());
+    }
+}
@@ -1,12 +1,19 @@
 //! Platform details for BSD-derivatives.
 
 const IOCPARM_SHIFT: u32 = 13;
+const IOCPARM_MASK: u32 = (1 << IOCPARM_SHIFT) - 1;
 
 pub(crate) const MAX_ARG_SIZE: usize = (1 << IOCPARM_SHIFT) - 1;
 
+/// Whether `IOC_VOID` `ioctl`s may have the size of an `int` (`_IOWINT`).
+pub(crate) const IOC_VOID_INT: bool = true;
+
 pub(crate) const IOC_VOID: u32 = 0x20000000;
 pub(crate) const IOC_OUT: u32 = 0x40000000;
 pub(crate) const IOC_IN: u32 = 0x80000000;
+const IOC_DIRMASK: u32 = IOC_VOID | IOC_OUT | IOC_IN;
+
+pub(crate) const ENCODING: crate::Encoding = crate::Encoding::Bsd;
 
 pub(crate) use IOC_IN as _IOC_WRITE;
 pub(crate) use IOC_OUT as _IOC_READ;
@@ -14,5 +21,50 @@ pub(crate) use IOC_VOID as _IOC_NONE;
 
 #[expect(non_snake_case)]
 pub(crate) const fn _IOC(dir: u32, group: u32, num: u32, len: u32) -> u32 {
-    dir | len << 16 | group << 8 | num
+    // A larger `len` would bleed into the direction bits.
+    assert!(
+        len <= IOCPARM_MASK,
+        "`ioctl` argument size exceeds `IOCPARM_MASK`"
+    );
+    dir | (len & IOCPARM_MASK) << 16 | (group & 0xff) << 8 | (num & 0xff)
+}
+
+#[expect(non_snake_case)]
+pub(crate) const fn IOCGROUP(x: u32) -> u32 {
+    (x >> 8) & 0xff
+}
+
+pub(crate) use IOCGROUP as _IOC_TYPE;
+
+/// The BSD headers have no macro for this.
+#[expect(non_snake_case)]
+pub(crate) const fn _IOC_NR(x: u32) -> u32 {
+    x & 0xff
+}
+
+#[expect(non_snake_case)]
+pub(crate) const fn IOCPARM_LEN(x: u32) -> u32 {
+    (x >> 16) & IOCPARM_MASK
+}
+
+pub(crate) use IOCPARM_LEN as _IOC_SIZE;
+
+/// The BSD headers have no macro for this.
+#[expect(non_snake_case)]
+pub(crate) const fn _IOC_DIR(x: u32) -> u32 {
+    x & IOC_DIRMASK
+}
+
+/// Returns a pointer to the calling thread's *errno*.
+#[cfg(feature = "std")]
+pub(crate) fn errno_location() -> *mut libc::c_int {
+    #[cfg(any(target_os = "openbsd", target_os = "netbsd"))]
+    unsafe {
+        libc::__errno()
+    }
+
+    #[cfg(not(any(target_os = "openbsd", target_os = "netbsd")))]
+    unsafe {
+        libc::__error()
+    }
 }
@@ -7,14 +7,17 @@
     target_arch = "sparc64",
     target_arch = "powerpc",
     target_arch = "powerpc64",
-    //target_arch = "alpha",
+    target_arch = "alpha",
 ))]
 mod consts {
     pub(crate) const _IOC_SIZEBITS: u32 = 13;
+    pub(crate) const _IOC_DIRBITS: u32 = 3;
 
     pub(crate) const _IOC_NONE: u32 = 1;
     pub(crate) const _IOC_READ: u32 = 2;
     pub(crate) const _IOC_WRITE: u32 = 4;
+
+    pub(crate) const ENCODING: crate::Encoding = crate::Encoding::LinuxAlternate;
 }
 
 #[cfg(not(any(
@@ -24,17 +27,31 @@ mod consts {
     target_arch = "sparc64",
     target_arch = "powerpc",
     target_arch = "powerpc64",
-    //target_arch = "alpha",
+    target_arch = "alpha",
 )))]
 mod consts {
     pub(crate) const _IOC_SIZEBITS: u32 = 14;
+    pub(crate) const _IOC_DIRBITS: u32 = 2;
 
     pub(crate) const _IOC_NONE: u32 = 0;
     pub(crate) const _IOC_READ: u32 = 2;
     pub(crate) const _IOC_WRITE: u32 = 1;
+
+    pub(crate) const ENCODING: crate::Encoding = crate::Encoding::LinuxGeneric;
 }
 
-use consts::_IOC_SIZEBITS;
+use consts::{_IOC_DIRBITS, _IOC_SIZEBITS};
+
+// Alpha has no Rust target yet, so this can't be covered by a test. It uses the same encoding as
+// MIPS, PowerPC, and SPARC (from `arch/alpha/include/uapi/asm/ioctl.h`).
+#[cfg(target_arch = "alpha")]
+const _: () = assert!(
+    _IOC_SIZEBITS == 13
+        && _IOC_DIRBITS == 3
+        && consts::_IOC_NONE == 1
+        && consts::_IOC_READ == 2
+        && consts::_IOC_WRITE == 4
+);
 
 const _IOC_NRBITS: u32 = 8;
 const _IOC_TYPEBITS: u32 = 8;
@@ -44,12 +61,104 @@ const _IOC_TYPESHIFT: u32 = _IOC_NRSHIFT + _IOC_NRBITS;
 const _IOC_SIZESHIFT: u32 = _IOC_TYPESHIFT + _IOC_TYPEBITS;
 const _IOC_DIRSHIFT: u32 = _IOC_SIZESHIFT + _IOC_SIZEBITS;
 
-pub(crate) use consts::{_IOC_NONE, _IOC_READ, _IOC_WRITE};
+const _IOC_NRMASK: u32 = (1 << _IOC_NRBITS) - 1;
+const _IOC_TYPEMASK: u32 = (1 << _IOC_TYPEBITS) - 1;
+const _IOC_SIZEMASK: u32 = (1 << _IOC_SIZEBITS) - 1;
+const _IOC_DIRMASK: u32 = (1 << _IOC_DIRBITS) - 1;
+
+pub(crate) use consts::{_IOC_NONE, _IOC_READ, _IOC_WRITE, ENCODING};
 
 /// The largest argument size that can be portably encoded.
 pub(crate) const MAX_ARG_SIZE: usize = (1 << 13) - 1;
 
+/// Whether `_IOC_NONE` `ioctl`s may have the size of an `int` (like the BSD `_IOWINT`).
+pub(crate) const IOC_VOID_INT: bool = false;
+
 #[expect(non_snake_case)]
 pub(crate) const fn _IOC(dir: u32, ty: u32, nr: u32, size: u32) -> u32 {
     dir << _IOC_DIRSHIFT | ty << _IOC_TYPESHIFT | nr << _IOC_NRSHIFT | size << _IOC_SIZESHIFT
 }
+
+#[expect(non_snake_case)]
+pub(crate) const fn _IOC_TYPE(nr: u32) -> u32 {
+    (nr >> _IOC_TYPESHIFT) & _IOC_TYPEMASK
+}
+
+#[expect(non_snake_case)]
+pub(crate) const fn _IOC_NR(nr: u32) -> u32 {
+    (nr >> _IOC_NRSHIFT) & _IOC_NRMASK
+}
+
+#[expect(non_snake_case)]
+pub(crate) const fn _IOC_DIR(nr: u32) -> u32 {
+    (nr >> _IOC_DIRSHIFT) & _IOC_DIRMASK
+}
+
+#[expect(non_snake_case)]
+pub(crate) const fn _IOC_SIZE(nr: u32) -> u32 {
+    (nr >> _IOC_SIZESHIFT) & _IOC_SIZEMASK
+}
+
+/// Performs the `ioctl` system call directly, without going through the libc wrapper.
+///
+/// Returns the raw result of the system call: a negated *errno* value on failure.
+#[cfg(feature = "raw-syscall")]
+pub(crate) unsafe fn raw_ioctl(fd: i32, request: u32, arg: usize) -> isize {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        let ret: isize;
+        core::arch::asm!(
+            "syscall",
+            inlateout("rax") libc::SYS_ioctl as isize => ret,
+            in("rdi") fd as isize,
+            in("rsi") request as usize,
+            in("rdx") arg,
+            lateout("rcx") _,
+            lateout("r11") _,
+            options(nostack),
+        );
+        ret
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        let ret: isize;
+        core::arch::asm!(
+            "svc 0",
+            in("x8") libc::SYS_ioctl as isize,
+            inlateout("x0") fd as isize => ret,
+            in("x1") request as usize,
+            in("x2") arg,
+            options(nostack),
+        );
+        ret
+    }
+
+    // Other architectures use `syscall(2)`, which still bypasses the `ioctl` wrapper, but reports
+    // errors via *errno*.
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    unsafe {
+        let ret = libc::syscall(libc::SYS_ioctl, fd, request as libc::c_ulong, arg);
+        if ret == -1 {
+            -(std::io::Error::last_os_error()
+                .raw_os_error()
+                .unwrap_or(libc::EIO) as isize)
+        } else {
+            ret as isize
+        }
+    }
+}
+
+/// Returns a pointer to the calling thread's *errno*.
+#[cfg(feature = "std")]
+pub(crate) fn errno_location() -> *mut libc::c_int {
+    #[cfg(target_os = "android")]
+    unsafe {
+        libc::__errno()
+    }
+
+    #[cfg(not(target_os = "android"))]
+    unsafe {
+        libc::__errno_location()
+    }
+}
@@ -0,0 +1,172 @@
+//! `ioctl`s for the PPP generic driver (`/dev/ppp`).
+//!
+//! A file descriptor for `/dev/ppp` starts out unattached. It can then either create a new PPP
+//! network interface ("unit") with [`PPPIOCNEWUNIT`], attach to an existing unit with
+//! [`PPPIOCATTACH`], or attach to a channel (like a PPPoE session or a serial line) with
+//! [`PPPIOCATTCHAN`].
+//! Creating and attaching to units requires `CAP_NET_ADMIN`.
+//!
+//! From `linux/ppp-ioctl.h`:
+//!
+//! ```c
+//! #define	PPPIOCGFLAGS	_IOR('t', 90, int)	/* get configuration flags */
+//! #define	PPPIOCSFLAGS	_IOW('t', 89, int)	/* set configuration flags */
+//! ...
+//! #define	PPPIOCGUNIT	_IOR('t', 86, int)	/* get ppp unit number */
+//! ...
+//! #define	PPPIOCSMRU	_IOW('t', 82, int)	/* set max receive unit */
+//! ...
+//! #define PPPIOCNEWUNIT	_IOWR('t', 62, int)	/* create new ppp unit */
+//! #define PPPIOCATTACH	_IOW('t', 61, int)	/* attach to ppp unit */
+//! ...
+//! #define PPPIOCCONNECT	_IOW('t', 58, int)	/* connect channel to unit */
+//! #define PPPIOCDISCONN	_IO('t', 57)		/* disconnect channel */
+//! #define PPPIOCATTCHAN	_IOW('t', 56, int)	/* attach to ppp channel */
+//! #define PPPIOCGCHAN	_IOR('t', 55, int)	/* get ppp channel number */
+//! ```
+//!
+//! # Example
+//!
+#![cfg_attr(feature = "std", doc = "```no_run")]
+#![cfg_attr(not(feature = "std"), doc = "```ignore")]
+//! use std::fs::File;
+//! use uoctl::ppp::*;
+//!
+//! let ppp = File::options().read(true).write(true).open("/dev/ppp")?;
+//!
+//! // Let the kernel pick the unit number.
+//! let mut unit = -1;
+//! unsafe { PPPIOCNEWUNIT.ioctl(&ppp, &mut unit)? };
+//! println!("created ppp{unit}");
+//!
+//! let mut flags = 0;
+//! unsafe {
+//!     PPPIOCGFLAGS.ioctl(&ppp, &mut flags)?;
+//!     flags |= SC_LOOP_TRAFFIC;
+//!     PPPIOCSFLAGS.ioctl(&ppp, &flags)?;
+//! }
+//! # std::io::Result::Ok(())
+//! ```
+
+use core::ffi::c_int;
+
+use crate::{_IO, _IOR, _IOW, _IOWR, Ioctl, NoArgs};
+
+/// Reads the flags of the attached unit (`SC_*`).
+pub const PPPIOCGFLAGS: Ioctl<*mut c_int> = _IOR(b't', 90);
+/// Sets the flags of the attached unit (`SC_*`).
+pub const PPPIOCSFLAGS: Ioctl<*const c_int> = _IOW(b't', 89);
+/// Reads the number of the attached unit.
+pub const PPPIOCGUNIT: Ioctl<*mut c_int> = _IOR(b't', 86);
+/// Sets the maximum receive unit of the attached unit.
+pub const PPPIOCSMRU: Ioctl<*const c_int> = _IOW(b't', 82);
+/// Creates a new unit, and attaches the file descriptor to it.
+///
+/// The argument is the requested unit number, or -1 to let the kernel choose one.
+/// The kernel writes the number of the new unit back to the argument.
+pub const PPPIOCNEWUNIT: Ioctl<*mut c_int> = _IOWR(b't', 62);
+/// Attaches the file descriptor to an existing unit.
+pub const PPPIOCATTACH: Ioctl<*const c_int> = _IOW(b't', 61);
+/// Connects the attached channel to a unit.
+pub const PPPIOCCONNECT: Ioctl<*const c_int> = _IOW(b't', 58);
+/// Disconnects the attached channel from its unit.
+pub const PPPIOCDISCONN: Ioctl<NoArgs> = _IO(b't', 57);
+/// Attaches the file descriptor to an existing channel.
+pub const PPPIOCATTCHAN: Ioctl<*const c_int> = _IOW(b't', 56);
+/// Reads the number of a channel.
+///
+/// This is performed on the file descriptor of the channel's underlying transport (like a PPPoE
+/// socket or a serial line), not on `/dev/ppp`.
+pub const PPPIOCGCHAN: Ioctl<*mut c_int> = _IOR(b't', 55);
+
+/// [`PPPIOCSFLAGS`]: protocol field compression.
+pub const SC_COMP_PROT: c_int = 0x00000001;
+/// [`PPPIOCSFLAGS`]: header (address/control field) compression.
+pub const SC_COMP_AC: c_int = 0x00000002;
+/// [`PPPIOCSFLAGS`]: TCP (Van Jacobson) header compression.
+pub const SC_COMP_TCP: c_int = 0x00000004;
+/// [`PPPIOCSFLAGS`]: don't compress the connection ID in VJ-compressed headers.
+pub const SC_NO_TCP_CCID: c_int = 0x00000008;
+/// [`PPPIOCSFLAGS`]: reject address/control field compression.
+pub const SC_REJ_COMP_AC: c_int = 0x00000010;
+/// [`PPPIOCSFLAGS`]: reject TCP header compression.
+pub const SC_REJ_COMP_TCP: c_int = 0x00000020;
+/// [`PPPIOCSFLAGS`]: the Compression Control Protocol is open.
+pub const SC_CCP_OPEN: c_int = 0x00000040;
+/// [`PPPIOCSFLAGS`]: the Compression Control Protocol is up.
+pub const SC_CCP_UP: c_int = 0x00000080;
+/// [`PPPIOCSFLAGS`]: pass all traffic to `pppd` instead of the network stack.
+pub const SC_LOOP_TRAFFIC: c_int = 0x00000200;
+/// [`PPPIOCSFLAGS`]: enable multilink operation.
+pub const SC_MULTILINK: c_int = 0x00000400;
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::fs::File;
+
+    use crate::{Encoding, encoding, header::assert_golden};
+
+    use super::*;
+
+    #[test]
+    fn codes() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(PPPIOCGFLAGS.request(), 0x8004745a);
+            assert_eq!(PPPIOCNEWUNIT.request(), 0xc004743e);
+            assert_eq!(PPPIOCDISCONN.request(), 0x7439);
+        }
+    }
+
+    #[test]
+    fn golden() {
+        if encoding() != Encoding::LinuxGeneric {
+            return;
+        }
+        assert_golden(include_str!("../fixtures/ppp.txt"), |name| {
+            Some(match name {
+                "PPPIOCGFLAGS" => PPPIOCGFLAGS.request(),
+                "PPPIOCSFLAGS" => PPPIOCSFLAGS.request(),
+                "PPPIOCGUNIT" => PPPIOCGUNIT.request(),
+                "PPPIOCSMRU" => PPPIOCSMRU.request(),
+                "PPPIOCNEWUNIT" => PPPIOCNEWUNIT.request(),
+                "PPPIOCATTACH" => PPPIOCATTACH.request(),
+                "PPPIOCCONNECT" => PPPIOCCONNECT.request(),
+                "PPPIOCDISCONN" => PPPIOCDISCONN.request(),
+                "PPPIOCATTCHAN" => PPPIOCATTCHAN.request(),
+                "PPPIOCGCHAN" => PPPIOCGCHAN.request(),
+                _ => return None,
+            })
+        });
+    }
+
+    #[test]
+    fn new_unit() {
+        // Requires the `ppp_generic` module and `CAP_NET_ADMIN`.
+        let Ok(ppp) = File::options().read(true).write(true).open("/dev/ppp") else {
+            return;
+        };
+
+        let mut unit = -1;
+        match unsafe { PPPIOCNEWUNIT.ioctl(&ppp, &mut unit) } {
+            Ok(_) => {}
+            Err(e) if e.raw_os_error() == Some(libc::EPERM) => return,
+            Err(e) => panic!("PPPIOCNEWUNIT failed: {e}"),
+        }
+        assert_ne!(unit, -1);
+
+        let mut attached = -1;
+        unsafe { PPPIOCGUNIT.ioctl(&ppp, &mut attached).unwrap() };
+        assert_eq!(attached, unit);
+
+        let mut flags = 0;
+        unsafe {
+            PPPIOCGFLAGS.ioctl(&ppp, &mut flags).unwrap();
+            PPPIOCSFLAGS
+                .ioctl(&ppp, &(flags | SC_LOOP_TRAFFIC))
+                .unwrap();
+            PPPIOCGFLAGS.ioctl(&ppp, &mut flags).unwrap();
+        }
+        assert_ne!(flags & SC_LOOP_TRAFFIC, 0);
+    }
+}
@@ -0,0 +1,269 @@
+//! Kernel resources that are released by an `ioctl`.
+
+use std::{
+    ffi::c_int,
+    fmt, io,
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    os::fd::{AsRawFd, RawFd},
+    ptr,
+};
+
+use crate::Ioctl;
+
+/// A kernel resource that is allocated by one `ioctl`, and freed by another.
+///
+/// Some drivers hand out resources (like DRM GEM handles) that are only freed automatically when
+/// the file descriptor is closed.
+/// Long-running programs that keep the file descriptor open have to free them explicitly with a
+/// dedicated `ioctl`, or they leak kernel memory.
+///
+/// [`IoctlResource`] stores the argument of that `ioctl` (typically a `struct` containing the
+/// resource's handle), and performs the `ioctl` when it is dropped.
+/// It borrows the file descriptor, so it cannot outlive it.
+///
+/// # Example
+///
+/// Allocating a DRM dumb buffer, which has to be freed with `DRM_IOCTL_GEM_CLOSE`.
+///
+/// From `drm/drm.h` and `drm/drm_mode.h`:
+///
+/// ```c
+/// struct drm_gem_close {
+/// 	/** Handle of the object to be closed. */
+/// 	__u32 handle;
+/// 	__u32 pad;
+/// };
+/// // ...
+/// struct drm_mode_create_dumb {
+/// 	__u32 height;
+/// 	__u32 width;
+/// 	__u32 bpp;
+/// 	__u32 flags;
+/// 	/* handle, pitch, size will be returned */
+/// 	__u32 handle;
+/// 	__u32 pitch;
+/// 	__u64 size;
+/// };
+/// // ...
+/// #define DRM_IOCTL_GEM_CLOSE		DRM_IOW (0x09, struct drm_gem_close)
+/// // ...
+/// #define DRM_IOCTL_MODE_CREATE_DUMB DRM_IOWR(0xB2, struct drm_mode_create_dumb)
+/// ```
+///
+/// ```no_run
+/// use std::fs::File;
+/// use uoctl::*;
+///
+/// #[repr(C)]
+/// struct GemClose {
+///     handle: u32,
+///     pad: u32,
+/// }
+///
+/// #[repr(C)]
+/// #[derive(Default)]
+/// struct CreateDumb {
+///     height: u32,
+///     width: u32,
+///     bpp: u32,
+///     flags: u32,
+///     handle: u32,
+///     pitch: u32,
+///     size: u64,
+/// }
+///
+/// const DRM_IOCTL_GEM_CLOSE: Ioctl<*const GemClose> = _IOW(b'd', 0x09);
+/// const DRM_IOCTL_MODE_CREATE_DUMB: Ioctl<*mut CreateDumb> = _IOWR(b'd', 0xB2);
+///
+/// let card = File::options().read(true).write(true).open("/dev/dri/card0")?;
+///
+/// let mut create = CreateDumb { width: 640, height: 480, bpp: 32, ..Default::default() };
+/// let buffer = unsafe {
+///     IoctlResource::acquire(
+///         &card,
+///         DRM_IOCTL_MODE_CREATE_DUMB,
+///         DRM_IOCTL_GEM_CLOSE,
+///         &mut create,
+///         |create| GemClose { handle: create.handle, pad: 0 },
+///     )?
+/// };
+/// println!("allocated buffer {} ({} bytes)", buffer.handle().handle, create.size);
+///
+/// // Performs `DRM_IOCTL_GEM_CLOSE`.
+/// drop(buffer);
+/// # std::io::Result::Ok(())
+/// ```
+pub struct IoctlResource<'fd, H> {
+    fd: RawFd,
+    free: Ioctl<*const H>,
+    handle: H,
+    _p: PhantomData<&'fd ()>,
+}
+
+impl<'fd, H> IoctlResource<'fd, H> {
+    /// Allocates a resource by performing `alloc` with `arg`, and returns a guard that frees it
+    /// with `free` when dropped.
+    ///
+    /// After `alloc` succeeds, `handle` is called with the argument to create the argument of
+    /// `free`, which is stored in the [`IoctlResource`].
+    ///
+    /// # Errors
+    ///
+    /// Returns any error reported by `alloc`. In that case, `handle` isn't called, and `free` is
+    /// not performed.
+    ///
+    /// # Safety
+    ///
+    /// `fd` has to refer to a device that supports both `ioctl`s (see [`Ioctl::ioctl`]).
+    /// Additionally, it has to be safe to perform `free` with the argument returned by `handle`
+    /// whenever the [`IoctlResource`] is dropped.
+    pub unsafe fn acquire<A>(
+        fd: &'fd impl AsRawFd,
+        alloc: Ioctl<*mut A>,
+        free: Ioctl<*const H>,
+        arg: &mut A,
+        handle: impl FnOnce(&A) -> H,
+    ) -> io::Result<Self> {
+        unsafe { alloc.ioctl(fd, arg)? };
+        Ok(Self {
+            fd: fd.as_raw_fd(),
+            free,
+            handle: handle(arg),
+            _p: PhantomData,
+        })
+    }
+
+    /// Returns the argument that will be passed to the `free` `ioctl`.
+    #[inline]
+    pub fn handle(&self) -> &H {
+        &self.handle
+    }
+
+    /// Frees the resource, and returns the result of the `free` `ioctl`.
+    ///
+    /// Dropping the [`IoctlResource`] also frees the resource, but ignores any errors.
+    pub fn release(self) -> io::Result<c_int> {
+        let this = ManuallyDrop::new(self);
+        let res = unsafe { this.free.ioctl(&this.fd, &this.handle) };
+        drop(unsafe { ptr::read(&this.handle) });
+        res
+    }
+
+    /// Returns the argument of the `free` `ioctl` without performing it, leaking the resource.
+    ///
+    /// The resource will still be freed when the file descriptor is closed.
+    pub fn into_handle(self) -> H {
+        let this = ManuallyDrop::new(self);
+        unsafe { ptr::read(&this.handle) }
+    }
+}
+
+impl<H> Drop for IoctlResource<'_, H> {
+    fn drop(&mut self) {
+        // There is nothing useful to do with an error here; `release` can be used to observe it.
+        unsafe {
+            self.free.ioctl(&self.fd, &self.handle).ok();
+        }
+    }
+}
+
+impl<H: fmt::Debug> fmt::Debug for IoctlResource<'_, H> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IoctlResource")
+            .field("fd", &self.fd)
+            .field("free", &self.free)
+            .field("handle", &self.handle)
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::{_IOW, _IOWR, MockFd};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    #[repr(C)]
+    struct Alloc {
+        size: u32,
+        handle: u32,
+    }
+
+    const ALLOC: Ioctl<*mut Alloc> = _IOWR(b'x', 1);
+    const FREE: Ioctl<*const u32> = _IOW(b'x', 2);
+
+    /// Returns a [`MockFd`] that hands out handles 1, 2, 3..., and reports freed handles.
+    fn allocator() -> (MockFd, mpsc::Receiver<u32>) {
+        let (tx, rx) = mpsc::channel();
+        let mut next = 1;
+        let fd = MockFd::new(move |request, arg| match request {
+            _ if request == ALLOC.request() => {
+                let alloc = unsafe { &mut *(arg as *mut Alloc) };
+                if alloc.size == 0 {
+                    return Err(io::Error::from_raw_os_error(libc::EINVAL));
+                }
+                alloc.handle = next;
+                next += 1;
+                Ok(0)
+            }
+            _ if request == FREE.request() => {
+                let handle = unsafe { *(arg as *const u32) };
+                tx.send(handle).unwrap();
+                if handle == 1 {
+                    Ok(0)
+                } else {
+                    Err(io::Error::from_raw_os_error(libc::ENOENT))
+                }
+            }
+            _ => Err(io::Error::from_raw_os_error(libc::ENOTTY)),
+        })
+        .unwrap();
+        (fd, rx)
+    }
+
+    fn acquire<'fd>(fd: &'fd MockFd, size: u32) -> io::Result<IoctlResource<'fd, u32>> {
+        let mut alloc = Alloc { size, handle: 0 };
+        unsafe { IoctlResource::acquire(fd, ALLOC, FREE, &mut alloc, |alloc| alloc.handle) }
+    }
+
+    #[test]
+    fn freed_on_drop() {
+        let (fd, freed) = allocator();
+        let res = acquire(&fd, 16).unwrap();
+        assert_eq!(*res.handle(), 1);
+        assert!(freed.try_recv().is_err());
+        drop(res);
+        assert_eq!(freed.try_iter().collect::<Vec<_>>(), [1]);
+    }
+
+    #[test]
+    fn release() {
+        let (fd, freed) = allocator();
+        let first = acquire(&fd, 16).unwrap();
+        let second = acquire(&fd, 16).unwrap();
+        assert_eq!(first.release().unwrap(), 0);
+        let err = second.release().unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+        assert_eq!(freed.try_iter().collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn failed_alloc() {
+        let (fd, freed) = allocator();
+        let err = acquire(&fd, 0).err().unwrap();
+        assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+        assert!(freed.try_recv().is_err());
+    }
+
+    #[test]
+    fn into_handle() {
+        let (fd, freed) = allocator();
+        let handle = acquire(&fd, 16).unwrap().into_handle();
+        assert_eq!(handle, 1);
+        assert!(freed.try_recv().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+//! `ioctl`s for the seccomp user-space notification API.
+//!
+//! When a seccomp filter is installed with `SECCOMP_FILTER_FLAG_NEW_LISTENER`, the kernel returns
+//! a "listener" file descriptor.
+//! Whenever the filter returns `SECCOMP_RET_USER_NOTIF` for a system call, the calling thread is
+//! suspended, and a notification describing the system call can be received from the listener with
+//! [`SECCOMP_IOCTL_NOTIF_RECV`].
+//! The supervisor then responds with [`SECCOMP_IOCTL_NOTIF_SEND`], which resumes the thread.
+//!
+//! From `linux/seccomp.h`:
+//!
+//! ```c
+//! #define SECCOMP_IOC_MAGIC		'!'
+//! #define SECCOMP_IO(nr)			_IO(SECCOMP_IOC_MAGIC, nr)
+//! #define SECCOMP_IOR(nr, type)		_IOR(SECCOMP_IOC_MAGIC, nr, type)
+//! #define SECCOMP_IOW(nr, type)		_IOW(SECCOMP_IOC_MAGIC, nr, type)
+//! #define SECCOMP_IOWR(nr, type)		_IOWR(SECCOMP_IOC_MAGIC, nr, type)
+//!
+//! /* Flags for seccomp notification fd ioctl. */
+//! #define SECCOMP_IOCTL_NOTIF_RECV	SECCOMP_IOWR(0, struct seccomp_notif)
+//! #define SECCOMP_IOCTL_NOTIF_SEND	SECCOMP_IOWR(1,	\
+//! 						struct seccomp_notif_resp)
+//! #define SECCOMP_IOCTL_NOTIF_ID_VALID	SECCOMP_IOW(2, __u64)
+//! /* On success, the return value is the remote process's added fd number */
+//! #define SECCOMP_IOCTL_NOTIF_ADDFD	SECCOMP_IOW(3,	\
+//! 						struct seccomp_notif_addfd)
+//!
+//! #define SECCOMP_IOCTL_NOTIF_SET_FLAGS	SECCOMP_IOW(4, __u64)
+//! ```
+
+#![allow(non_camel_case_types)]
+
+use crate::{_IOW, _IOWR, Ioctl};
+
+/// The `ioctl` group used by the seccomp notification API.
+pub const SECCOMP_IOC_MAGIC: u8 = b'!';
+
+/// Receives a notification from the listener.
+///
+/// The [`seccomp_notif`] passed to the kernel has to be zeroed, or the `ioctl` will fail with
+/// `EINVAL`.
+/// Blocks until a notification is available, unless the listener is in non-blocking mode.
+pub const SECCOMP_IOCTL_NOTIF_RECV: Ioctl<*mut seccomp_notif> = _IOWR(SECCOMP_IOC_MAGIC, 0);
+
+/// Responds to a notification, resuming the suspended thread.
+///
+/// The kernel only reads the [`seccomp_notif_resp`], despite the `ioctl` being declared with
+/// [`_IOWR`].
+pub const SECCOMP_IOCTL_NOTIF_SEND: Ioctl<*mut seccomp_notif_resp> = _IOWR(SECCOMP_IOC_MAGIC, 1);
+
+/// Checks whether a notification ID is still valid.
+///
+/// Fails with `ENOENT` if the thread that caused the notification has been killed, or the system
+/// call was interrupted.
+pub const SECCOMP_IOCTL_NOTIF_ID_VALID: Ioctl<*const u64> = _IOW(SECCOMP_IOC_MAGIC, 2);
+
+/// Installs a file descriptor into the file descriptor table of the notifying process.
+///
+/// On success, returns the number of the new file descriptor *in the target process*.
+pub const SECCOMP_IOCTL_NOTIF_ADDFD: Ioctl<*const seccomp_notif_addfd> = _IOW(SECCOMP_IOC_MAGIC, 3);
+
+/// Sets flags on the listener file descriptor (`SECCOMP_USER_NOTIF_FD_*`).
+pub const SECCOMP_IOCTL_NOTIF_SET_FLAGS: Ioctl<*const u64> = _IOW(SECCOMP_IOC_MAGIC, 4);
+
+/// [`seccomp_notif_resp::flags`]: let the system call proceed as if no filter was installed.
+pub const SECCOMP_USER_NOTIF_FLAG_CONTINUE: u32 = 1 << 0;
+
+/// [`seccomp_notif_addfd::flags`]: install the file descriptor as
+/// [`seccomp_notif_addfd::newfd`] instead of the lowest available number.
+pub const SECCOMP_ADDFD_FLAG_SETFD: u32 = 1 << 0;
+/// [`seccomp_notif_addfd::flags`]: atomically add the file descriptor and respond to the
+/// notification, returning the new file descriptor number from the system call.
+pub const SECCOMP_ADDFD_FLAG_SEND: u32 = 1 << 1;
+
+/// [`SECCOMP_IOCTL_NOTIF_SET_FLAGS`]: hint that the supervisor and target run synchronously.
+pub const SECCOMP_USER_NOTIF_FD_SYNC_WAKE_UP: u64 = 1 << 0;
+
+/// The system call that caused a notification.
+///
+/// ```c
+/// struct seccomp_data {
+/// 	int nr;
+/// 	__u32 arch;
+/// 	__u64 instruction_pointer;
+/// 	__u64 args[6];
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct seccomp_data {
+    pub nr: i32,
+    pub arch: u32,
+    pub instruction_pointer: u64,
+    pub args: [u64; 6],
+}
+
+/// A notification received with [`SECCOMP_IOCTL_NOTIF_RECV`].
+///
+/// ```c
+/// struct seccomp_notif {
+/// 	__u64 id;
+/// 	__u32 pid;
+/// 	__u32 flags;
+/// 	struct seccomp_data data;
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct seccomp_notif {
+    pub id: u64,
+    pub pid: u32,
+    pub flags: u32,
+    pub data: seccomp_data,
+}
+
+/// A response sent with [`SECCOMP_IOCTL_NOTIF_SEND`].
+///
+/// ```c
+/// struct seccomp_notif_resp {
+/// 	__u64 id;
+/// 	__s64 val;
+/// 	__s32 error;
+/// 	__u32 flags;
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct seccomp_notif_resp {
+    pub id: u64,
+    pub val: i64,
+    pub error: i32,
+    pub flags: u32,
+}
+
+/// Arguments of [`SECCOMP_IOCTL_NOTIF_ADDFD`].
+///
+/// ```c
+/// struct seccomp_notif_addfd {
+/// 	__u64 id;
+/// 	__u32 flags;
+/// 	__u32 srcfd;
+/// 	__u32 newfd;
+/// 	__u32 newfd_flags;
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C)]
+pub struct seccomp_notif_addfd {
+    pub id: u64,
+    pub flags: u32,
+    pub srcfd: u32,
+    pub newfd: u32,
+    pub newfd_flags: u32,
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::{
+        fs::File,
+        io,
+        os::fd::{AsRawFd, FromRawFd, OwnedFd},
+        sync::mpsc,
+        thread,
+    };
+
+    use super::*;
+
+    /// Installs a filter that sends a notification for every `getppid` call made by the current
+    /// thread, and returns the listener.
+    fn install_getppid_listener() -> io::Result<OwnedFd> {
+        const BPF_K: u32 = 0;
+
+        let mut filter = [
+            // Load the syscall number (`seccomp_data::nr`).
+            libc::sock_filter {
+                code: (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16,
+                jt: 0,
+                jf: 0,
+                k: 0,
+            },
+            // Trap `getppid`, allow everything else.
+            libc::sock_filter {
+                code: (libc::BPF_JMP | libc::BPF_JEQ | BPF_K) as u16,
+                jt: 0,
+                jf: 1,
+                k: libc::SYS_getppid as u32,
+            },
+            libc::sock_filter {
+                code: (libc::BPF_RET | BPF_K) as u16,
+                jt: 0,
+                jf: 0,
+                k: libc::SECCOMP_RET_USER_NOTIF,
+            },
+            libc::sock_filter {
+                code: (libc::BPF_RET | BPF_K) as u16,
+                jt: 0,
+                jf: 0,
+                k: libc::SECCOMP_RET_ALLOW,
+            },
+        ];
+        let prog = libc::sock_fprog {
+            len: filter.len() as u16,
+            filter: filter.as_mut_ptr(),
+        };
+
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            let fd = libc::syscall(
+                libc::SYS_seccomp,
+                libc::SECCOMP_SET_MODE_FILTER,
+                libc::SECCOMP_FILTER_FLAG_NEW_LISTENER,
+                &prog,
+            );
+            if fd == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(OwnedFd::from_raw_fd(fd as _))
+        }
+    }
+
+    #[test]
+    fn notification_round_trip() {
+        let (sender, receiver) = mpsc::channel();
+        let target = thread::spawn(move || {
+            let listener = install_getppid_listener().unwrap();
+            sender.send(listener).unwrap();
+            unsafe { libc::getppid() }
+        });
+        let listener = receiver.recv().unwrap();
+
+        unsafe {
+            let mut notif = seccomp_notif::default();
+            SECCOMP_IOCTL_NOTIF_RECV
+                .ioctl(&listener, &mut notif)
+                .unwrap();
+            assert_eq!(libc::c_long::from(notif.data.nr), libc::SYS_getppid);
+
+            SECCOMP_IOCTL_NOTIF_ID_VALID
+                .ioctl(&listener, &notif.id)
+                .unwrap();
+
+            // Give the target a file descriptor, and return its number from `getppid`.
+            let file = File::open("/dev/null").unwrap();
+            let addfd = seccomp_notif_addfd {
+                id: notif.id,
+                srcfd: file.as_raw_fd() as u32,
+                ..Default::default()
+            };
+            let newfd = SECCOMP_IOCTL_NOTIF_ADDFD.ioctl(&listener, &addfd).unwrap();
+            // The target shares our file descriptor table.
+            let newfd = OwnedFd::from_raw_fd(newfd);
+
+            let mut resp = seccomp_notif_resp {
+                id: notif.id,
+                val: newfd.as_raw_fd().into(),
+                ..Default::default()
+            };
+            SECCOMP_IOCTL_NOTIF_SEND
+                .ioctl(&listener, &mut resp)
+                .unwrap();
+
+            assert_eq!(target.join().unwrap(), newfd.as_raw_fd());
+
+            // The notification has been answered, so its ID is no longer valid.
+            let err = SECCOMP_IOCTL_NOTIF_ID_VALID
+                .ioctl(&listener, &notif.id)
+                .unwrap_err();
+            assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+        }
+    }
+}
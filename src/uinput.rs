@@ -0,0 +1,306 @@
+//! `ioctl`s for creating virtual input devices with uinput (`/dev/uinput`).
+//!
+//! A virtual device is configured by enabling the event types and codes it can emit (with
+//! [`UI_SET_EVBIT`], [`UI_SET_KEYBIT`], and friends), describing it with [`UI_DEV_SETUP`], and
+//! finally creating it with [`UI_DEV_CREATE`].
+//! Once created, events are emitted by `write(2)`ing [`libc::input_event`]s to the file
+//! descriptor; this is not done via `ioctl`s.
+//!
+//! [`VirtualDeviceBuilder`] performs this sequence, and [`VirtualDevice::emit`] writes events.
+//!
+//! From `linux/uinput.h`:
+//!
+//! ```c
+//! #define UINPUT_IOCTL_BASE	'U'
+//! #define UI_DEV_CREATE		_IO(UINPUT_IOCTL_BASE, 1)
+//! #define UI_DEV_DESTROY		_IO(UINPUT_IOCTL_BASE, 2)
+//! ...
+//! #define UI_DEV_SETUP _IOW(UINPUT_IOCTL_BASE, 3, struct uinput_setup)
+//! ...
+//! #define UI_ABS_SETUP _IOW(UINPUT_IOCTL_BASE, 4, struct uinput_abs_setup)
+//!
+//! #define UI_SET_EVBIT		_IOW(UINPUT_IOCTL_BASE, 100, int)
+//! #define UI_SET_KEYBIT		_IOW(UINPUT_IOCTL_BASE, 101, int)
+//! #define UI_SET_RELBIT		_IOW(UINPUT_IOCTL_BASE, 102, int)
+//! #define UI_SET_ABSBIT		_IOW(UINPUT_IOCTL_BASE, 103, int)
+//! ```
+//!
+//! # Example
+//!
+//! Creating a virtual keyboard and typing the letter "a".
+//!
+//! From `linux/input-event-codes.h`:
+//!
+//! ```c
+//! #define KEY_A			30
+//! ```
+//!
+//! ```no_run
+//! use uoctl::uinput::*;
+//!
+//! const KEY_A: u16 = 30;
+//!
+//! let mut keyboard = VirtualDeviceBuilder::new()?
+//!     .key(KEY_A)?
+//!     .create("uoctl keyboard", BUS_VIRTUAL, 0x1234, 0x5678)?;
+//!
+//! keyboard.emit(EV_KEY, KEY_A, 1)?;
+//! keyboard.emit(EV_SYN, SYN_REPORT, 0)?;
+//! keyboard.emit(EV_KEY, KEY_A, 0)?;
+//! keyboard.emit(EV_SYN, SYN_REPORT, 0)?;
+//! # std::io::Result::Ok(())
+//! ```
+
+use std::{
+    ffi::{c_char, c_int},
+    fmt,
+    fs::File,
+    io::{self, Write},
+    mem,
+    os::{
+        fd::{AsFd, AsRawFd, BorrowedFd, RawFd},
+        unix::fs::OpenOptionsExt,
+    },
+    slice,
+};
+
+use libc::{input_absinfo, input_event, uinput_abs_setup, uinput_setup};
+
+use crate::{_IO, _IOW, Ioctl, NoArgs};
+
+/// The `ioctl` group used by uinput.
+pub const UINPUT_IOCTL_BASE: u8 = b'U';
+
+/// Creates the device, after it has been configured.
+pub const UI_DEV_CREATE: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 1);
+/// Destroys the device.
+///
+/// Closing the file descriptor also destroys the device.
+pub const UI_DEV_DESTROY: Ioctl<NoArgs> = _IO(UINPUT_IOCTL_BASE, 2);
+/// Sets the name and ID of the device, before it is created.
+pub const UI_DEV_SETUP: Ioctl<*const uinput_setup> = _IOW(UINPUT_IOCTL_BASE, 3);
+/// Configures an absolute axis of the device, before it is created.
+pub const UI_ABS_SETUP: Ioctl<*const uinput_abs_setup> = _IOW(UINPUT_IOCTL_BASE, 4);
+
+/// Enables an event type (`EV_*`).
+pub const UI_SET_EVBIT: Ioctl<c_int> = _IOW(UINPUT_IOCTL_BASE, 100).with_direct_arg();
+/// Enables a key or button code (`KEY_*` or `BTN_*`).
+pub const UI_SET_KEYBIT: Ioctl<c_int> = _IOW(UINPUT_IOCTL_BASE, 101).with_direct_arg();
+/// Enables a relative axis (`REL_*`).
+pub const UI_SET_RELBIT: Ioctl<c_int> = _IOW(UINPUT_IOCTL_BASE, 102).with_direct_arg();
+/// Enables an absolute axis (`ABS_*`).
+pub const UI_SET_ABSBIT: Ioctl<c_int> = _IOW(UINPUT_IOCTL_BASE, 103).with_direct_arg();
+
+/// Event type: synchronization events, which mark the end of a group of events.
+pub const EV_SYN: u16 = 0x00;
+/// Event type: key and button state changes.
+pub const EV_KEY: u16 = 0x01;
+/// Event type: relative axis movement.
+pub const EV_REL: u16 = 0x02;
+/// Event type: absolute axis changes.
+pub const EV_ABS: u16 = 0x03;
+
+/// [`EV_SYN`]: the events since the last report form one atomic state change.
+pub const SYN_REPORT: u16 = 0;
+
+/// [`VirtualDeviceBuilder::create`]: the bus type of virtual devices.
+pub const BUS_VIRTUAL: u16 = 0x06;
+/// [`VirtualDeviceBuilder::create`]: the bus type of USB devices.
+pub const BUS_USB: u16 = 0x03;
+
+/// Configures and creates a [`VirtualDevice`].
+///
+/// Every method performs the corresponding `ioctl` on `/dev/uinput` immediately, and returns any
+/// error reported by the kernel.
+#[derive(Debug)]
+pub struct VirtualDeviceBuilder {
+    file: File,
+}
+
+impl VirtualDeviceBuilder {
+    /// Opens `/dev/uinput` to configure a new device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/dev/uinput` can't be opened. Opening it typically requires root
+    /// privileges or membership in a dedicated group, and the `uinput` kernel module has to be
+    /// loaded.
+    pub fn new() -> io::Result<Self> {
+        let file = File::options()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open("/dev/uinput")?;
+        Ok(Self { file })
+    }
+
+    /// Enables the event type `ev` (`EV_*`).
+    ///
+    /// The other methods of [`VirtualDeviceBuilder`] enable the event type they need
+    /// automatically.
+    pub fn event_type(self, ev: u16) -> io::Result<Self> {
+        unsafe { UI_SET_EVBIT.ioctl(&self.file, ev.into())? };
+        Ok(self)
+    }
+
+    /// Enables the key or button `code` (`KEY_*` or `BTN_*`).
+    pub fn key(self, code: u16) -> io::Result<Self> {
+        let this = self.event_type(EV_KEY)?;
+        unsafe { UI_SET_KEYBIT.ioctl(&this.file, code.into())? };
+        Ok(this)
+    }
+
+    /// Enables the relative axis `code` (`REL_*`).
+    pub fn rel(self, code: u16) -> io::Result<Self> {
+        let this = self.event_type(EV_REL)?;
+        unsafe { UI_SET_RELBIT.ioctl(&this.file, code.into())? };
+        Ok(this)
+    }
+
+    /// Enables the absolute axis `code` (`ABS_*`), with the range and resolution in `info`.
+    pub fn abs(self, code: u16, info: input_absinfo) -> io::Result<Self> {
+        let this = self.event_type(EV_ABS)?;
+        let setup = uinput_abs_setup {
+            code,
+            absinfo: info,
+        };
+        unsafe {
+            UI_SET_ABSBIT.ioctl(&this.file, code.into())?;
+            UI_ABS_SETUP.write(&this.file, &setup)?;
+        }
+        Ok(this)
+    }
+
+    /// Creates the device with the given name and ID.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` is empty, if it doesn't fit in the kernel's 80-byte name buffer
+    /// (including the NUL terminator), or if it contains a NUL byte.
+    /// Also returns any error reported by the kernel.
+    pub fn create(
+        self,
+        name: &str,
+        bustype: u16,
+        vendor: u16,
+        product: u16,
+    ) -> io::Result<VirtualDevice> {
+        let mut setup: uinput_setup = unsafe { mem::zeroed() };
+        if name.is_empty() || name.len() >= setup.name.len() || name.contains('\0') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid uinput device name {name:?}"),
+            ));
+        }
+        for (dest, &b) in setup.name.iter_mut().zip(name.as_bytes()) {
+            *dest = b as c_char;
+        }
+        setup.id.bustype = bustype;
+        setup.id.vendor = vendor;
+        setup.id.product = product;
+
+        unsafe {
+            UI_DEV_SETUP.write(&self.file, &setup)?;
+            UI_DEV_CREATE.ioctl(&self.file)?;
+        }
+        Ok(VirtualDevice { file: self.file })
+    }
+}
+
+/// A virtual input device created by a [`VirtualDeviceBuilder`].
+///
+/// The device is destroyed when the [`VirtualDevice`] is dropped.
+pub struct VirtualDevice {
+    file: File,
+}
+
+impl VirtualDevice {
+    /// Emits a single event.
+    ///
+    /// Applications only see the events once a [`SYN_REPORT`] event is emitted, which groups all
+    /// events since the previous one into a single state change.
+    /// The kernel fills in the timestamp of the event.
+    ///
+    /// This `write(2)`s an [`input_event`] to the file descriptor, so it can also be done manually
+    /// via [`AsRawFd`] or [`AsFd`].
+    pub fn emit(&mut self, ty: u16, code: u16, value: i32) -> io::Result<()> {
+        let mut event: input_event = unsafe { mem::zeroed() };
+        event.type_ = ty;
+        event.code = code;
+        event.value = value;
+
+        let bytes = unsafe {
+            slice::from_raw_parts((&raw const event).cast::<u8>(), size_of::<input_event>())
+        };
+        self.file.write_all(bytes)
+    }
+}
+
+impl Drop for VirtualDevice {
+    fn drop(&mut self) {
+        // Closing the file descriptor destroys the device too, so errors can be ignored.
+        unsafe {
+            UI_DEV_DESTROY.ioctl(&self.file).ok();
+        }
+    }
+}
+
+impl AsRawFd for VirtualDevice {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl AsFd for VirtualDevice {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl fmt::Debug for VirtualDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("VirtualDevice")
+            .field("file", &self.file)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: u16 = 30;
+
+    #[test]
+    fn codes() {
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(UI_DEV_CREATE.request(), 0x5501);
+            assert_eq!(UI_DEV_SETUP.request(), 0x405c5503);
+            assert_eq!(UI_ABS_SETUP.request(), 0x401c5504);
+            assert_eq!(UI_SET_EVBIT.request(), 0x40045564);
+            assert_eq!(UI_SET_KEYBIT.request(), 0x40045565);
+        }
+    }
+
+    #[test]
+    fn virtual_keyboard() {
+        // Requires the `uinput` module, and permission to open `/dev/uinput`.
+        let Ok(builder) = VirtualDeviceBuilder::new() else {
+            return;
+        };
+
+        let err = builder.create("", BUS_VIRTUAL, 0, 0).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut keyboard = VirtualDeviceBuilder::new()
+            .unwrap()
+            .key(KEY_A)
+            .unwrap()
+            .create("uoctl test keyboard", BUS_VIRTUAL, 0x1234, 0x5678)
+            .unwrap();
+        keyboard.emit(EV_KEY, KEY_A, 1).unwrap();
+        keyboard.emit(EV_SYN, SYN_REPORT, 0).unwrap();
+        keyboard.emit(EV_KEY, KEY_A, 0).unwrap();
+        keyboard.emit(EV_SYN, SYN_REPORT, 0).unwrap();
+    }
+}